@@ -1,5 +1,6 @@
 use menugen::{menu, menuitem};
 
+#[allow(dead_code)]
 #[derive(Clone, Default)]
 struct Context {
   foo: bool
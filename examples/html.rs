@@ -6,10 +6,14 @@ struct Context {
   enabled: bool
 }
 
-fn build_strvec(it: reciter::MenuIter<Context>) -> Vec<String>
-where
-  Context: Clone + Default
-{
+fn description(mi: &menuitem::MenuItem<Context>) -> String {
+  match mi.presentation().description {
+    Some(ref s) => format!("<p>{}</p>", s),
+    None => String::new()
+  }
+}
+
+fn build_strvec(it: reciter::MenuIter<Context>) -> Vec<String> {
   let mut out = Vec::new();
   let indent_str = String::from("  ");
   let mut indent = 0;
@@ -19,9 +23,82 @@ where
         out.push(format!("{}<ul>", indent_str.repeat(indent)));
         indent += 1;
       }
-      reciter::Event::MenuItem(mi) => {
+      reciter::Event::MenuItem(mi) => match mi.kind() {
+        menuitem::Kind::Separator => {
+          out.push(format!(
+            r#"{}<li id="{}" class="separator"></li>"#,
+            indent_str.repeat(indent),
+            mi.id()
+          ));
+        }
+        menuitem::Kind::Header | menuitem::Kind::Group => {
+          out.push(format!(
+            r#"{}<li id="{}" class="{}">{}</li>"#,
+            indent_str.repeat(indent),
+            mi.id(),
+            mi.kind(),
+            mi.title()
+          ));
+        }
+        menuitem::Kind::Checkbox | menuitem::Kind::Radio => {
+          out.push(format!(
+            r#"{}<li id="{}"><label><input type="{}"{}{}> {}</label>{}</li>"#,
+            indent_str.repeat(indent),
+            mi.id(),
+            mi.kind(),
+            if mi.checked == Some(true) {
+              " checked"
+            } else {
+              ""
+            },
+            if mi.is_disabled() { " disabled" } else { "" },
+            mi.title(),
+            description(&mi)
+          ));
+        }
+        _ => {
+          let pres = mi.presentation();
+          let tooltip = match pres.tooltip {
+            Some(ref s) => format!(r#" title="{}""#, s),
+            None => String::new()
+          };
+          let icon = match pres.icon {
+            Some(ref s) => format!(r#"<i class="icon-{}"></i> "#, s),
+            None => String::new()
+          };
+          let badge = match pres.badge {
+            Some(ref s) => format!(r#" <span class="badge">{}</span>"#, s),
+            None => String::new()
+          };
+          let link = if pres.disabled {
+            format!(
+              r#"<span class="disabled"{}>{}{}</span>"#,
+              tooltip,
+              icon,
+              mi.title()
+            )
+          } else {
+            format!(
+              r#"<a href="{}"{}>{}{}</a>"#,
+              mi.appctx().url,
+              tooltip,
+              icon,
+              mi.title()
+            )
+          };
+          out.push(format!(
+            r#"{}<li id="{}">{}{}{}</li>"#,
+            indent_str.repeat(indent),
+            mi.id(),
+            link,
+            badge,
+            description(&mi)
+          ));
+        }
+      },
+      reciter::Event::Collapsed(mi) => {
         out.push(format!(
-          r#"{}<li id="{}"><a href="{}">{}</a></li>"#,
+          r#"{}<li id="{}" class="collapsed"><a href="{}">{}</a></li>"#,
           indent_str.repeat(indent),
           mi.id(),
           mi.appctx().url,
@@ -32,6 +109,7 @@ where
         indent -= 1;
         out.push(format!("{}</ul>", indent_str.repeat(indent)));
       }
+      _ => {}
     }
   }
   out
@@ -58,7 +136,23 @@ fn main() {
       enabled: true
     }
   );
-  mib.parent("lib");
+  mib
+    .parent("lib")
+    .icon("book")
+    .tooltip("Articles and how-tos")
+    .description("Answers to frequently asked questions")
+    .badge(3);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx(
+    "lib-kb-new",
+    "Submit Article",
+    Context {
+      url: "https://library.org/kb/new".to_string(),
+      enabled: true
+    }
+  );
+  mib.parent("lib-kb").weight(200).disabled(true);
   mb.add(mib);
 
   let mut mib = menuitem::Builder::new_ctx(
@@ -72,6 +166,14 @@ fn main() {
   mib.parent("lib-kb");
   mb.add(mib);
 
+  // Not rendered, because the only item after it is hidden
+  let mut mib = menuitem::Builder::new("lib-sep", "");
+  mib
+    .parent("lib")
+    .weight(150)
+    .kind(menuitem::Kind::Separator);
+  mb.add(mib);
+
   let mut mib = menuitem::Builder::new_ctx(
     "lib-secret",
     "Secret",
@@ -80,7 +182,7 @@ fn main() {
       enabled: false
     }
   );
-  mib.parent("lib");
+  mib.parent("lib").weight(200);
   mb.add(mib);
 
   let mut mib = menuitem::Builder::new_ctx(
@@ -98,7 +200,7 @@ fn main() {
 
   let mut rit = menu.iter_hier();
   rit.root_scope();
-  rit.itemfilter(|mi| mi.appctx().enabled == true);
+  rit.itemfilter(|mi| mi.appctx().enabled);
 
   let html = build_strvec(rit);
 
@@ -4,13 +4,11 @@ use menugen::{menu, menuitem, recfiltiter};
 
 #[derive(Clone, Default)]
 struct Context {
-  url: String,
-  reqperms: HashSet<String>
+  url: String
 }
 
 fn build_strvec<F>(it: recfiltiter::MenuIter<Context, F>) -> Vec<String>
 where
-  Context: Clone + Default,
   F: Fn(&menuitem::MenuItem<Context>) -> bool
 {
   let mut out = Vec::new();
@@ -31,10 +29,20 @@ where
           mi.title()
         ));
       }
+      recfiltiter::Event::Container(mi) => {
+        // Only leads to accessible items, so render it without a link
+        out.push(format!(
+          r#"{}<li id="{}">{}</li>"#,
+          indent_str.repeat(indent),
+          mi.id(),
+          mi.title()
+        ));
+      }
       recfiltiter::Event::LeaveScope => {
         indent -= 1;
         out.push(format!("{}</ul>", indent_str.repeat(indent)));
       }
+      _ => {}
     }
   }
   out
@@ -43,79 +51,74 @@ where
 fn main() {
   let mut mb = menu::Builder::<Context>::new();
 
-  let mut perms = HashSet::new();
-  perms.insert("adm".to_owned());
-  let mib = menuitem::Builder::new_ctx(
+  let mut mib = menuitem::Builder::new_ctx(
     "lib",
     "Library",
     Context {
-      url: "https://library.org/".to_string(),
-      reqperms: perms
+      url: "https://library.org/".to_string()
     }
   );
+  mib.require_any(vec!["adm"]);
   mb.add(mib);
 
-  let mut perms = HashSet::new();
-  perms.insert("adm".to_owned());
   let mut mib = menuitem::Builder::new_ctx(
     "lib-kb",
     "Knowledge Base",
     Context {
-      url: "https://library.org/kb".to_string(),
-      reqperms: perms
+      url: "https://library.org/kb".to_string()
     }
   );
-  mib.parent("lib");
+  mib.require_any(vec!["adm"]).parent("lib");
   mb.add(mib);
 
-  let mut perms = HashSet::new();
-  perms.insert("adm".to_owned());
   let mut mib = menuitem::Builder::new_ctx(
     "lib-kb-arch",
     "Archive",
     Context {
-      url: "https://library.org/kb/archive".to_string(),
-      reqperms: perms
+      url: "https://library.org/kb/archive".to_string()
     }
   );
-  mib.parent("lib-kb");
+  mib.require_any(vec!["adm"]).parent("lib-kb");
   mb.add(mib);
 
-  let mut perms = HashSet::new();
-  perms.insert("adm".to_owned());
   let mut mib = menuitem::Builder::new_ctx(
     "lib-secret",
     "Secret",
     Context {
-      url: "https://library.org/secret".to_string(),
-      reqperms: perms
+      url: "https://library.org/secret".to_string()
+    }
+  );
+  // not accessible because the account lacks "secret", but shown as a
+  // container for "lib-secret-public", which doesn't inherit its parent's
+  // requirements
+  mib.require_all(vec!["adm", "secret"]).parent("lib");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx(
+    "lib-secret-public",
+    "Declassified",
+    Context {
+      url: "https://library.org/secret/public".to_string()
     }
   );
-  mib.parent("lib");
+  mib.inherit_requirements(false).parent("lib-secret");
   mb.add(mib);
 
-  let mut perms = HashSet::new();
-  perms.insert("adm".to_owned());
   let mut mib = menuitem::Builder::new_ctx(
     "lib-secret-secret",
     "Very Secret",
     Context {
-      url: "https://library.org/secret/very".to_string(),
-      reqperms: perms
+      url: "https://library.org/secret/very".to_string()
     }
   );
-  mib.parent("lib-secret");
+  mib.require_any(vec!["adm"]).parent("lib-secret");
   mb.add(mib);
 
   let menu = mb.build();
 
   let mut account_perms = HashSet::new();
   account_perms.insert("adm".to_owned());
-  let mut rit = menu.filtiter_hier(|mi| {
-    let isect = account_perms.intersection(&mi.appctx().reqperms);
-    let ret: HashSet<_> = isect.collect();
-    !ret.is_empty()
-  });
+  let mut rit = menu.for_principal(&account_perms);
   rit.root_scope();
 
   let html = build_strvec(rit);
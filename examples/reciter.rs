@@ -70,6 +70,7 @@ fn main() {
         println!("Leave scope");
         indent -= 1;
       }
+      _ => {}
     }
   }
 }
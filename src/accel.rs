@@ -0,0 +1,520 @@
+//! Keyboard accelerators and mnemonics.
+//!
+//! An _accelerator_ is a key chord, such as `Ctrl+Shift+S`, which activates
+//! a menu item from anywhere in the application.  Accelerators are parsed
+//! from strings using [`Accel::parse()`], which accepts common aliases
+//! (`Control`, `Cmd`, `Option`, `Esc`, ...) in any case, and are displayed in
+//! a canonical form, with the modifiers in the order `Mod`, `Ctrl`, `Alt`,
+//! `Shift`, `Meta`.
+//!
+//! `Mod` (also spelled `Primary` or `CmdOrCtrl`) is the platform's primary
+//! shortcut modifier: it is presented as `Ctrl` on Windows and Linux, and as
+//! `⌘` on macOS.  Use it for shortcuts such as `Mod+S` that should follow
+//! the platform conventions.
+//!
+//! A _mnemonic_ is a letter which activates an item among its siblings
+//! while a menu is open.  Mnemonics are usually marked in titles with an
+//! ampersand, as in `&File`; see [`parse_mnemonic()`].
+//!
+//! A [`Keymap`] maps the accelerators of a menu to menu item identifiers,
+//! and reports accelerators that are used by several items and mnemonics
+//! that are used by several siblings.
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::menu::Menu;
+use crate::menuitem::{self, Kind, MenuItem};
+
+/// Errors that can occur when parsing an accelerator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// The accelerator has no key, only modifiers or nothing at all.
+  MissingKey,
+
+  /// A modifier is not known.
+  UnknownModifier(String),
+
+  /// A key is not known.
+  UnknownKey(String),
+
+  /// A modifier is specified more than once.
+  DuplicateModifier(String)
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::MissingKey => write!(f, "Accelerator has no key"),
+      Error::UnknownModifier(s) => write!(f, "Unknown modifier '{}'", s),
+      Error::UnknownKey(s) => write!(f, "Unknown key '{}'", s),
+      Error::DuplicateModifier(s) => write!(f, "Duplicate modifier '{}'", s)
+    }
+  }
+}
+
+
+/// Named keys, in their canonical spelling.
+const NAMED_KEYS: &[&str] = &[
+  "Enter",
+  "Escape",
+  "Tab",
+  "Space",
+  "Backspace",
+  "Delete",
+  "Insert",
+  "Home",
+  "End",
+  "PageUp",
+  "PageDown",
+  "Up",
+  "Down",
+  "Left",
+  "Right"
+];
+
+/// Aliases of named keys, and names of keys whose canonical form is a single
+/// character.
+const KEY_ALIASES: &[(&str, &str)] = &[
+  ("plus", "+"),
+  ("minus", "-"),
+  ("return", "Enter"),
+  ("esc", "Escape"),
+  ("del", "Delete"),
+  ("ins", "Insert"),
+  ("pgup", "PageUp"),
+  ("pgdn", "PageDown"),
+  ("pagedn", "PageDown")
+];
+
+/// Keyboard modifiers.
+#[derive(
+  Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Modifiers {
+  pub ctrl: bool,
+  pub alt: bool,
+  pub shift: bool,
+
+  /// The Command key on macOS, the Windows or Super key elsewhere.
+  pub meta: bool,
+
+  /// The primary modifier: the Command key on macOS, Ctrl elsewhere.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub primary: bool
+}
+
+/// A key chord: a key and the modifiers held down with it.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Accel {
+  pub mods: Modifiers,
+
+  /// The key in canonical form: an upper case letter, a digit or another
+  /// single character, `F1` to `F24`, or one of the named keys such as
+  /// `Enter` and `PageUp`.  `Plus` and `Minus` are spelled `+` and `-`.
+  pub key: String
+}
+
+impl Accel {
+  /// Parse an accelerator such as `Ctrl+Shift+S`.
+  pub fn parse(s: &str) -> Result<Self, Error> {
+    let s = s.trim();
+    let (mods, key) = if s == "+" {
+      ("", "+")
+    } else if let Some(rest) = s.strip_suffix("++") {
+      (rest, "+")
+    } else {
+      match s.rsplit_once('+') {
+        Some((mods, key)) => (mods, key),
+        None => ("", s)
+      }
+    };
+
+    let mut m = Modifiers::default();
+    for name in mods.split('+').map(str::trim).filter(|s| !s.is_empty()) {
+      let flag = match modifier(&mut m, name) {
+        Some(flag) => flag,
+        None => return Err(Error::UnknownModifier(name.to_string()))
+      };
+      if *flag {
+        return Err(Error::DuplicateModifier(name.to_string()));
+      }
+      *flag = true;
+    }
+
+    // A chord of modifiers only, such as `Ctrl+Shift`
+    let key = key.trim();
+    if modifier(&mut Modifiers::default(), key).is_some() {
+      return Err(Error::MissingKey);
+    }
+
+    Ok(Accel {
+      mods: m,
+      key: canonical_key(key)?
+    })
+  }
+}
+
+/// Return the flag of `m` for the modifier `name`, or `None` if `name` is
+/// not a modifier.
+fn modifier<'m>(m: &'m mut Modifiers, name: &str) -> Option<&'m mut bool> {
+  match name.to_lowercase().as_str() {
+    "ctrl" | "control" | "ctl" => Some(&mut m.ctrl),
+    "alt" | "option" | "opt" => Some(&mut m.alt),
+    "shift" => Some(&mut m.shift),
+    "meta" | "cmd" | "command" | "super" | "win" | "windows" => {
+      Some(&mut m.meta)
+    }
+    "mod" | "primary" | "cmdorctrl" | "commandorcontrol" => {
+      Some(&mut m.primary)
+    }
+    _ => None
+  }
+}
+
+/// Return the canonical form of a key name.
+fn canonical_key(key: &str) -> Result<String, Error> {
+  let mut chars = key.chars();
+  match (chars.next(), chars.next()) {
+    (None, _) => return Err(Error::MissingKey),
+    (Some(c), None) => return Ok(c.to_uppercase().collect()),
+    _ => {}
+  }
+
+  let lc = key.to_lowercase();
+  if let Some(n) = lc.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+    if (1..=24).contains(&n) {
+      return Ok(format!("F{}", n));
+    }
+  }
+  if let Some(name) = NAMED_KEYS.iter().find(|k| k.to_lowercase() == lc) {
+    return Ok(name.to_string());
+  }
+  if let Some((_, name)) = KEY_ALIASES.iter().find(|(a, _)| *a == lc) {
+    return Ok(name.to_string());
+  }
+  Err(Error::UnknownKey(key.to_string()))
+}
+
+impl FromStr for Accel {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Accel::parse(s)
+  }
+}
+
+impl fmt::Display for Accel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let m = &self.mods;
+    for (on, name) in [
+      (m.primary, "Mod"),
+      (m.ctrl, "Ctrl"),
+      (m.alt, "Alt"),
+      (m.shift, "Shift"),
+      (m.meta, "Meta")
+    ] {
+      if on {
+        write!(f, "{}+", name)?;
+      }
+    }
+    write!(f, "{}", self.key)
+  }
+}
+
+/// Platform conventions for presenting accelerators.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Platform {
+  /// `Ctrl+Shift+S`, with `Win` for the meta key.
+  Windows,
+
+  /// `Ctrl+Shift+S`, with `Super` for the meta key.
+  Linux,
+
+  /// `⇧⌘S`, using the macOS modifier and key symbols, with `⌘` for the
+  /// primary modifier.
+  Mac
+}
+
+impl Platform {
+  /// Return the platform the program was compiled for.
+  pub fn native() -> Self {
+    if cfg!(target_os = "macos") {
+      Platform::Mac
+    } else if cfg!(windows) {
+      Platform::Windows
+    } else {
+      Platform::Linux
+    }
+  }
+}
+
+impl Accel {
+  /// Present the accelerator using the conventions of `platform`.  The
+  /// primary modifier is presented as `Ctrl` on Windows and Linux, and as
+  /// `⌘` on macOS.
+  pub fn display_for(&self, platform: Platform) -> String {
+    let m = &self.mods;
+    let meta = match platform {
+      Platform::Windows => "Win",
+      Platform::Linux => "Super",
+      Platform::Mac => {
+        let mut s = String::new();
+        for (on, sym) in [
+          (m.ctrl, '⌃'),
+          (m.alt, '⌥'),
+          (m.shift, '⇧'),
+          (m.meta || m.primary, '⌘')
+        ] {
+          if on {
+            s.push(sym);
+          }
+        }
+        s.push_str(mac_key(&self.key));
+        return s;
+      }
+    };
+    let mut parts = Vec::new();
+    for (on, name) in [
+      (m.ctrl || m.primary, "Ctrl"),
+      (m.alt, "Alt"),
+      (m.shift, "Shift"),
+      (m.meta, meta)
+    ] {
+      if on {
+        parts.push(name);
+      }
+    }
+    parts.push(&self.key);
+    parts.join("+")
+  }
+}
+
+/// Return the macOS symbol for a key, or the key itself if it has none.
+fn mac_key(key: &str) -> &str {
+  match key {
+    "Enter" => "↩",
+    "Escape" => "⎋",
+    "Tab" => "⇥",
+    "Backspace" => "⌫",
+    "Delete" => "⌦",
+    "Home" => "↖",
+    "End" => "↘",
+    "PageUp" => "⇞",
+    "PageDown" => "⇟",
+    "Up" => "↑",
+    "Down" => "↓",
+    "Left" => "←",
+    "Right" => "→",
+    _ => key
+  }
+}
+
+
+/// A mnemonic letter.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mnemonic {
+  /// The mnemonic, in lower case.
+  pub key: char,
+
+  /// Byte offset of the marked character in the title, for underlining, if
+  /// the mnemonic was taken from the title.
+  pub offset: Option<usize>
+}
+
+/// Parse a title in which the mnemonic is marked with an ampersand, as in
+/// `&File` or `Save &As`.  A double ampersand stands for a literal one.
+///
+/// Returns the title without the markers, and the mnemonic, if any.  Only
+/// the first marker is used.
+pub fn parse_mnemonic(s: &str) -> (String, Option<Mnemonic>) {
+  let mut title = String::with_capacity(s.len());
+  let mut mnemonic = None;
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    if c != '&' {
+      title.push(c);
+      continue;
+    }
+    match chars.next() {
+      Some('&') | None => title.push('&'),
+      Some(m) => {
+        if mnemonic.is_none() && !m.is_whitespace() {
+          mnemonic = Some(Mnemonic {
+            key: m.to_lowercase().next().unwrap_or(m),
+            offset: Some(title.len())
+          });
+        }
+        title.push(m);
+      }
+    }
+  }
+  (title, mnemonic)
+}
+
+/// Suggest mnemonics for the items of a list of siblings that do not have
+/// one, or whose mnemonic is also used by an earlier sibling.
+///
+/// Letters that start words in the title are preferred over other letters
+/// and digits, and letters that are in use by other siblings are skipped.
+/// Items for which no free letter can be found are left out.  Returns the
+/// identifiers of the items, in list order, with the suggested letters.
+pub fn suggest_mnemonics<C>(lst: &[MenuItem<C>]) -> Vec<(String, char)> {
+  let lst: Vec<&MenuItem<C>> = menuitem::shown(lst, &|_: &MenuItem<C>| true)
+    .filter(|mi| !matches!(mi.kind(), Kind::Header | Kind::Separator))
+    .collect();
+  let mut used: Vec<char> = Vec::new();
+  let mut needs = Vec::new();
+  for mi in &lst {
+    match mi.mnemonic() {
+      Some(m) if !used.contains(&m.key) => used.push(m.key),
+      _ => needs.push(*mi)
+    }
+  }
+
+  let mut out = Vec::new();
+  for mi in needs {
+    let title = mi.title();
+    let word_starts = title
+      .split_whitespace()
+      .filter_map(|w| w.chars().find(|c| c.is_alphanumeric()));
+    let others = title.chars().filter(|c| c.is_alphanumeric());
+    let free = word_starts
+      .chain(others)
+      .filter_map(|c| c.to_lowercase().next())
+      .find(|c| !used.contains(c));
+    if let Some(c) = free {
+      used.push(c);
+      out.push((mi.id().to_string(), c));
+    }
+  }
+  out
+}
+
+
+/// A key that is used by more than one menu item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+  /// Several items, listed in menu order, have the same accelerator.
+  Accel { accel: Accel, ids: Vec<String> },
+
+  /// Several siblings, listed in menu order, have the same mnemonic.
+  /// `parent` is `None` for root items.
+  Mnemonic {
+    parent: Option<String>,
+    key: char,
+    ids: Vec<String>
+  }
+}
+
+/// Accelerator to menu item lookup table.
+pub struct Keymap {
+  accels: HashMap<Accel, String>,
+  conflicts: Vec<Conflict>
+}
+
+impl Keymap {
+  /// Create a keymap for all the items of `menu`.
+  pub fn new<C>(menu: &Menu<C>) -> Self {
+    Self::filtered(menu, |_| true)
+  }
+
+  /// Create a keymap for the items of `menu` that are visible when it is
+  /// filtered using the predicate `p`.  Accelerators and mnemonics of hidden
+  /// items do not conflict with those of visible items.
+  pub fn filtered<C, F>(menu: &Menu<C>, p: F) -> Self
+  where
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    let mut accels: Vec<(Accel, Vec<String>)> = Vec::new();
+    let mut conflicts = Vec::new();
+    scan(&menu.rootlst, None, &p, &mut accels, &mut conflicts);
+
+    let mut map = HashMap::new();
+    let mut accel_conflicts = Vec::new();
+    for (accel, ids) in accels {
+      if ids.len() > 1 {
+        accel_conflicts.push(Conflict::Accel {
+          accel: accel.clone(),
+          ids: ids.clone()
+        });
+      }
+      map.insert(accel, ids.into_iter().next().unwrap_or_default());
+    }
+    accel_conflicts.extend(conflicts);
+
+    Keymap {
+      accels: map,
+      conflicts: accel_conflicts
+    }
+  }
+
+  /// Return the identifier of the menu item with the accelerator `accel`.
+  /// If several items have the same accelerator, the first one in menu order
+  /// is returned.
+  pub fn lookup(&self, accel: &Accel) -> Option<&str> {
+    self.accels.get(accel).map(String::as_str)
+  }
+
+  /// Return the accelerators used by several items, followed by the
+  /// mnemonics used by several siblings.
+  pub fn conflicts(&self) -> &[Conflict] {
+    &self.conflicts
+  }
+
+  /// Return `true` if there are no conflicts.
+  pub fn is_valid(&self) -> bool {
+    self.conflicts.is_empty()
+  }
+}
+
+/// Collect the accelerators of the shown items of a list of siblings and
+/// their descendants, and check their mnemonics.
+fn scan<C, F>(
+  lst: &[MenuItem<C>],
+  parent: Option<&str>,
+  p: &F,
+  accels: &mut Vec<(Accel, Vec<String>)>,
+  conflicts: &mut Vec<Conflict>
+) where
+  F: Fn(&MenuItem<C>) -> bool
+{
+  let mut mnemonics: Vec<(char, Vec<String>)> = Vec::new();
+  for mi in menuitem::shown(lst, p) {
+    if let Some(m) = mi.mnemonic() {
+      match mnemonics.iter_mut().find(|(k, _)| *k == m.key) {
+        Some((_, ids)) => ids.push(mi.id().to_string()),
+        None => mnemonics.push((m.key, vec![mi.id().to_string()]))
+      }
+    }
+  }
+  for (key, ids) in mnemonics {
+    if ids.len() > 1 {
+      conflicts.push(Conflict::Mnemonic {
+        parent: parent.map(str::to_string),
+        key,
+        ids
+      });
+    }
+  }
+
+  for mi in menuitem::shown(lst, p) {
+    if let Some(accel) = mi.accel() {
+      match accels.iter_mut().find(|(a, _)| a == accel) {
+        Some((_, ids)) => ids.push(mi.id().to_string()),
+        None => accels.push((accel.clone(), vec![mi.id().to_string()]))
+      }
+    }
+    scan(&mi.children, Some(mi.id()), p, accels, conflicts);
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,238 @@
+//! Bind command handlers to menu items.
+//!
+//! A [`Registry`] maps menu item identifiers to handlers, which are called
+//! with the activated item and an application defined context when the item
+//! is activated using [`Menu::activate()`].  Commands can have an `enabled`
+//! predicate, which is evaluated against the application context when an
+//! item is activated.  The built-in renderers take a registry and an
+//! application context through their `enabled()` methods, and present the
+//! items that can not be activated as disabled.
+//!
+//! A registry is created for a menu, and handlers and predicates can only be
+//! bound to items of that menu.  [`Registry::validate()`] checks that a
+//! registry and a menu match: that every item that can be activated has a
+//! handler, and that there are no handlers for items that no longer exist.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::menu::Menu;
+use crate::menuitem::{Kind, MenuItem};
+
+/// Errors that can occur when validating a registry or activating an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// There is no menu item with the specified identifier.
+  UnknownItem(String),
+
+  /// No handler is bound to the menu item.
+  Unhandled(String),
+
+  /// The menu item, or its command, is disabled.
+  Disabled(String),
+
+  /// The registry does not match the menu.  `unhandled` lists the items that
+  /// can be activated but have no handler, and `unknown` the handlers and
+  /// predicates that are bound to items which do not exist.
+  Mismatch {
+    unhandled: Vec<String>,
+    unknown: Vec<String>
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::UnknownItem(s) => write!(f, "Unknown menu item '{}'", s),
+      Error::Unhandled(s) => write!(f, "No handler for menu item '{}'", s),
+      Error::Disabled(s) => write!(f, "Menu item '{}' is disabled", s),
+      Error::Mismatch { unhandled, unknown } => {
+        write!(f, "Handlers do not match menu")?;
+        if !unhandled.is_empty() {
+          write!(f, "; unhandled items: {}", unhandled.join(", "))?;
+        }
+        if !unknown.is_empty() {
+          write!(f, "; unknown items: {}", unknown.join(", "))?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+
+type Handler<'a, C, A, R> = Box<dyn Fn(&MenuItem<C>, &mut A) -> R + 'a>;
+type Predicate<'a, C, A> = Box<dyn Fn(&MenuItem<C>, &A) -> bool + 'a>;
+
+/// Predicate which tells the renderers whether a menu item is enabled.
+pub(crate) type EnabledFn<'a, C> = Box<dyn Fn(&MenuItem<C>) -> bool + 'a>;
+
+/// Return `true` if `mi` is enabled according to `enabled` or, without a
+/// predicate, if it is not disabled.
+pub(crate) fn is_enabled<C>(
+  mi: &MenuItem<C>,
+  enabled: &Option<EnabledFn<'_, C>>
+) -> bool {
+  match enabled {
+    Some(f) => f(mi),
+    None => !mi.is_disabled()
+  }
+}
+
+/// Command handlers and `enabled` predicates, keyed by menu item identifier.
+///
+/// `A` is the application context passed to the handlers, and `R` is the
+/// handlers' return type.
+pub struct Registry<'a, C, A, R = ()> {
+  ids: HashSet<String>,
+  handlers: HashMap<String, Handler<'a, C, A, R>>,
+  enabled: HashMap<String, Predicate<'a, C, A>>
+}
+
+impl<'a, C, A, R> Registry<'a, C, A, R> {
+  /// Create an empty registry for the items of `menu`.
+  ///
+  /// The registry does not borrow the menu; it records the identifiers of the
+  /// menu's items, so that binding to an unknown item can be reported.
+  pub fn new(menu: &Menu<C>) -> Self {
+    let mut ids = HashSet::new();
+    let mut stack: Vec<&MenuItem<C>> = menu.rootlst.iter().collect();
+    while let Some(mi) = stack.pop() {
+      ids.insert(mi.id().to_string());
+      stack.extend(mi.children.iter());
+    }
+    Registry {
+      ids,
+      handlers: HashMap::new(),
+      enabled: HashMap::new()
+    }
+  }
+
+  /// Bind the handler `f` to the menu item `miid`, replacing any previously
+  /// bound handler.
+  ///
+  /// Fails if the menu the registry was created for has no such item.
+  pub fn bind<M, F>(&mut self, miid: M, f: F) -> Result<&mut Self, Error>
+  where
+    M: ToString,
+    F: Fn(&MenuItem<C>, &mut A) -> R + 'a
+  {
+    let miid = self.known(miid)?;
+    self.handlers.insert(miid, Box::new(f));
+    Ok(self)
+  }
+
+  /// Only enable the command of the menu item `miid` when `f` returns
+  /// `true`.  A disabled item stays disabled whatever the predicate says.
+  ///
+  /// Fails if the menu the registry was created for has no such item.
+  pub fn enabled<M, F>(&mut self, miid: M, f: F) -> Result<&mut Self, Error>
+  where
+    M: ToString,
+    F: Fn(&MenuItem<C>, &A) -> bool + 'a
+  {
+    let miid = self.known(miid)?;
+    self.enabled.insert(miid, Box::new(f));
+    Ok(self)
+  }
+
+  fn known<M: ToString>(&self, miid: M) -> Result<String, Error> {
+    let miid = miid.to_string();
+    if self.ids.contains(&miid) {
+      Ok(miid)
+    } else {
+      Err(Error::UnknownItem(miid))
+    }
+  }
+
+  /// Return `true` if a handler is bound to the menu item `miid`.
+  pub fn is_bound(&self, miid: &str) -> bool {
+    self.handlers.contains_key(miid)
+  }
+
+  /// Return `true` if the menu item is enabled: if it is not disabled and
+  /// its `enabled` predicate, if it has one, holds.
+  pub fn is_enabled(&self, mi: &MenuItem<C>, app: &A) -> bool {
+    if mi.is_disabled() {
+      return false;
+    }
+    match self.enabled.get(mi.id()) {
+      Some(f) => f(mi, app),
+      None => true
+    }
+  }
+
+  /// Return a predicate which evaluates [`Registry::is_enabled()`] in the
+  /// application context `app`, for the renderers.
+  pub(crate) fn enabled_fn<'b>(&'b self, app: &'b A) -> EnabledFn<'b, C> {
+    Box::new(move |mi| self.is_enabled(mi, app))
+  }
+
+  /// Check that the registry matches `menu`.
+  ///
+  /// Every item that can be activated -- an item without children which is
+  /// not a separator, header or group -- must have a handler, and handlers
+  /// and predicates may only be bound to items that exist.  The menu may have
+  /// been edited since the registry was created.
+  pub fn validate(&self, menu: &Menu<C>) -> Result<(), Error> {
+    let mut unhandled = Vec::new();
+    let mut stack: Vec<&MenuItem<C>> = menu.rootlst.iter().rev().collect();
+    while let Some(mi) = stack.pop() {
+      if is_activatable(mi) && !self.handlers.contains_key(mi.id()) {
+        unhandled.push(mi.id().to_string());
+      }
+      stack.extend(mi.children.iter().rev());
+    }
+
+    let mut unknown: Vec<String> = self
+      .handlers
+      .keys()
+      .chain(self.enabled.keys())
+      .filter(|id| menu.get(id).is_none())
+      .cloned()
+      .collect();
+    unknown.sort();
+    unknown.dedup();
+
+    if unhandled.is_empty() && unknown.is_empty() {
+      Ok(())
+    } else {
+      Err(Error::Mismatch { unhandled, unknown })
+    }
+  }
+}
+
+fn is_activatable<C>(mi: &MenuItem<C>) -> bool {
+  mi.children.is_empty()
+    && !matches!(mi.kind(), Kind::Header | Kind::Separator | Kind::Group)
+}
+
+
+impl<C> Menu<C> {
+  /// Activate the menu item `miid`: call the handler bound to it in
+  /// `registry` with the item and the application context `app`, and return
+  /// the handler's result.
+  ///
+  /// Fails if the item does not exist, has no handler or is disabled.
+  pub fn activate<A, R>(
+    &self,
+    registry: &Registry<'_, C, A, R>,
+    miid: &str,
+    app: &mut A
+  ) -> Result<R, Error> {
+    let mi = self
+      .get(miid)
+      .ok_or_else(|| Error::UnknownItem(miid.to_string()))?;
+    let f = registry
+      .handlers
+      .get(miid)
+      .ok_or_else(|| Error::Unhandled(miid.to_string()))?;
+    if !registry.is_enabled(mi, app) {
+      return Err(Error::Disabled(miid.to_string()));
+    }
+    Ok(f(mi, app))
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
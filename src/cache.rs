@@ -0,0 +1,151 @@
+//! Cache of materialized, per-principal, menus.
+//!
+//! Personalized menus typically only depend on a small number of distinct
+//! permission combinations, even when there are many users.  A
+//! [`MenuCache`] stores materialized menus keyed by a caller supplied
+//! fingerprint of the principal's permissions and the version of the source
+//! menu, so each personalized menu only needs to be computed once.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use crate::menu::Menu;
+use crate::menuitem::MenuItem;
+
+struct Entry<C> {
+  menu: Arc<Menu<C>>,
+  last_used: u64
+}
+
+struct Inner<K, C> {
+  entries: HashMap<(K, u64), Entry<C>>,
+  tick: u64
+}
+
+/// A bounded, thread safe, cache of menus with least recently used eviction.
+pub struct MenuCache<K, C> {
+  capacity: usize,
+  inner: Mutex<Inner<K, C>>
+}
+
+impl<K, C> MenuCache<K, C>
+where
+  K: Hash + Eq + Clone
+{
+  /// Create a cache which holds at most `capacity` menus.
+  pub fn new(capacity: usize) -> Self {
+    MenuCache {
+      capacity: capacity.max(1),
+      inner: Mutex::new(Inner {
+        entries: HashMap::new(),
+        tick: 0
+      })
+    }
+  }
+
+  /// Return the cached menu for the fingerprint `key` and the menu version
+  /// `version`, if there is one.
+  pub fn get(&self, key: &K, version: u64) -> Option<Arc<Menu<C>>> {
+    let mut inner = self.lock();
+    inner.tick += 1;
+    let tick = inner.tick;
+    let entry = inner.entries.get_mut(&(key.clone(), version))?;
+    entry.last_used = tick;
+    Some(Arc::clone(&entry.menu))
+  }
+
+  /// Return the cached menu for the fingerprint `key` and the menu version
+  /// `version`, or generate it using `f` and store it in the cache.
+  ///
+  /// The cache is not locked while `f` runs, so concurrent callers asking
+  /// for the same key may both run `f`.  If so, the menu stored first is
+  /// kept.
+  pub fn get_or_insert_with<F>(
+    &self,
+    key: K,
+    version: u64,
+    f: F
+  ) -> Arc<Menu<C>>
+  where
+    F: FnOnce() -> Menu<C>
+  {
+    if let Some(menu) = self.get(&key, version) {
+      return menu;
+    }
+    let menu = Arc::new(f());
+
+    let mut inner = self.lock();
+    inner.tick += 1;
+    let tick = inner.tick;
+    let idx = (key, version);
+    if let Some(entry) = inner.entries.get_mut(&idx) {
+      entry.last_used = tick;
+      return Arc::clone(&entry.menu);
+    }
+
+    if inner.entries.len() >= self.capacity {
+      let lru = inner
+        .entries
+        .iter()
+        .min_by_key(|(_, e)| e.last_used)
+        .map(|(k, _)| k.clone());
+      if let Some(lru) = lru {
+        inner.entries.remove(&lru);
+      }
+    }
+
+    inner.entries.insert(
+      idx,
+      Entry {
+        menu: Arc::clone(&menu),
+        last_used: tick
+      }
+    );
+    menu
+  }
+
+  /// Return the cached filtered view of `menu` for the fingerprint `key`, or
+  /// materialize it using [`Menu::filtered()`] with the predicate `p`.
+  pub fn materialize<F>(
+    &self,
+    menu: &Menu<C>,
+    version: u64,
+    key: K,
+    p: F
+  ) -> Arc<Menu<C>>
+  where
+    C: Clone,
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    self.get_or_insert_with(key, version, || menu.filtered(p))
+  }
+
+  /// Remove all menus generated from versions other than `version`.
+  pub fn retain_version(&self, version: u64) {
+    self.lock().entries.retain(|(_, v), _| *v == version);
+  }
+
+  /// Remove all menus from the cache.
+  pub fn clear(&self) {
+    self.lock().entries.clear();
+  }
+
+  pub fn len(&self) -> usize {
+    self.lock().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner<K, C>> {
+    // A panic while holding the lock can not leave the cache in an
+    // inconsistent state, so ignore poisoning.
+    match self.inner.lock() {
+      Ok(guard) => guard,
+      Err(poisoned) => poisoned.into_inner()
+    }
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,249 @@
+//! Keyboard shortcut reference generation.
+//!
+//! A [`Sheet`] collects the items of a menu that have accelerators, grouped
+//! in one section per top-level menu, and renders them as HTML, Markdown or
+//! plain text.  Items that are nested deeper than the top-level menu are
+//! listed with the titles of their ancestors, as in `Zoom > In`.  Root items
+//! that have accelerators themselves are listed in a leading section without
+//! a title.
+//!
+//! Accelerators are presented using the conventions of the
+//! [`Platform`] passed to the renderers.  Disabled items are listed, but
+//! marked as such; [`Sheet::enabled()`] also marks the items that an action
+//! registry does not consider enabled.
+use std::fmt::Write;
+
+use crate::accel::{Accel, Platform};
+use crate::action::Registry;
+use crate::menu::Menu;
+use crate::menuitem::{self, MenuItem};
+
+/// A single shortcut.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+  pub id: String,
+
+  /// The item's title, preceded by the titles of its ancestors below the
+  /// section's top-level menu.
+  pub title: String,
+  pub accel: Accel,
+
+  /// `true` if the shortcut can not currently be used.
+  pub disabled: bool
+}
+
+/// The shortcuts of a top-level menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+  /// The title of the top-level menu, or `None` for the section of root
+  /// items.
+  pub title: Option<String>,
+  pub entries: Vec<Entry>
+}
+
+/// Keyboard shortcut reference for a menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sheet {
+  pub sections: Vec<Section>
+}
+
+impl Sheet {
+  /// Collect the shortcuts of all items of `menu`.
+  pub fn new<C>(menu: &Menu<C>) -> Self {
+    Self::filtered(menu, |_| true)
+  }
+
+  /// Collect the shortcuts of the items of `menu` that are visible when it
+  /// is filtered using the predicate `p`.  Sections without shortcuts are
+  /// left out.
+  pub fn filtered<C, F>(menu: &Menu<C>, p: F) -> Self
+  where
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    let mut root = Section {
+      title: None,
+      entries: Vec::new()
+    };
+    let mut sections = Vec::new();
+    for mi in menuitem::shown(&menu.rootlst, &p) {
+      if let Some(accel) = mi.accel() {
+        root.entries.push(Entry {
+          id: mi.id().to_string(),
+          title: mi.title().to_string(),
+          accel: accel.clone(),
+          disabled: mi.is_disabled()
+        });
+      }
+      let mut sect = Section {
+        title: Some(mi.title().to_string()),
+        entries: Vec::new()
+      };
+      collect(&mi.children, &p, &mut Vec::new(), &mut sect.entries);
+      if !sect.entries.is_empty() {
+        sections.push(sect);
+      }
+    }
+    if !root.entries.is_empty() {
+      sections.insert(0, root);
+    }
+    Sheet { sections }
+  }
+
+  /// Mark the entries whose items `registry` does not consider enabled in
+  /// the application context `app` as disabled, and the others as enabled
+  /// (see [`Registry::is_enabled()`]).  `menu` is the menu the sheet was
+  /// collected from.
+  pub fn enabled<C, A, R>(
+    &mut self,
+    menu: &Menu<C>,
+    registry: &Registry<'_, C, A, R>,
+    app: &A
+  ) -> &mut Self {
+    for e in self.sections.iter_mut().flat_map(|s| s.entries.iter_mut()) {
+      e.disabled = match menu.get(&e.id) {
+        Some(mi) => !registry.is_enabled(mi, app),
+        None => true
+      };
+    }
+    self
+  }
+
+  /// Return `true` if the menu has no shortcuts.
+  pub fn is_empty(&self) -> bool {
+    self.sections.is_empty()
+  }
+
+  /// Render the sheet as HTML: a heading and a table per section.  The rows
+  /// of disabled entries have the class `disabled`.
+  pub fn html(&self, platform: Platform) -> String {
+    let mut out = String::new();
+    for sect in &self.sections {
+      if let Some(ref title) = sect.title {
+        let _ = writeln!(out, "<h2>{}</h2>", escape_html(title));
+      }
+      out.push_str("<table class=\"shortcuts\">\n");
+      for e in &sect.entries {
+        let _ = writeln!(
+          out,
+          "<tr{}><td>{}</td><td><kbd>{}</kbd></td></tr>",
+          if e.disabled {
+            " class=\"disabled\""
+          } else {
+            ""
+          },
+          escape_html(&e.title),
+          escape_html(&e.accel.display_for(platform))
+        );
+      }
+      out.push_str("</table>\n");
+    }
+    out
+  }
+
+  /// Render the sheet as Markdown: a heading and a table per section.
+  /// Disabled entries are marked with `(disabled)`.
+  pub fn markdown(&self, platform: Platform) -> String {
+    let mut out = String::new();
+    for (i, sect) in self.sections.iter().enumerate() {
+      if i > 0 {
+        out.push('\n');
+      }
+      if let Some(ref title) = sect.title {
+        let _ = writeln!(out, "## {}\n", escape_markdown(title));
+      }
+      out.push_str("| Command | Shortcut |\n|---|---|\n");
+      for e in &sect.entries {
+        let _ = writeln!(
+          out,
+          "| {}{} | `{}` |",
+          escape_markdown(&e.title),
+          if e.disabled { " (disabled)" } else { "" },
+          e.accel.display_for(platform)
+        );
+      }
+    }
+    out
+  }
+
+  /// Render the sheet as plain text, with the shortcuts of each section
+  /// aligned in a column.  Disabled entries are marked with `(disabled)`.
+  pub fn text(&self, platform: Platform) -> String {
+    let mut out = String::new();
+    for (i, sect) in self.sections.iter().enumerate() {
+      if i > 0 {
+        out.push('\n');
+      }
+      let indent = match sect.title {
+        Some(ref title) => {
+          let _ = writeln!(out, "{}", title);
+          "  "
+        }
+        None => ""
+      };
+      let width = sect
+        .entries
+        .iter()
+        .map(|e| e.title.chars().count())
+        .max()
+        .unwrap_or(0);
+      for e in &sect.entries {
+        let pad = width - e.title.chars().count();
+        let _ = writeln!(
+          out,
+          "{}{}{}  {}{}",
+          indent,
+          e.title,
+          " ".repeat(pad),
+          e.accel.display_for(platform),
+          if e.disabled { " (disabled)" } else { "" }
+        );
+      }
+    }
+    out
+  }
+}
+
+/// Collect the shortcuts of the shown items of a list of siblings and their
+/// descendants.  `path` holds the titles of the ancestors below the section.
+fn collect<'a, C, F>(
+  lst: &'a [MenuItem<C>],
+  p: &'a F,
+  path: &mut Vec<&'a str>,
+  out: &mut Vec<Entry>
+) where
+  F: Fn(&MenuItem<C>) -> bool
+{
+  for mi in menuitem::shown(lst, p) {
+    path.push(mi.title());
+    if let Some(accel) = mi.accel() {
+      out.push(Entry {
+        id: mi.id().to_string(),
+        title: path.join(" > "),
+        accel: accel.clone(),
+        disabled: mi.is_disabled()
+      });
+    }
+    collect(&mi.children, p, path, out);
+    path.pop();
+  }
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+fn escape_markdown(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    if matches!(c, '|' | '\\' | '*' | '_' | '`' | '[' | ']' | '<') {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  out
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,308 @@
+//! State of checkbox and radio items.
+//!
+//! The checked state of [`Kind::Checkbox`] and [`Kind::Radio`] items is kept
+//! in a [`Store`], separately from the menu, so that one menu can be shared
+//! between several users or windows that each have their own state.  The
+//! store makes sure that at most one item in each radio group is checked, and
+//! reports every change it makes to the listeners registered using
+//! [`Store::on_change()`].
+//!
+//! The state is reflected by the hierarchical iterators, in the events'
+//! [`Item::checked`](crate::reciter::Item::checked) field (see
+//! [`reciter::MenuIter::checks()`](crate::reciter::MenuIter::checks)), and
+//! by the built-in renderers.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::menu::Menu;
+use crate::menuitem::{Kind, MenuItem};
+
+/// Errors that can occur when changing the state of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// There is no checkbox or radio item with the specified identifier.
+  NotCheckable(String),
+
+  /// Radio items can not be unchecked directly; check another item in the
+  /// same group instead.
+  UncheckRadio(String),
+
+  /// The item is disabled.
+  Disabled(String)
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::NotCheckable(s) => {
+        write!(f, "'{}' is not a checkbox or radio item", s)
+      }
+      Error::UncheckRadio(s) => {
+        write!(f, "Radio item '{}' can not be unchecked", s)
+      }
+      Error::Disabled(s) => write!(f, "Menu item '{}' is disabled", s)
+    }
+  }
+}
+
+/// A change of the state of an item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Change {
+  pub id: String,
+  pub checked: bool
+}
+
+/// A radio group: either a named group, or the implicit group of the radio
+/// items without a group name that share a parent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Group {
+  Named(String),
+  Siblings(Option<String>)
+}
+
+type Listener = Box<dyn FnMut(&Change) + Send>;
+
+/// Checked state of the checkbox and radio items of a menu.
+pub struct Store {
+  checked: HashSet<String>,
+
+  /// The checkable items, mapped to the radio groups of the radio items.
+  items: HashMap<String, Option<Group>>,
+
+  /// Radio groups, mapped to their members in menu order.
+  groups: HashMap<Group, Vec<String>>,
+
+  /// The checkable items that are disabled.
+  disabled: HashSet<String>,
+  listeners: Vec<Listener>
+}
+
+impl Store {
+  /// Create a store for the checkable items of `menu`, initialized to the
+  /// items' initial states.
+  ///
+  /// If several items in a radio group are initially checked, only the
+  /// first one is.
+  pub fn new<C>(menu: &Menu<C>) -> Self {
+    let mut store = Store {
+      checked: HashSet::new(),
+      items: HashMap::new(),
+      groups: HashMap::new(),
+      disabled: HashSet::new(),
+      listeners: Vec::new()
+    };
+    let mut found = Vec::new();
+    store.scan(&menu.rootlst, None, &mut found);
+    store.init(found);
+    store
+  }
+
+  /// Update the store after the menu has been edited.
+  ///
+  /// Items that are new to the store get their initial states, and items
+  /// that no longer are in the menu are forgotten; neither is reported as a
+  /// change.  If the edits have brought several checked items together in
+  /// one radio group, all but the first of them are unchecked.
+  ///
+  /// Returns the items that were unchecked, which are also reported to the
+  /// listeners.
+  pub fn sync<C>(&mut self, menu: &Menu<C>) -> Vec<Change> {
+    let known = std::mem::take(&mut self.items);
+    self.groups.clear();
+    self.disabled.clear();
+    let mut found = Vec::new();
+    self.scan(&menu.rootlst, None, &mut found);
+
+    let items = &self.items;
+    self.checked.retain(|id| items.contains_key(id));
+    found.retain(|(id, _)| !known.contains_key(id));
+    self.init(found);
+
+    let mut changes = Vec::new();
+    for members in self.groups.values() {
+      let mut checked = members.iter().filter(|m| self.checked.contains(*m));
+      checked.next();
+      changes.extend(checked.map(|m| Change {
+        id: m.clone(),
+        checked: false
+      }));
+    }
+    changes.sort_by(|a, b| a.id.cmp(&b.id));
+    self.commit(&changes);
+    changes
+  }
+
+  /// Collect the checkable items and radio groups of a list of items, and
+  /// their descendants.  The identifiers of the items are added to `found`,
+  /// in menu order, along with their initial states.
+  fn scan<C>(
+    &mut self,
+    lst: &[MenuItem<C>],
+    parent: Option<&str>,
+    found: &mut Vec<(String, bool)>
+  ) {
+    for mi in lst {
+      if mi.is_checkable() {
+        let group = match (mi.kind(), mi.radio_group()) {
+          (Kind::Radio, Some(name)) => Some(Group::Named(name.to_string())),
+          (Kind::Radio, None) => {
+            Some(Group::Siblings(parent.map(str::to_string)))
+          }
+          _ => None
+        };
+        if let Some(ref g) = group {
+          self
+            .groups
+            .entry(g.clone())
+            .or_default()
+            .push(mi.id().to_string());
+        }
+        self.items.insert(mi.id().to_string(), group);
+        if mi.is_disabled() {
+          self.disabled.insert(mi.id().to_string());
+        }
+        found.push((mi.id().to_string(), mi.default_checked()));
+      }
+      self.scan(&mi.children, Some(mi.id()), found);
+    }
+  }
+
+  /// Apply the initial states of items, without checking a radio item if
+  /// another item in its group already is checked.
+  fn init(&mut self, found: Vec<(String, bool)>) {
+    for (id, checked) in found {
+      let taken = match self.items[&id] {
+        Some(ref g) => self.groups[g].iter().any(|m| self.checked.contains(m)),
+        None => false
+      };
+      if checked && !taken {
+        self.checked.insert(id);
+      }
+    }
+  }
+
+  /// Register a listener which is called for every change of state.
+  pub fn on_change<F>(&mut self, f: F) -> &mut Self
+  where
+    F: FnMut(&Change) + Send + 'static
+  {
+    self.listeners.push(Box::new(f));
+    self
+  }
+
+  /// Return `true` if the item with the identifier `miid` is checked.
+  pub fn is_checked(&self, miid: &str) -> bool {
+    self.checked.contains(miid)
+  }
+
+  /// Return the identifier of the checked item of the radio group `group`.
+  pub fn selected(&self, group: &str) -> Option<&str> {
+    self
+      .groups
+      .get(&Group::Named(group.to_string()))?
+      .iter()
+      .find(|m| self.checked.contains(*m))
+      .map(String::as_str)
+  }
+
+  /// Check or uncheck an item.  Checking a radio item unchecks the other
+  /// items in its group.  Items that were disabled when the store was
+  /// created, or last synchronized with the menu, can not be changed.
+  ///
+  /// Returns the changes that were made, which are also reported to the
+  /// listeners.
+  pub fn set(
+    &mut self,
+    miid: &str,
+    checked: bool
+  ) -> Result<Vec<Change>, Error> {
+    let group = self
+      .items
+      .get(miid)
+      .ok_or_else(|| Error::NotCheckable(miid.to_string()))?;
+    if self.disabled.contains(miid) {
+      return Err(Error::Disabled(miid.to_string()));
+    }
+
+    let mut changes = Vec::new();
+    match group {
+      Some(_) if !checked && self.checked.contains(miid) => {
+        return Err(Error::UncheckRadio(miid.to_string()));
+      }
+      Some(g) if checked => {
+        for m in &self.groups[g] {
+          if m != miid && self.checked.contains(m) {
+            changes.push(Change {
+              id: m.clone(),
+              checked: false
+            });
+          }
+        }
+      }
+      _ => {}
+    }
+    if self.checked.contains(miid) != checked {
+      changes.push(Change {
+        id: miid.to_string(),
+        checked
+      });
+    }
+    self.commit(&changes);
+    Ok(changes)
+  }
+
+  /// Apply changes and report them to the listeners.
+  fn commit(&mut self, changes: &[Change]) {
+    for ch in changes {
+      if ch.checked {
+        self.checked.insert(ch.id.clone());
+      } else {
+        self.checked.remove(&ch.id);
+      }
+      for f in self.listeners.iter_mut() {
+        f(ch);
+      }
+    }
+  }
+
+  /// Toggle a checkbox item, or check a radio item.  This is what activating
+  /// the item does.
+  pub fn toggle(&mut self, miid: &str) -> Result<Vec<Change>, Error> {
+    let checked = match self.items.get(miid) {
+      Some(None) => !self.is_checked(miid),
+      Some(Some(_)) => true,
+      None => return Err(Error::NotCheckable(miid.to_string()))
+    };
+    self.set(miid, checked)
+  }
+}
+
+/// Return `true` if `mi` is a checkbox or radio item that is checked
+/// according to `store` or, without a store, by default.
+pub(crate) fn is_checked<C>(mi: &MenuItem<C>, store: Option<&Store>) -> bool {
+  mi.is_checkable()
+    && store.map_or(mi.default_checked(), |s| s.is_checked(mi.id()))
+}
+
+/// Generate the text presented for an item by the plain text renderers: the
+/// title, preceded by a check mark for checkable items and followed by the
+/// badge.
+pub(crate) fn label<C>(mi: &MenuItem<C>, store: Option<&Store>) -> String {
+  let checked = is_checked(mi, store);
+  let mark = match (mi.kind(), checked) {
+    (Kind::Checkbox, true) => "[x] ",
+    (Kind::Checkbox, false) => "[ ] ",
+    (Kind::Radio, true) => "(*) ",
+    (Kind::Radio, false) => "( ) ",
+    _ => ""
+  };
+  format!("{}{}", mark, mi.label())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
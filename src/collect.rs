@@ -0,0 +1,216 @@
+//! Reconstruct menus from hierarchical event streams.
+//!
+//! A [`Collector`] is the inverse of the hierarchical iterators: it consumes
+//! `EnterScope`/`MenuItem`/`LeaveScope` events and builds a new [`Menu`]
+//! from them.  This makes it possible to, for instance, filter a menu by
+//! permissions and then pass the result on to any renderer, or to let
+//! applications synthesize menus from their own event streams.
+//!
+//! An `EnterScope` event opens the children of the most recently collected
+//! item.  An `EnterScope` event at the very start of the stream, before any
+//! item, is treated as the scope of the root items (as generated by the
+//! iterators' `root_scope()` option).
+//!
+//! Items are kept in the order they are collected; they are not re-sorted.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::menu::Menu;
+use crate::menuitem::{self, MenuItem};
+use crate::order::Order;
+use crate::{recfiltiter, reciter};
+
+/// Errors that can occur when collecting events into a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// A scope was entered without a preceding menu item to hold it.
+  OrphanScope,
+
+  /// A scope was left without having been entered.
+  UnbalancedLeave,
+
+  /// The event stream ended with scopes still open.
+  Unterminated,
+
+  /// A menu item identifier occurred more than once.
+  DuplicateId(String)
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::OrphanScope => write!(f, "Scope entered without a parent item"),
+      Error::UnbalancedLeave => write!(f, "Scope left without being entered"),
+      Error::Unterminated => write!(f, "Event stream ended inside a scope"),
+      Error::DuplicateId(s) => write!(f, "Duplicate menu item id '{}'", s)
+    }
+  }
+}
+
+
+/// A single event accepted by a [`Collector`].
+pub enum Token<'a, C> {
+  EnterScope,
+  LeaveScope,
+
+  /// An existing menu item.  Its children are not collected; only the
+  /// events that follow it determine its children.
+  MenuItem(&'a MenuItem<C>),
+
+  /// A new menu item.  The builder's parent, if any, is ignored.
+  Builder(Box<menuitem::Builder<C>>)
+}
+
+impl<'a, C> From<reciter::Event<'a, C>> for Token<'a, C> {
+  /// Convert a hierarchical iterator event.  Collapsed items are collected
+  /// without children.
+  fn from(ev: reciter::Event<'a, C>) -> Self {
+    match ev {
+      reciter::Event::EnterScope => Token::EnterScope,
+      reciter::Event::LeaveScope => Token::LeaveScope,
+      reciter::Event::MenuItem(mi) | reciter::Event::Collapsed(mi) => {
+        Token::MenuItem(mi.item)
+      }
+    }
+  }
+}
+
+impl<'a, C> From<recfiltiter::Event<'a, C>> for Token<'a, C> {
+  /// Convert a filtered hierarchical iterator event.  Collapsed items are
+  /// collected without children, and containers as ordinary items.
+  fn from(ev: recfiltiter::Event<'a, C>) -> Self {
+    match ev {
+      recfiltiter::Event::EnterScope => Token::EnterScope,
+      recfiltiter::Event::LeaveScope => Token::LeaveScope,
+      recfiltiter::Event::MenuItem(mi)
+      | recfiltiter::Event::Collapsed(mi)
+      | recfiltiter::Event::Container(mi) => Token::MenuItem(mi.item)
+    }
+  }
+}
+
+impl<C> From<menuitem::Builder<C>> for Token<'_, C> {
+  fn from(mib: menuitem::Builder<C>) -> Self {
+    Token::Builder(Box::new(mib))
+  }
+}
+
+
+struct Level<C> {
+  parent: Option<MenuItem<C>>,
+  items: Vec<MenuItem<C>>
+}
+
+/// Event stream to menu collector.
+pub struct Collector<C> {
+  levels: Vec<Level<C>>,
+  ids: HashSet<String>,
+  seq: usize,
+  root_scope: bool,
+  started: bool
+}
+
+impl<C> Default for Collector<C> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<C> Collector<C> {
+  pub fn new() -> Self {
+    Collector {
+      levels: vec![Level {
+        parent: None,
+        items: Vec::new()
+      }],
+      ids: HashSet::new(),
+      seq: 1,
+      root_scope: false,
+      started: false
+    }
+  }
+
+  /// Feed a single event to the collector.
+  pub fn push<'a, T>(&mut self, ev: T) -> Result<(), Error>
+  where
+    T: Into<Token<'a, C>>,
+    C: Clone + 'a
+  {
+    let started = self.started;
+    self.started = true;
+
+    match ev.into() {
+      Token::EnterScope => {
+        let level = self.levels.last_mut().unwrap();
+        match level.items.pop() {
+          Some(parent) => {
+            self.levels.push(Level {
+              parent: Some(parent),
+              items: Vec::new()
+            });
+          }
+          None if !started => self.root_scope = true,
+          None => return Err(Error::OrphanScope)
+        }
+      }
+      Token::LeaveScope => {
+        if self.levels.len() > 1 {
+          let level = self.levels.pop().unwrap();
+          if let Some(mut parent) = level.parent {
+            parent.children = level.items;
+            self.levels.last_mut().unwrap().items.push(parent);
+          }
+        } else if self.root_scope {
+          self.root_scope = false;
+        } else {
+          return Err(Error::UnbalancedLeave);
+        }
+      }
+      Token::MenuItem(mi) => self.add_item(mi.clone_shallow())?,
+      Token::Builder(mib) => self.add_item(mib.build())?
+    }
+    Ok(())
+  }
+
+  /// Feed all the events of an iterator to the collector.
+  pub fn extend<'a, I, T>(&mut self, it: I) -> Result<(), Error>
+  where
+    I: IntoIterator<Item = T>,
+    T: Into<Token<'a, C>>,
+    C: Clone + 'a
+  {
+    for ev in it {
+      self.push(ev)?;
+    }
+    Ok(())
+  }
+
+  /// Finish collecting and return the new menu.
+  pub fn finish(mut self) -> Result<Menu<C>, Error> {
+    if self.levels.len() != 1 || self.root_scope {
+      return Err(Error::Unterminated);
+    }
+    let level = self.levels.pop().unwrap();
+    Ok(Menu {
+      rootlst: level.items,
+      orphans: HashMap::new(),
+      order: Order::Declaration
+    })
+  }
+
+  /// Add an item to the current level.  Items are declared in the order in
+  /// which they are collected.
+  fn add_item(&mut self, mut mi: MenuItem<C>) -> Result<(), Error> {
+    if !self.ids.insert(mi.id().to_string()) {
+      return Err(Error::DuplicateId(mi.id().to_string()));
+    }
+    mi.seq = self.seq;
+    self.seq += 1;
+    self.levels.last_mut().unwrap().items.push(mi);
+    Ok(())
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
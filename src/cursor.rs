@@ -0,0 +1,402 @@
+//! Keyboard-driven navigation model for tree-style menus.
+//!
+//! A [`MenuCursor`] tracks the selected menu item and the set of expanded
+//! branches, and produces the list of currently visible rows.  It does not
+//! depend on any terminal library; applications map their key events to the
+//! cursor's movement methods and draw the rows returned by
+//! [`MenuCursor::rows()`].
+//!
+//! Separators and headers are returned as rows so that they can be drawn,
+//! but the cursor never selects them, nor disabled items.  Items can also be
+//! disabled through the `enabled` predicates of an action registry, using
+//! [`MenuCursor::enabled()`].
+use std::collections::HashSet;
+
+use crate::action::{self, EnabledFn, Registry};
+use crate::menu::Menu;
+use crate::menuitem::{self, Kind, MenuItem};
+use crate::reciter::Event;
+
+/// A single visible row.
+pub struct Row<'a, C> {
+  pub item: &'a MenuItem<C>,
+
+  /// Nesting depth; root items have depth 0.
+  pub depth: usize,
+
+  /// `true` if the item has children and is expanded.
+  pub expanded: bool,
+
+  /// `true` if this is the selected row.
+  pub selected: bool,
+
+  /// `false` for separators, headers and disabled items, including the ones
+  /// disabled through [`MenuCursor::enabled()`], which the cursor skips.
+  pub focusable: bool
+}
+
+/// Selection and expansion state of a tree-style menu.
+pub struct MenuCursor<'a, C> {
+  menu: &'a Menu<C>,
+  selected: Option<String>,
+  expanded: HashSet<String>,
+  enabled: Option<EnabledFn<'a, C>>,
+  wrap: bool,
+  page_height: usize
+}
+
+impl<'a, C> MenuCursor<'a, C> {
+  /// Create a new cursor with all branches collapsed and the first focusable
+  /// root item selected.
+  pub fn new(menu: &'a Menu<C>) -> Self {
+    let mut cursor = MenuCursor {
+      menu,
+      selected: None,
+      expanded: HashSet::new(),
+      enabled: None,
+      wrap: false,
+      page_height: 10
+    };
+    cursor.selected = cursor
+      .first_focusable(&menu.rootlst)
+      .map(|mi| mi.id().to_string());
+    cursor
+  }
+
+  /// Treat the items that `registry` does not consider enabled in the
+  /// application context `app` as disabled (see
+  /// [`Registry::is_enabled()`]).  If the selected item becomes disabled,
+  /// its closest focusable ancestor is selected instead.
+  pub fn enabled<A, R>(
+    &mut self,
+    registry: &'a Registry<'_, C, A, R>,
+    app: &'a A
+  ) -> &mut Self {
+    self.enabled = Some(registry.enabled_fn(app));
+    let path = match self.selected.as_deref() {
+      Some(sel) => self.menu.path_to(sel).unwrap_or_default(),
+      None => Vec::new()
+    };
+    if !path.is_empty() {
+      self.select_in(&path);
+    } else {
+      self.home();
+    }
+    self
+  }
+
+  /// Make up/down movements wrap around at the first and last rows.
+  pub fn wrap(&mut self, wrap: bool) -> &mut Self {
+    self.wrap = wrap;
+    self
+  }
+
+  /// Set the number of rows moved by page up/down.
+  pub fn page_height(&mut self, n: usize) -> &mut Self {
+    self.page_height = n.max(1);
+    self
+  }
+
+  /// Return the selected menu item.
+  pub fn selected(&self) -> Option<&'a MenuItem<C>> {
+    self
+      .selected
+      .as_deref()
+      .and_then(|miid| self.menu.get(miid))
+  }
+
+  /// Return the identifiers of all expanded items.
+  pub fn expanded(&self) -> &HashSet<String> {
+    &self.expanded
+  }
+
+  /// Return `true` if the item with the identifier `miid` is expanded.
+  pub fn is_expanded(&self, miid: &str) -> bool {
+    self.expanded.contains(miid)
+  }
+
+  /// Return the currently visible rows, in display order.
+  pub fn rows(&self) -> Vec<Row<'a, C>> {
+    let mut rows = Vec::new();
+    self.collect_rows(&self.menu.rootlst, 0, &mut rows);
+    rows
+  }
+
+  /// Return the index of the selected row within the visible rows.
+  pub fn selected_index(&self) -> Option<usize> {
+    self.rows().iter().position(|r| r.selected)
+  }
+
+  /// Select the item with the identifier `miid`, expanding all its ancestors
+  /// so that it becomes visible.  Returns `false` if there is no such item,
+  /// or if it is a separator, header or disabled item.
+  pub fn select(&mut self, miid: &str) -> bool {
+    let path = match self.menu.path_to(miid) {
+      Some(path) if self.is_focusable(path[path.len() - 1]) => path,
+      _ => return false
+    };
+    for mi in &path[..path.len() - 1] {
+      self.expanded.insert(mi.id().to_string());
+    }
+    self.selected = Some(miid.to_string());
+    true
+  }
+
+  pub fn up(&mut self) -> bool {
+    self.move_by(-1, self.wrap)
+  }
+
+  pub fn down(&mut self) -> bool {
+    self.move_by(1, self.wrap)
+  }
+
+  pub fn page_up(&mut self) -> bool {
+    self.move_by(-(self.page_height as isize), false)
+  }
+
+  pub fn page_down(&mut self) -> bool {
+    self.move_by(self.page_height as isize, false)
+  }
+
+  /// Select the first focusable visible row.
+  pub fn home(&mut self) -> bool {
+    let rows = self.rows();
+    match rows.iter().position(|r| r.focusable) {
+      Some(idx) => self.select_row(&rows, idx),
+      None => false
+    }
+  }
+
+  /// Select the last focusable visible row.
+  pub fn end(&mut self) -> bool {
+    let rows = self.rows();
+    match rows.iter().rposition(|r| r.focusable) {
+      Some(idx) => self.select_row(&rows, idx),
+      None => false
+    }
+  }
+
+  /// Collapse the selected item if it is expanded, otherwise select its
+  /// closest focusable ancestor.
+  pub fn left(&mut self) -> bool {
+    let miid = match self.selected.clone() {
+      Some(miid) => miid,
+      None => return false
+    };
+    if self.expanded.remove(&miid) {
+      return true;
+    }
+    let path = match self.menu.path_to(&miid) {
+      Some(path) => path,
+      None => return false
+    };
+    match path[..path.len() - 1]
+      .iter()
+      .rev()
+      .find(|mi| self.is_focusable(mi))
+    {
+      Some(mi) => {
+        self.selected = Some(mi.id().to_string());
+        true
+      }
+      None => false
+    }
+  }
+
+  /// Expand the selected item if it is collapsed, otherwise select its first
+  /// focusable child.
+  pub fn right(&mut self) -> bool {
+    let mi = match self.selected() {
+      Some(mi) => mi,
+      None => return false
+    };
+    let first = match self.first_focusable(&mi.children) {
+      Some(first) => first,
+      None => return false
+    };
+    if self.expanded.insert(mi.id().to_string()) {
+      return true;
+    }
+    self.selected = Some(first.id().to_string());
+    true
+  }
+
+  /// Expand the item with the identifier `miid`.
+  pub fn expand(&mut self, miid: &str) -> bool {
+    match self.menu.get(miid) {
+      Some(mi) if !mi.children.is_empty() => {
+        self.expanded.insert(miid.to_string())
+      }
+      _ => false
+    }
+  }
+
+  /// Collapse the item with the identifier `miid`.
+  ///
+  /// If the selected item is hidden by the collapse, the collapsed item
+  /// becomes selected, or its closest focusable ancestor if it is not
+  /// focusable itself.
+  pub fn collapse(&mut self, miid: &str) -> bool {
+    if !self.expanded.remove(miid) {
+      return false;
+    }
+    let path = match self.selected.as_deref() {
+      Some(sel) => self.menu.path_to(sel).unwrap_or_default(),
+      None => Vec::new()
+    };
+    if let Some(idx) = path[..path.len().saturating_sub(1)]
+      .iter()
+      .position(|mi| mi.id() == miid)
+    {
+      self.select_in(&path[..=idx]);
+    }
+    true
+  }
+
+  /// Toggle the expansion state of the selected item.
+  pub fn toggle(&mut self) -> bool {
+    let miid = match self.selected.clone() {
+      Some(miid) => miid,
+      None => return false
+    };
+    if self.is_expanded(&miid) {
+      self.collapse(&miid)
+    } else {
+      self.expand(&miid)
+    }
+  }
+
+  /// Expand all items that have children.
+  pub fn expand_all(&mut self) {
+    for ev in self.menu.iter_hier() {
+      if let Event::MenuItem(mi) = ev {
+        if !mi.children.is_empty() {
+          self.expanded.insert(mi.id().to_string());
+        }
+      }
+    }
+  }
+
+  /// Collapse all items.  The selection moves to the root item containing
+  /// the selected item, or to the first focusable root item if that item is
+  /// not focusable.
+  pub fn collapse_all(&mut self) {
+    self.expanded.clear();
+    let path = match self.selected.as_deref() {
+      Some(sel) => self.menu.path_to(sel).unwrap_or_default(),
+      None => Vec::new()
+    };
+    if !path.is_empty() {
+      self.select_in(&path[..1]);
+    }
+  }
+
+  /// Select the next focusable visible row, after the selected one, whose
+  /// title starts with the character `ch`.  The search is case insensitive and
+  /// wraps around.
+  pub fn jump_to_letter(&mut self, ch: char) -> bool {
+    let rows = self.rows();
+    if rows.is_empty() {
+      return false;
+    }
+    let start = rows.iter().position(|r| r.selected).map_or(0, |i| i + 1);
+    let needle: Vec<char> = ch.to_lowercase().collect();
+
+    for n in 0..rows.len() {
+      let idx = (start + n) % rows.len();
+      if !rows[idx].focusable {
+        continue;
+      }
+      let first = rows[idx].item.title().chars().next();
+      if let Some(first) = first {
+        if first.to_lowercase().eq(needle.iter().copied()) {
+          return self.select_row(&rows, idx);
+        }
+      }
+    }
+    false
+  }
+
+  fn collect_rows(
+    &self,
+    lst: &'a [MenuItem<C>],
+    depth: usize,
+    rows: &mut Vec<Row<'a, C>>
+  ) {
+    for mi in menuitem::shown(lst, &|_: &MenuItem<C>| true) {
+      let expanded = !mi.children.is_empty() && self.is_expanded(mi.id());
+      rows.push(Row {
+        item: mi,
+        depth,
+        expanded,
+        selected: self.selected.as_deref() == Some(mi.id()),
+        focusable: self.is_focusable(mi)
+      });
+      if expanded {
+        self.collect_rows(&mi.children, depth + 1, rows);
+      }
+    }
+  }
+
+  /// Select the innermost focusable item of `path`, a visible chain of
+  /// items starting at a root item.  If there is none, the first focusable
+  /// row is selected instead.
+  fn select_in(&mut self, path: &[&MenuItem<C>]) {
+    match path.iter().rev().find(|mi| self.is_focusable(mi)) {
+      Some(mi) => self.selected = Some(mi.id().to_string()),
+      None => {
+        self.selected = None;
+        self.home();
+      }
+    }
+  }
+
+  fn select_row(&mut self, rows: &[Row<'a, C>], idx: usize) -> bool {
+    match rows.get(idx) {
+      Some(row) if !row.selected => {
+        self.selected = Some(row.item.id().to_string());
+        true
+      }
+      _ => false
+    }
+  }
+
+  fn move_by(&mut self, delta: isize, wrap: bool) -> bool {
+    let rows = self.rows();
+    let focusable: Vec<usize> =
+      (0..rows.len()).filter(|&i| rows[i].focusable).collect();
+    if focusable.is_empty() {
+      return false;
+    }
+    let len = focusable.len() as isize;
+    let cur = match focusable.iter().position(|&i| rows[i].selected) {
+      Some(idx) => idx as isize,
+      None => return self.select_row(&rows, focusable[0])
+    };
+    let idx = if wrap {
+      (cur + delta).rem_euclid(len)
+    } else {
+      (cur + delta).clamp(0, len - 1)
+    };
+    self.select_row(&rows, focusable[idx as usize])
+  }
+
+  /// Return `true` if the cursor may select `mi`.  Groups are focusable so
+  /// that they can be expanded and collapsed.
+  fn is_focusable(&self, mi: &MenuItem<C>) -> bool {
+    matches!(
+      mi.kind(),
+      Kind::Link | Kind::Checkbox | Kind::Radio | Kind::Group
+    ) && action::is_enabled(mi, &self.enabled)
+  }
+
+  fn first_focusable<'b>(
+    &self,
+    lst: &'b [MenuItem<C>]
+  ) -> Option<&'b MenuItem<C>> {
+    menuitem::shown(lst, &|_: &MenuItem<C>| true)
+      .find(|mi| self.is_focusable(mi))
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,515 @@
+//! Structural differences between two menus.
+//!
+//! [`diff()`] compares two menus item by item, matching items by their
+//! identifiers, and reports what it would take to turn the old menu into the
+//! new one.  The resulting [`Diff`] can be rendered as text, using its
+//! `Display` implementation, or as JSON (with the `json` feature).
+//!
+//! Changes are reported in a stable order: removed items first, in the old
+//! menu's order, followed by the changes to the items of the new menu, in
+//! the new menu's order.
+//!
+//! An item that has been moved to another parent is reported as moved, but
+//! not as reordered.  Among the items that stay with the same parent, the
+//! smallest set of items whose relocation explains the new order is reported
+//! as reordered.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "json")]
+use serde_json::{json, Value};
+
+use crate::accel::Accel;
+use crate::menu::Menu;
+use crate::menuitem::{Kind, MenuItem, Presentation};
+
+/// A single difference between two menus.
+///
+/// Parents are `None` for root items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Change {
+  Added {
+    id: String,
+    parent: Option<String>
+  },
+  Removed {
+    id: String,
+    parent: Option<String>
+  },
+  Moved {
+    id: String,
+    from: Option<String>,
+    to: Option<String>
+  },
+
+  /// The item's position among its siblings changed.  Positions are indexes
+  /// into the parent's list of children.
+  Reordered {
+    id: String,
+    parent: Option<String>,
+    from: usize,
+    to: usize
+  },
+  Retitled {
+    id: String,
+    from: String,
+    to: String
+  },
+  Reweighted {
+    id: String,
+    from: isize,
+    to: isize
+  },
+  KindChanged {
+    id: String,
+    from: Kind,
+    to: Kind
+  },
+  PresentationChanged {
+    id: String,
+    from: Presentation,
+    to: Presentation
+  },
+  AccelChanged {
+    id: String,
+    from: Option<Accel>,
+    to: Option<Accel>
+  },
+
+  /// The item's mnemonic letter changed.  Changes of only the position of
+  /// the mnemonic within the title are not reported.
+  MnemonicChanged {
+    id: String,
+    from: Option<char>,
+    to: Option<char>
+  },
+  ContextChanged {
+    id: String
+  }
+}
+
+impl Change {
+  /// Return the identifier of the menu item the change applies to.
+  pub fn id(&self) -> &str {
+    match self {
+      Change::Added { id, .. }
+      | Change::Removed { id, .. }
+      | Change::Moved { id, .. }
+      | Change::Reordered { id, .. }
+      | Change::Retitled { id, .. }
+      | Change::Reweighted { id, .. }
+      | Change::KindChanged { id, .. }
+      | Change::PresentationChanged { id, .. }
+      | Change::AccelChanged { id, .. }
+      | Change::MnemonicChanged { id, .. }
+      | Change::ContextChanged { id } => id
+    }
+  }
+
+  #[cfg(feature = "json")]
+  fn to_json(&self) -> Value {
+    match self {
+      Change::Added { id, parent } => {
+        json!({ "change": "added", "id": id, "parent": parent })
+      }
+      Change::Removed { id, parent } => {
+        json!({ "change": "removed", "id": id, "parent": parent })
+      }
+      Change::Moved { id, from, to } => {
+        json!({ "change": "moved", "id": id, "from": from, "to": to })
+      }
+      Change::Reordered {
+        id,
+        parent,
+        from,
+        to
+      } => json!({
+        "change": "reordered",
+        "id": id,
+        "parent": parent,
+        "from": from,
+        "to": to
+      }),
+      Change::Retitled { id, from, to } => {
+        json!({ "change": "retitled", "id": id, "from": from, "to": to })
+      }
+      Change::Reweighted { id, from, to } => {
+        json!({ "change": "reweighted", "id": id, "from": from, "to": to })
+      }
+      Change::KindChanged { id, from, to } => json!({
+        "change": "kind",
+        "id": id,
+        "from": from.to_string(),
+        "to": to.to_string()
+      }),
+      Change::PresentationChanged { id, from, to } => json!({
+        "change": "presentation",
+        "id": id,
+        "from": pres_json(from),
+        "to": pres_json(to)
+      }),
+      Change::AccelChanged { id, from, to } => json!({
+        "change": "accel",
+        "id": id,
+        "from": from.as_ref().map(Accel::to_string),
+        "to": to.as_ref().map(Accel::to_string)
+      }),
+      Change::MnemonicChanged { id, from, to } => {
+        json!({ "change": "mnemonic", "id": id, "from": from, "to": to })
+      }
+      Change::ContextChanged { id } => {
+        json!({ "change": "context", "id": id })
+      }
+    }
+  }
+}
+
+#[cfg(feature = "json")]
+fn pres_json(pres: &Presentation) -> Value {
+  json!({
+    "icon": pres.icon,
+    "tooltip": pres.tooltip,
+    "description": pres.description,
+    "badge": pres.badge,
+    "disabled": pres.disabled
+  })
+}
+
+fn parent_str(parent: &Option<String>) -> &str {
+  parent.as_deref().unwrap_or("<root>")
+}
+
+/// Return the names of the presentation fields that differ.
+fn pres_fields(a: &Presentation, b: &Presentation) -> Vec<&'static str> {
+  let mut out = Vec::new();
+  if a.icon != b.icon {
+    out.push("icon");
+  }
+  if a.tooltip != b.tooltip {
+    out.push("tooltip");
+  }
+  if a.description != b.description {
+    out.push("description");
+  }
+  if a.badge != b.badge {
+    out.push("badge");
+  }
+  if a.disabled != b.disabled {
+    out.push("disabled");
+  }
+  out
+}
+
+fn opt_str<T: fmt::Display>(v: &Option<T>) -> String {
+  match v {
+    Some(v) => v.to_string(),
+    None => "none".to_string()
+  }
+}
+
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Change::Added { id, parent } => {
+        write!(f, "+ {} (under {})", id, parent_str(parent))
+      }
+      Change::Removed { id, parent } => {
+        write!(f, "- {} (was under {})", id, parent_str(parent))
+      }
+      Change::Moved { id, from, to } => write!(
+        f,
+        "~ {}: moved from {} to {}",
+        id,
+        parent_str(from),
+        parent_str(to)
+      ),
+      Change::Reordered {
+        id,
+        parent,
+        from,
+        to
+      } => write!(
+        f,
+        "~ {}: reordered under {}, position {} -> {}",
+        id,
+        parent_str(parent),
+        from,
+        to
+      ),
+      Change::Retitled { id, from, to } => {
+        write!(f, "~ {}: title {:?} -> {:?}", id, from, to)
+      }
+      Change::Reweighted { id, from, to } => {
+        write!(f, "~ {}: weight {} -> {}", id, from, to)
+      }
+      Change::KindChanged { id, from, to } => {
+        write!(f, "~ {}: kind {} -> {}", id, from, to)
+      }
+      Change::PresentationChanged { id, from, to } => write!(
+        f,
+        "~ {}: presentation changed ({})",
+        id,
+        pres_fields(from, to).join(", ")
+      ),
+      Change::AccelChanged { id, from, to } => write!(
+        f,
+        "~ {}: accelerator {} -> {}",
+        id,
+        opt_str(from),
+        opt_str(to)
+      ),
+      Change::MnemonicChanged { id, from, to } => {
+        write!(f, "~ {}: mnemonic {} -> {}", id, opt_str(from), opt_str(to))
+      }
+      Change::ContextChanged { id } => write!(f, "~ {}: context changed", id)
+    }
+  }
+}
+
+
+/// The differences between two menus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Diff {
+  pub changes: Vec<Change>
+}
+
+impl Diff {
+  pub fn is_empty(&self) -> bool {
+    self.changes.is_empty()
+  }
+
+  /// Return the changes that apply to the menu item `miid`.
+  pub fn for_item<'a>(
+    &'a self,
+    miid: &'a str
+  ) -> impl Iterator<Item = &'a Change> + 'a {
+    self.changes.iter().filter(move |ch| ch.id() == miid)
+  }
+
+  /// Render the diff as a JSON array of change objects.  Each object has a
+  /// `change` field naming the kind of change (`added`, `removed`, `moved`,
+  /// `reordered`, `retitled`, `reweighted`, `kind`, `presentation`, `accel`,
+  /// `mnemonic` or `context`), an `id` field, and the fields of the
+  /// corresponding [`Change`] variant.  Kinds and accelerators are rendered
+  /// as strings.
+  #[cfg(feature = "json")]
+  pub fn to_json(&self) -> Value {
+    Value::Array(self.changes.iter().map(Change::to_json).collect())
+  }
+}
+
+impl fmt::Display for Diff {
+  /// Render the diff as one change per line.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, ch) in self.changes.iter().enumerate() {
+      if i > 0 {
+        writeln!(f)?;
+      }
+      write!(f, "{}", ch)?;
+    }
+    Ok(())
+  }
+}
+
+
+/// Where an item is located in a menu.
+struct Loc<'a, C> {
+  item: &'a MenuItem<C>,
+  parent: Option<&'a str>,
+  pos: usize
+}
+
+fn index<'a, C>(
+  lst: &'a [MenuItem<C>],
+  parent: Option<&'a str>,
+  order: &mut Vec<&'a str>,
+  idx: &mut HashMap<&'a str, Loc<'a, C>>
+) {
+  for (pos, mi) in lst.iter().enumerate() {
+    order.push(mi.id());
+    idx.insert(
+      mi.id(),
+      Loc {
+        item: mi,
+        parent,
+        pos
+      }
+    );
+    index(&mi.children, Some(mi.id()), order, idx);
+  }
+}
+
+/// Compare two menus.  Contexts are compared using `PartialEq`.
+pub fn diff<C>(old: &Menu<C>, new: &Menu<C>) -> Diff
+where
+  C: PartialEq
+{
+  diff_by(old, new, |a, b| a == b)
+}
+
+/// Compare two menus, using `eq` to determine whether the contexts of two
+/// menu items with the same identifier are equal.
+pub fn diff_by<C, D, F>(old: &Menu<C>, new: &Menu<D>, eq: F) -> Diff
+where
+  F: Fn(&C, &D) -> bool
+{
+  let mut old_order = Vec::new();
+  let mut old_idx = HashMap::new();
+  index(&old.rootlst, None, &mut old_order, &mut old_idx);
+  let mut new_order = Vec::new();
+  let mut new_idx = HashMap::new();
+  index(&new.rootlst, None, &mut new_order, &mut new_idx);
+
+  let reordered = reordered(&new.rootlst, None, &old_idx);
+
+  let owned = |s: Option<&str>| s.map(|s| s.to_string());
+  let mut changes = Vec::new();
+
+  for id in &old_order {
+    if !new_idx.contains_key(id) {
+      changes.push(Change::Removed {
+        id: id.to_string(),
+        parent: owned(old_idx[id].parent)
+      });
+    }
+  }
+
+  for id in &new_order {
+    let n = &new_idx[id];
+    let o = match old_idx.get(id) {
+      Some(o) => o,
+      None => {
+        changes.push(Change::Added {
+          id: id.to_string(),
+          parent: owned(n.parent)
+        });
+        continue;
+      }
+    };
+
+    if o.parent != n.parent {
+      changes.push(Change::Moved {
+        id: id.to_string(),
+        from: owned(o.parent),
+        to: owned(n.parent)
+      });
+    } else if reordered.contains(id) {
+      changes.push(Change::Reordered {
+        id: id.to_string(),
+        parent: owned(n.parent),
+        from: o.pos,
+        to: n.pos
+      });
+    }
+    if o.item.title() != n.item.title() {
+      changes.push(Change::Retitled {
+        id: id.to_string(),
+        from: o.item.title().to_string(),
+        to: n.item.title().to_string()
+      });
+    }
+    if o.item.weight() != n.item.weight() {
+      changes.push(Change::Reweighted {
+        id: id.to_string(),
+        from: o.item.weight(),
+        to: n.item.weight()
+      });
+    }
+    if o.item.kind() != n.item.kind() {
+      changes.push(Change::KindChanged {
+        id: id.to_string(),
+        from: o.item.kind(),
+        to: n.item.kind()
+      });
+    }
+    if o.item.presentation() != n.item.presentation() {
+      changes.push(Change::PresentationChanged {
+        id: id.to_string(),
+        from: o.item.presentation().clone(),
+        to: n.item.presentation().clone()
+      });
+    }
+    if o.item.accel() != n.item.accel() {
+      changes.push(Change::AccelChanged {
+        id: id.to_string(),
+        from: o.item.accel().cloned(),
+        to: n.item.accel().cloned()
+      });
+    }
+    let (om, nm) = (mnemonic(o.item), mnemonic(n.item));
+    if om != nm {
+      changes.push(Change::MnemonicChanged {
+        id: id.to_string(),
+        from: om,
+        to: nm
+      });
+    }
+    if !eq(o.item.appctx(), n.item.appctx()) {
+      changes.push(Change::ContextChanged { id: id.to_string() });
+    }
+  }
+
+  Diff { changes }
+}
+
+fn mnemonic<C>(mi: &MenuItem<C>) -> Option<char> {
+  mi.mnemonic().map(|m| m.key)
+}
+
+/// Find the items of the new menu that kept their parent, but which are not
+/// part of the longest sequence of siblings that kept their relative order.
+fn reordered<'a, C, D>(
+  lst: &'a [MenuItem<D>],
+  parent: Option<&str>,
+  old_idx: &HashMap<&str, Loc<'_, C>>
+) -> HashSet<&'a str> {
+  let mut out = HashSet::new();
+
+  // Old positions of the siblings that were siblings before as well
+  let kept: Vec<(&str, usize)> = lst
+    .iter()
+    .filter_map(|mi| {
+      old_idx
+        .get(mi.id())
+        .filter(|o| o.parent == parent)
+        .map(|o| (mi.id(), o.pos))
+    })
+    .collect();
+
+  // Longest increasing subsequence of old positions; O(n^2) is fine for the
+  // size of a menu level.
+  let n = kept.len();
+  let mut len = vec![1; n];
+  let mut prev = vec![None; n];
+  for i in 0..n {
+    for j in 0..i {
+      if kept[j].1 < kept[i].1 && len[j] + 1 > len[i] {
+        len[i] = len[j] + 1;
+        prev[i] = Some(j);
+      }
+    }
+  }
+  let mut stable = HashSet::new();
+  let mut cur = (0..n).max_by_key(|&i| (len[i], std::cmp::Reverse(i)));
+  while let Some(i) = cur {
+    stable.insert(i);
+    cur = prev[i];
+  }
+  for (i, (id, _)) in kept.iter().enumerate() {
+    if !stable.contains(&i) {
+      out.insert(*id);
+    }
+  }
+
+  for mi in lst {
+    out.extend(reordered(&mi.children, Some(mi.id()), old_idx));
+  }
+  out
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,320 @@
+//! Modify menus after they have been built.
+//!
+//! A built [`Menu`] can be edited in place: items can be inserted, removed,
+//! moved to other parents and retitled or reweighted.  Each operation is
+//! validated before the menu is touched, so a failed operation leaves the
+//! menu unchanged.  Sibling lists are kept sorted in the same order as
+//! [`menu::Builder::build()`](crate::menu::Builder::build) sorts them.
+use std::collections::HashSet;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::menu::Menu;
+use crate::menuitem::{self, MenuItem};
+use crate::order::Order;
+
+/// Errors that can occur when editing a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// The menu item to operate on does not exist.
+  UnknownItem(String),
+
+  /// The requested parent item does not exist.
+  UnknownParent(String),
+
+  /// A menu item with the specified identifier already exists.
+  DuplicateId(String),
+
+  /// The operation would make the menu item an ancestor of itself.
+  Cycle(String),
+
+  /// The menu item can not be removed because it has children.
+  HasChildren(String)
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::UnknownItem(s) => write!(f, "Unknown menu item '{}'", s),
+      Error::UnknownParent(s) => write!(f, "Unknown parent item '{}'", s),
+      Error::DuplicateId(s) => write!(f, "Duplicate menu item id '{}'", s),
+      Error::Cycle(s) => {
+        write!(f, "Menu item '{}' can not be moved into itself", s)
+      }
+      Error::HasChildren(s) => write!(f, "Menu item '{}' has children", s)
+    }
+  }
+}
+
+
+/// What to do with the children of a removed menu item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Removal {
+  /// Remove the children along with the item.
+  Recursive,
+
+  /// Move the children to the removed item's parent.
+  Reparent,
+
+  /// Refuse to remove items that have children.
+  Reject
+}
+
+
+impl<C> Menu<C> {
+  /// Insert a new menu item.
+  ///
+  /// The item is placed under the builder's parent, or among the root items
+  /// if it has none, and is sorted into place among its siblings.
+  pub fn insert(&mut self, mib: menuitem::Builder<C>) -> Result<(), Error> {
+    let parent = mib.parent.clone();
+    self.insert_item(parent.as_deref(), mib.build())
+  }
+
+  /// Insert a menu item, along with all its children, under the item with
+  /// the identifier `parent`, or among the root items if `parent` is `None`.
+  ///
+  /// This can be used to put back an item returned by [`Menu::remove()`],
+  /// which keeps its original position in declaration order.  Items that
+  /// have never been part of a menu are declared after all existing items.
+  pub fn insert_item(
+    &mut self,
+    parent: Option<&str>,
+    mut mi: MenuItem<C>
+  ) -> Result<(), Error> {
+    let mut ids = HashSet::new();
+    collect_ids(&self.rootlst, &mut ids);
+    check_ids(&mi, &mut ids)?;
+
+    let mut next = max_seq(&self.rootlst) + 1;
+    assign_seq(&mut mi, &mut next);
+
+    let lst = children_mut(&mut self.rootlst, parent)?;
+    lst.push(mi);
+    sort(lst, &self.order);
+
+    self.forget_orphans();
+    Ok(())
+  }
+
+  /// Remove the menu item with the identifier `miid` and return it.
+  ///
+  /// `policy` determines what happens to the item's children.  Unless they
+  /// are removed along with it, the returned item has no children.
+  pub fn remove(
+    &mut self,
+    miid: &str,
+    policy: Removal
+  ) -> Result<MenuItem<C>, Error> {
+    let path = self.parent_path(miid)?;
+    let has_children = !self.get(miid).unwrap().children.is_empty();
+    if has_children && policy == Removal::Reject {
+      return Err(Error::HasChildren(miid.to_string()));
+    }
+
+    let parent = path.last().map(String::as_str);
+    let lst = children_mut(&mut self.rootlst, parent)?;
+    let idx = lst.iter().position(|mi| mi.id() == miid).unwrap();
+    let mut mi = lst.remove(idx);
+
+    if policy == Removal::Reparent {
+      lst.append(&mut mi.children);
+      sort(lst, &self.order);
+    }
+    Ok(mi)
+  }
+
+  /// Move the menu item with the identifier `miid`, and its children, to the
+  /// parent `parent`, or to the root items if `parent` is `None`.
+  pub fn move_item(
+    &mut self,
+    miid: &str,
+    parent: Option<&str>
+  ) -> Result<(), Error> {
+    let path = self.parent_path(miid)?;
+    if let Some(parent_id) = parent {
+      match self.path_to(parent_id) {
+        Some(ppath) => {
+          if ppath.iter().any(|mi| mi.id() == miid) {
+            return Err(Error::Cycle(miid.to_string()));
+          }
+        }
+        None => return Err(Error::UnknownParent(parent_id.to_string()))
+      }
+    }
+
+    let old =
+      children_mut(&mut self.rootlst, path.last().map(String::as_str))?;
+    let idx = old.iter().position(|mi| mi.id() == miid).unwrap();
+    let mi = old.remove(idx);
+
+    let lst = children_mut(&mut self.rootlst, parent)?;
+    lst.push(mi);
+    sort(lst, &self.order);
+    Ok(())
+  }
+
+  /// Change the title of the menu item with the identifier `miid`.
+  pub fn set_title<T: ToString>(
+    &mut self,
+    miid: &str,
+    title: T
+  ) -> Result<(), Error> {
+    let title = title.to_string();
+    self.update(miid, |mi| mi.set_title(title))
+  }
+
+  /// Change the weight of the menu item with the identifier `miid`.
+  pub fn set_weight(
+    &mut self,
+    miid: &str,
+    weight: isize
+  ) -> Result<(), Error> {
+    self.update(miid, |mi| mi.set_weight(weight))
+  }
+
+  /// Modify an item using `f` and restore the order of its siblings.
+  fn update<F>(&mut self, miid: &str, f: F) -> Result<(), Error>
+  where
+    F: FnOnce(&mut MenuItem<C>)
+  {
+    let path = self.parent_path(miid)?;
+    let lst =
+      children_mut(&mut self.rootlst, path.last().map(String::as_str))?;
+    if let Some(mi) = lst.iter_mut().find(|mi| mi.id() == miid) {
+      f(mi);
+    }
+    sort(lst, &self.order);
+    Ok(())
+  }
+
+  /// Replace the sibling ordering strategy, and reorder all lists of
+  /// siblings accordingly.
+  pub fn set_order(&mut self, order: Order<C>) {
+    self.order = order;
+    sort_all(&mut self.rootlst, &self.order);
+  }
+
+  /// Look up a menu item by its identifier, for modification.
+  ///
+  /// Only the item's context and presentation metadata can be modified
+  /// through the returned reference (see [`MenuItem::appctx_mut()`] and
+  /// [`MenuItem::presentation_mut()`]); use the other editing methods to
+  /// change its structure.
+  pub fn get_mut(&mut self, miid: &str) -> Option<&mut MenuItem<C>> {
+    find_mut(&mut self.rootlst, miid)
+  }
+
+  /// Return the identifiers of the ancestors of an item, topmost first.
+  fn parent_path(&self, miid: &str) -> Result<Vec<String>, Error> {
+    let mut path: Vec<String> = self
+      .path_to(miid)
+      .ok_or_else(|| Error::UnknownItem(miid.to_string()))?
+      .iter()
+      .map(|mi| mi.id().to_string())
+      .collect();
+    path.pop();
+    Ok(path)
+  }
+
+  /// Items that are part of the menu are no longer orphans.
+  fn forget_orphans(&mut self) {
+    if self.orphans.is_empty() {
+      return;
+    }
+    let mut ids = HashSet::new();
+    collect_ids(&self.rootlst, &mut ids);
+    self.orphans.retain(|id, _| !ids.contains(id));
+  }
+}
+
+/// Restore the order of a list of siblings.  As with
+/// [`menu::Builder::build()`](crate::menu::Builder::build), contradictory
+/// placement constraints are ignored.
+fn sort<C>(lst: &mut Vec<MenuItem<C>>, order: &Order<C>) {
+  let _ = menuitem::sort_siblings(lst, order);
+}
+
+fn sort_all<C>(lst: &mut Vec<MenuItem<C>>, order: &Order<C>) {
+  for mi in lst.iter_mut() {
+    sort_all(&mut mi.children, order);
+  }
+  sort(lst, order);
+}
+
+/// Return the list of children of `parent`, or the root list if `parent` is
+/// `None`.
+fn children_mut<'a, C>(
+  rootlst: &'a mut Vec<MenuItem<C>>,
+  parent: Option<&str>
+) -> Result<&'a mut Vec<MenuItem<C>>, Error> {
+  match parent {
+    Some(parent_id) => find_mut(rootlst, parent_id)
+      .map(|mi| &mut mi.children)
+      .ok_or_else(|| Error::UnknownParent(parent_id.to_string())),
+    None => Ok(rootlst)
+  }
+}
+
+/// Give an item, and its children, that have not been part of a menu yet
+/// the declaration sequence numbers starting at `next`.
+fn assign_seq<C>(mi: &mut MenuItem<C>, next: &mut usize) {
+  if mi.seq == 0 {
+    mi.seq = *next;
+    *next += 1;
+  }
+  for child in &mut mi.children {
+    assign_seq(child, next);
+  }
+}
+
+/// Return the highest declaration sequence number in use.
+fn max_seq<C>(lst: &[MenuItem<C>]) -> usize {
+  lst
+    .iter()
+    .map(|mi| mi.seq.max(max_seq(&mi.children)))
+    .max()
+    .unwrap_or(0)
+}
+
+fn find_mut<'a, C>(
+  lst: &'a mut [MenuItem<C>],
+  miid: &str
+) -> Option<&'a mut MenuItem<C>> {
+  for mi in lst {
+    if mi.id() == miid {
+      return Some(mi);
+    }
+    if let Some(found) = find_mut(&mut mi.children, miid) {
+      return Some(found);
+    }
+  }
+  None
+}
+
+fn collect_ids<C>(lst: &[MenuItem<C>], ids: &mut HashSet<String>) {
+  for mi in lst {
+    ids.insert(mi.id().to_string());
+    collect_ids(&mi.children, ids);
+  }
+}
+
+fn check_ids<C>(
+  mi: &MenuItem<C>,
+  ids: &mut HashSet<String>
+) -> Result<(), Error> {
+  if !ids.insert(mi.id().to_string()) {
+    return Err(Error::DuplicateId(mi.id().to_string()));
+  }
+  for child in &mi.children {
+    check_ids(child, ids)?;
+  }
+  Ok(())
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -1,26 +1,17 @@
 use crate::menuitem;
 
-pub struct MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+pub struct MenuIter<'a, C> {
   lst: &'a Vec<menuitem::MenuItem<C>>,
   idx: usize
 }
 
-impl<'a, C> MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+impl<'a, C> MenuIter<'a, C> {
   pub fn new(cont: &'a Vec<menuitem::MenuItem<C>>) -> Self {
     MenuIter { lst: cont, idx: 0 }
   }
 }
 
-impl<'a, C> Iterator for MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+impl<'a, C> Iterator for MenuIter<'a, C> {
   type Item = &'a menuitem::MenuItem<C>;
 
   fn next(&mut self) -> Option<Self::Item> {
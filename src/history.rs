@@ -0,0 +1,417 @@
+//! Undoable menu edits.
+//!
+//! An [`Editor`] owns a [`Menu`] and applies [`Command`]s to it.  Every
+//! applied command is recorded along with the information needed to reverse
+//! it, so changes can be undone and redone.  Commands can be grouped into
+//! transactions, which are undone and redone as a unit.
+//!
+//! The commands that make up the current state of the menu are available as
+//! a change log through [`Editor::log()`].  With the `serde` feature enabled
+//! the log can be serialized, and later be replayed on another copy of the
+//! original menu using [`replay()`].
+use std::mem;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::accel::Accel;
+use crate::edit::{Error, Removal};
+use crate::menu::Menu;
+use crate::menuitem::{self, Kind, MenuItem, Presentation};
+
+/// A single structural change to a menu.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Command<C> {
+  /// Add a new menu item, along with its children, under `parent`, or among
+  /// the root items if `parent` is `None`.
+  Add {
+    parent: Option<String>,
+    item: Box<MenuItem<C>>
+  },
+
+  /// Remove a menu item.
+  Remove { id: String, policy: Removal },
+
+  /// Move a menu item to a new parent, or to the root if `parent` is `None`.
+  Move { id: String, parent: Option<String> },
+
+  /// Change the title of a menu item.
+  Retitle { id: String, title: String },
+
+  /// Change the weight of a menu item.
+  Reweight { id: String, weight: isize },
+
+  /// Replace the application context of a menu item.
+  SetContext { id: String, ctx: C },
+
+  /// Change the kind of a menu item.
+  SetKind { id: String, kind: Kind },
+
+  /// Replace the presentation metadata of a menu item.
+  SetPresentation {
+    id: String,
+    presentation: Presentation
+  },
+
+  /// Replace, or with `None` remove, the accelerator of a menu item.
+  SetAccel { id: String, accel: Option<Accel> }
+}
+
+impl<C> Command<C> {
+  /// Return a command which adds the menu item described by `mib`.
+  pub fn add(mib: menuitem::Builder<C>) -> Self {
+    Command::Add {
+      parent: mib.parent.clone(),
+      item: Box::new(mib.build())
+    }
+  }
+
+  /// Apply the command to `menu`.
+  pub fn apply(self, menu: &mut Menu<C>) -> Result<(), Error> {
+    Op::from(self).apply(menu).map(|_| ())
+  }
+}
+
+/// Apply all the commands in `cmds` to `menu`, in order.
+///
+/// Stops at the first command that fails; the commands before it remain
+/// applied.
+pub fn replay<C, I>(menu: &mut Menu<C>, cmds: I) -> Result<(), Error>
+where
+  I: IntoIterator<Item = Command<C>>
+{
+  for cmd in cmds {
+    cmd.apply(menu)?;
+  }
+  Ok(())
+}
+
+
+/// Primitive operations.  Applying an operation yields the operation that
+/// reverses it.
+enum Op<C> {
+  /// Insert a menu item, with its children, and then move the items in
+  /// `adopt` under it.
+  Insert {
+    parent: Option<String>,
+    item: Box<MenuItem<C>>,
+    adopt: Vec<String>
+  },
+  Remove {
+    id: String,
+    policy: Removal
+  },
+  Move {
+    id: String,
+    parent: Option<String>
+  },
+  Retitle {
+    id: String,
+    title: String
+  },
+  Reweight {
+    id: String,
+    weight: isize
+  },
+  SetContext {
+    id: String,
+    ctx: C
+  },
+  SetKind {
+    id: String,
+    kind: Kind
+  },
+  SetPresentation {
+    id: String,
+    presentation: Presentation
+  },
+  SetAccel {
+    id: String,
+    accel: Option<Accel>
+  }
+}
+
+impl<C> From<Command<C>> for Op<C> {
+  fn from(cmd: Command<C>) -> Self {
+    match cmd {
+      Command::Add { parent, item } => Op::Insert {
+        parent,
+        item,
+        adopt: Vec::new()
+      },
+      Command::Remove { id, policy } => Op::Remove { id, policy },
+      Command::Move { id, parent } => Op::Move { id, parent },
+      Command::Retitle { id, title } => Op::Retitle { id, title },
+      Command::Reweight { id, weight } => Op::Reweight { id, weight },
+      Command::SetContext { id, ctx } => Op::SetContext { id, ctx },
+      Command::SetKind { id, kind } => Op::SetKind { id, kind },
+      Command::SetPresentation { id, presentation } => {
+        Op::SetPresentation { id, presentation }
+      }
+      Command::SetAccel { id, accel } => Op::SetAccel { id, accel }
+    }
+  }
+}
+
+impl<C> Op<C> {
+  fn apply(self, menu: &mut Menu<C>) -> Result<Op<C>, Error> {
+    match self {
+      Op::Insert {
+        parent,
+        item,
+        adopt
+      } => {
+        let id = item.id().to_string();
+        menu.insert_item(parent.as_deref(), *item)?;
+        for child in &adopt {
+          menu.move_item(child, Some(&id))?;
+        }
+        let policy = if adopt.is_empty() {
+          Removal::Recursive
+        } else {
+          Removal::Reparent
+        };
+        Ok(Op::Remove { id, policy })
+      }
+      Op::Remove { id, policy } => {
+        let parent = parent_of(menu, &id)?;
+        let adopt = match policy {
+          Removal::Reparent => menu
+            .get(&id)
+            .map(|mi| mi.children.iter().map(|c| c.id().to_string()))
+            .into_iter()
+            .flatten()
+            .collect(),
+          _ => Vec::new()
+        };
+        let item = Box::new(menu.remove(&id, policy)?);
+        Ok(Op::Insert {
+          parent,
+          item,
+          adopt
+        })
+      }
+      Op::Move { id, parent } => {
+        let old = parent_of(menu, &id)?;
+        menu.move_item(&id, parent.as_deref())?;
+        Ok(Op::Move { id, parent: old })
+      }
+      Op::Retitle { id, title } => {
+        let old = get(menu, &id)?.title().to_string();
+        menu.set_title(&id, title)?;
+        Ok(Op::Retitle { id, title: old })
+      }
+      Op::Reweight { id, weight } => {
+        let old = get(menu, &id)?.weight();
+        menu.set_weight(&id, weight)?;
+        Ok(Op::Reweight { id, weight: old })
+      }
+      Op::SetContext { id, ctx } => {
+        let old = mem::replace(get_mut(menu, &id)?.appctx_mut(), ctx);
+        Ok(Op::SetContext { id, ctx: old })
+      }
+      Op::SetKind { id, kind } => {
+        let mi = get_mut(menu, &id)?;
+        let old = mi.kind();
+        mi.set_kind(kind);
+        Ok(Op::SetKind { id, kind: old })
+      }
+      Op::SetPresentation { id, presentation } => {
+        let old =
+          mem::replace(get_mut(menu, &id)?.presentation_mut(), presentation);
+        Ok(Op::SetPresentation {
+          id,
+          presentation: old
+        })
+      }
+      Op::SetAccel { id, accel } => {
+        let mi = get_mut(menu, &id)?;
+        let old = mi.accel().cloned();
+        mi.set_accel(accel);
+        Ok(Op::SetAccel { id, accel: old })
+      }
+    }
+  }
+}
+
+fn get<'a, C>(
+  menu: &'a Menu<C>,
+  miid: &str
+) -> Result<&'a MenuItem<C>, Error> {
+  menu
+    .get(miid)
+    .ok_or_else(|| Error::UnknownItem(miid.to_string()))
+}
+
+fn get_mut<'a, C>(
+  menu: &'a mut Menu<C>,
+  miid: &str
+) -> Result<&'a mut MenuItem<C>, Error> {
+  menu
+    .get_mut(miid)
+    .ok_or_else(|| Error::UnknownItem(miid.to_string()))
+}
+
+fn parent_of<C>(menu: &Menu<C>, miid: &str) -> Result<Option<String>, Error> {
+  let mut path = menu
+    .path_to(miid)
+    .ok_or_else(|| Error::UnknownItem(miid.to_string()))?;
+  path.pop();
+  Ok(path.last().map(|mi| mi.id().to_string()))
+}
+
+
+struct Step<C> {
+  cmd: Command<C>,
+  inverse: Op<C>
+}
+
+/// Menu editor with undo and redo.
+pub struct Editor<C> {
+  menu: Menu<C>,
+  done: Vec<Vec<Step<C>>>,
+  undone: Vec<Vec<Step<C>>>,
+  open: Option<Vec<Step<C>>>
+}
+
+impl<C> Editor<C> {
+  pub fn new(menu: Menu<C>) -> Self {
+    Editor {
+      menu,
+      done: Vec::new(),
+      undone: Vec::new(),
+      open: None
+    }
+  }
+
+  /// Return a reference to the edited menu.
+  pub fn menu(&self) -> &Menu<C> {
+    &self.menu
+  }
+
+  /// Consume the editor and return the edited menu.
+  pub fn into_menu(self) -> Menu<C> {
+    self.menu
+  }
+
+  /// Apply a command to the menu.
+  ///
+  /// Unless a transaction is open, the command forms a transaction of its
+  /// own.  Applying a command discards all undone transactions.  A command
+  /// that fails leaves the menu unchanged and is not recorded.
+  pub fn apply(&mut self, cmd: Command<C>) -> Result<(), Error>
+  where
+    C: Clone
+  {
+    let inverse = Op::from(cmd.clone()).apply(&mut self.menu)?;
+    let step = Step { cmd, inverse };
+    match self.open {
+      Some(ref mut steps) => steps.push(step),
+      None => self.done.push(vec![step])
+    }
+    self.undone.clear();
+    Ok(())
+  }
+
+  /// Start a transaction.  All commands applied until the transaction is
+  /// committed are undone and redone as a unit.
+  ///
+  /// Does nothing if a transaction is already open.
+  pub fn begin(&mut self) {
+    if self.open.is_none() {
+      self.open = Some(Vec::new());
+    }
+  }
+
+  /// Finish the open transaction.
+  pub fn commit(&mut self) {
+    if let Some(steps) = self.open.take() {
+      if !steps.is_empty() {
+        self.done.push(steps);
+      }
+    }
+  }
+
+  /// Reverse all the commands of the open transaction and close it.
+  pub fn rollback(&mut self) -> Result<(), Error> {
+    if let Some(steps) = self.open.take() {
+      revert(&mut self.menu, steps)?;
+    }
+    Ok(())
+  }
+
+  /// Return `true` if a transaction is open.
+  pub fn in_transaction(&self) -> bool {
+    self.open.is_some()
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.done.is_empty()
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.undone.is_empty()
+  }
+
+  /// Undo the most recent transaction.  Any open transaction is committed
+  /// first.
+  ///
+  /// Returns `false` if there was nothing to undo.
+  pub fn undo(&mut self) -> Result<bool, Error> {
+    self.commit();
+    let steps = match self.done.pop() {
+      Some(steps) => steps,
+      None => return Ok(false)
+    };
+    let steps = revert(&mut self.menu, steps)?;
+    self.undone.push(steps);
+    Ok(true)
+  }
+
+  /// Redo the most recently undone transaction.
+  ///
+  /// Returns `false` if there was nothing to redo.
+  pub fn redo(&mut self) -> Result<bool, Error> {
+    self.commit();
+    let steps = match self.undone.pop() {
+      Some(steps) => steps,
+      None => return Ok(false)
+    };
+    // Undone transactions are stored in reverse order
+    let steps = revert(&mut self.menu, steps)?;
+    self.done.push(steps);
+    Ok(true)
+  }
+
+  /// Return the commands that have been applied to the original menu to
+  /// bring it to its current state, in order.
+  pub fn log(&self) -> Vec<&Command<C>> {
+    self
+      .done
+      .iter()
+      .chain(self.open.iter())
+      .flatten()
+      .map(|step| &step.cmd)
+      .collect()
+  }
+}
+
+/// Apply the inverse of `steps` in reverse order, and return the steps that
+/// reverse the reversal (in reverse order as well).
+fn revert<C>(
+  menu: &mut Menu<C>,
+  steps: Vec<Step<C>>
+) -> Result<Vec<Step<C>>, Error> {
+  let mut out = Vec::with_capacity(steps.len());
+  for step in steps.into_iter().rev() {
+    let inverse = step.inverse.apply(menu)?;
+    out.push(Step {
+      cmd: step.cmd,
+      inverse
+    });
+  }
+  Ok(out)
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,244 @@
+//! JSON export of menus, intended for single page application frontends.
+//!
+//! Two shapes are supported.  The _tree_ form is an array of root nodes,
+//! where each node has the layout:
+//!
+//! ```text
+//! {
+//!   "id": "lib-kb",
+//!   "title": "Knowledge Base",
+//!   "kind": "link",
+//!   "icon": "book",
+//!   "tooltip": null,
+//!   "description": null,
+//!   "badge": "3",
+//!   "disabled": false,
+//!   "depth": 1,
+//!   "path": ["lib"],
+//!   "active": false,
+//!   "expanded": true,
+//!   "ctx": <value returned by the context projection>,
+//!   "children": [ <node>, ... ]
+//! }
+//! ```
+//!
+//! `path` is the list of ancestor identifiers, starting with the root item.
+//! `depth` is the length of `path`.  `kind` is one of `link`, `header`,
+//! `separator`, `group`, `checkbox` and `radio`.  The presentation fields
+//! are `null` if they are not set.  Checkbox and radio items have two extra
+//! fields: `checked`, and `group` with the name of the radio group, if any.
+//!
+//! The _flat_ (normalized) form stores every node once, keyed by its
+//! identifier, and refers to children by identifier:
+//!
+//! ```text
+//! {
+//!   "nodes": {
+//!     "lib": { "id": "lib", ..., "parent": null, "children": ["lib-kb"] },
+//!     "lib-kb": { "id": "lib-kb", ..., "parent": "lib", "children": [] }
+//!   },
+//!   "roots": ["lib"]
+//! }
+//! ```
+//!
+//! The order of the `children` and `roots` arrays is the menu order.
+//! Separators, headers and groups with nothing to separate, introduce or
+//! group are left out of both forms.
+use serde_json::{json, Map, Value};
+
+use crate::action::{self, EnabledFn, Registry};
+use crate::checks::{self, Store};
+use crate::menu::Menu;
+use crate::menuitem::{self, MenuItem};
+
+/// Menu to JSON exporter.
+///
+/// The context projection is used to generate the `ctx` field of each node
+/// from the menu item's application context.
+pub struct Exporter<'a, C, F>
+where
+  F: Fn(&C) -> Value
+{
+  menu: &'a Menu<C>,
+  ctxfn: F,
+  active: Option<String>,
+  expand_all: bool,
+  checks: Option<&'a Store>,
+  enabled: Option<EnabledFn<'a, C>>
+}
+
+impl<'a, C, F> Exporter<'a, C, F>
+where
+  F: Fn(&C) -> Value
+{
+  pub fn new(menu: &'a Menu<C>, ctxfn: F) -> Self {
+    Exporter {
+      menu,
+      ctxfn,
+      active: None,
+      expand_all: false,
+      checks: None,
+      enabled: None
+    }
+  }
+
+  /// Mark a menu item as the active one.
+  ///
+  /// The active item gets its `active` flag set, and it and all its
+  /// ancestors get their `expanded` flag set (as long as they have children).
+  pub fn active<M: ToString>(&mut self, miid: M) -> &mut Self {
+    self.active = Some(miid.to_string());
+    self
+  }
+
+  /// Set the `expanded` flag on all items that have children, regardless of
+  /// the active trail.
+  pub fn expand_all(&mut self) -> &mut Self {
+    self.expand_all = true;
+    self
+  }
+
+  /// Take the `checked` fields of checkbox and radio items from `store`.
+  /// Without a store, the items' initial states are used.
+  pub fn checks(&mut self, store: &'a Store) -> &mut Self {
+    self.checks = Some(store);
+    self
+  }
+
+  /// Set the `disabled` fields of the items that `registry` does not consider
+  /// enabled in the application context `app` (see
+  /// [`Registry::is_enabled()`]).  Without a registry, the items' own
+  /// disabled flags are used.
+  pub fn enabled<A, R>(
+    &mut self,
+    registry: &'a Registry<'_, C, A, R>,
+    app: &'a A
+  ) -> &mut Self {
+    self.enabled = Some(registry.enabled_fn(app));
+    self
+  }
+
+  /// Generate the tree form of the menu.
+  pub fn tree(&self) -> Value {
+    let trail = self.active_trail();
+    let mut path = Vec::new();
+    Value::Array(self.tree_nodes(&self.menu.rootlst, &mut path, &trail))
+  }
+
+  /// Generate the flat, normalized, form of the menu.
+  pub fn flat(&self) -> Value {
+    let trail = self.active_trail();
+    let mut nodes = Map::new();
+    let mut path = Vec::new();
+    self.flat_nodes(&self.menu.rootlst, &mut path, &trail, &mut nodes);
+
+    let roots: Vec<Value> = menuitem::shown(&self.menu.rootlst, &all)
+      .map(|mi| json!(mi.id()))
+      .collect();
+
+    json!({
+      "nodes": nodes,
+      "roots": roots
+    })
+  }
+
+  fn tree_nodes(
+    &self,
+    lst: &[MenuItem<C>],
+    path: &mut Vec<String>,
+    trail: &[String]
+  ) -> Vec<Value> {
+    let mut out = Vec::new();
+    for mi in menuitem::shown(lst, &all) {
+      let mut node = self.node(mi, path, trail);
+
+      path.push(mi.id().to_string());
+      let children = self.tree_nodes(&mi.children, path, trail);
+      path.pop();
+
+      node.insert("children".to_string(), Value::Array(children));
+      out.push(Value::Object(node));
+    }
+    out
+  }
+
+  fn flat_nodes(
+    &self,
+    lst: &[MenuItem<C>],
+    path: &mut Vec<String>,
+    trail: &[String],
+    nodes: &mut Map<String, Value>
+  ) {
+    for mi in menuitem::shown(lst, &all) {
+      let mut node = self.node(mi, path, trail);
+
+      let parent = match path.last() {
+        Some(id) => json!(id),
+        None => Value::Null
+      };
+      let children: Vec<Value> = menuitem::shown(&mi.children, &all)
+        .map(|c| json!(c.id()))
+        .collect();
+      node.insert("parent".to_string(), parent);
+      node.insert("children".to_string(), Value::Array(children));
+      nodes.insert(mi.id().to_string(), Value::Object(node));
+
+      path.push(mi.id().to_string());
+      self.flat_nodes(&mi.children, path, trail, nodes);
+      path.pop();
+    }
+  }
+
+  /// Generate the fields that are common to both forms.
+  fn node(
+    &self,
+    mi: &MenuItem<C>,
+    path: &[String],
+    trail: &[String]
+  ) -> Map<String, Value> {
+    let active = self.active.as_deref() == Some(mi.id());
+    let expanded = !mi.children.is_empty()
+      && (self.expand_all || trail.iter().any(|id| id == mi.id()));
+
+    let mut node = Map::new();
+    node.insert("id".to_string(), json!(mi.id()));
+    node.insert("title".to_string(), json!(mi.title()));
+    node.insert("kind".to_string(), json!(mi.kind().to_string()));
+    let pres = mi.presentation();
+    node.insert("icon".to_string(), json!(pres.icon));
+    node.insert("tooltip".to_string(), json!(pres.tooltip));
+    node.insert("description".to_string(), json!(pres.description));
+    node.insert("badge".to_string(), json!(pres.badge));
+    node.insert(
+      "disabled".to_string(),
+      json!(!action::is_enabled(mi, &self.enabled))
+    );
+    if mi.is_checkable() {
+      let checked = checks::is_checked(mi, self.checks);
+      node.insert("checked".to_string(), json!(checked));
+      node.insert("group".to_string(), json!(mi.radio_group()));
+    }
+    node.insert("depth".to_string(), json!(path.len()));
+    node.insert("path".to_string(), json!(path));
+    node.insert("active".to_string(), json!(active));
+    node.insert("expanded".to_string(), json!(expanded));
+    node.insert("ctx".to_string(), (self.ctxfn)(mi.appctx()));
+    node
+  }
+
+  /// Return the identifiers of the active item and all its ancestors.
+  fn active_trail(&self) -> Vec<String> {
+    self
+      .active
+      .as_deref()
+      .and_then(|miid| self.menu.path_to(miid))
+      .map(|path| path.iter().map(|mi| mi.id().to_string()).collect())
+      .unwrap_or_default()
+  }
+}
+
+fn all<C>(_: &MenuItem<C>) -> bool {
+  true
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
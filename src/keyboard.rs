@@ -0,0 +1,390 @@
+//! Inline keyboard layouts for chat bots.
+//!
+//! A [`Builder`] turns one level of a menu into rows of buttons, in the form
+//! used by Telegram-style inline keyboards:
+//!
+//! ```text
+//! { "inline_keyboard": [ [ { "text": "...", "callback_data": "..." } ] ] }
+//! ```
+//!
+//! Each button carries a compact callback payload, which is mapped back to a
+//! menu item or level using [`Builder::decode()`].  Payloads have one of the
+//! following forms, optionally preceded by an application defined prefix:
+//!
+//! - `i:<id>` -- the menu item with the identifier `<id>` was pressed.
+//! - `l:<page>:<id>` -- show page `<page>` of the children of `<id>`.  An
+//!   empty `<id>` refers to the root level.
+//!
+//! If a payload would exceed the configured size limit, the identifier is
+//! replaced by the item's position in the menu (dot-separated child indexes)
+//! and the upper case `I` and `L` tags are used instead.  Positional payloads
+//! are only valid for as long as the menu's structure is unchanged.
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::action::{self, EnabledFn, Registry};
+use crate::checks::{self, Store};
+use crate::menu::Menu;
+use crate::menuitem::{self, Kind, MenuItem};
+
+/// Errors that can occur when generating a keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// The requested level does not exist in the menu.
+  UnknownItem(String),
+
+  /// A callback payload for the item with the specified identifier can not be
+  /// made to fit within the payload size limit.
+  PayloadTooLong(String)
+}
+
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::UnknownItem(s) => write!(f, "Unknown menu item '{}'", s),
+      Error::PayloadTooLong(s) => {
+        write!(f, "Callback payload for '{}' is too long", s)
+      }
+    }
+  }
+}
+
+/// A single keyboard button.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Button {
+  pub text: String,
+  pub callback: String
+}
+
+/// Rows of keyboard buttons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyboard {
+  pub rows: Vec<Vec<Button>>
+}
+
+impl Keyboard {
+  /// Generate the `inline_keyboard` JSON structure.
+  pub fn to_json(&self) -> Value {
+    let rows: Vec<Value> = self
+      .rows
+      .iter()
+      .map(|row| {
+        Value::Array(
+          row
+            .iter()
+            .map(|b| json!({ "text": b.text, "callback_data": b.callback }))
+            .collect()
+        )
+      })
+      .collect();
+    json!({ "inline_keyboard": rows })
+  }
+}
+
+/// A decoded callback payload.
+pub enum Callback<'a, C> {
+  /// A menu item button was pressed.
+  Item(&'a MenuItem<C>),
+
+  /// A navigation button was pressed, and the application should present the
+  /// specified page of a level.  `parent` is `None` for the root level.
+  Level {
+    parent: Option<&'a MenuItem<C>>,
+    page: usize
+  }
+}
+
+/// Keyboard layout generator.
+pub struct Builder<'a, C> {
+  menu: &'a Menu<C>,
+  checks: Option<&'a Store>,
+  enabled: Option<EnabledFn<'a, C>>,
+  prefix: String,
+  columns: usize,
+  page_size: Option<usize>,
+  max_payload: usize,
+  back_title: String,
+  home_title: String,
+  prev_title: String,
+  next_title: String
+}
+
+impl<'a, C> Builder<'a, C> {
+  /// Create a new keyboard layout generator.
+  ///
+  /// By default a single column is used, levels are not paginated and
+  /// payloads are limited to 64 bytes.
+  pub fn new(menu: &'a Menu<C>) -> Self {
+    Builder {
+      menu,
+      checks: None,
+      enabled: None,
+      prefix: String::new(),
+      columns: 1,
+      page_size: None,
+      max_payload: 64,
+      back_title: "« Back".to_string(),
+      home_title: "Home".to_string(),
+      prev_title: "‹ Prev".to_string(),
+      next_title: "Next ›".to_string()
+    }
+  }
+
+  /// Set a prefix to put in front of all callback payloads, to be able to
+  /// tell them apart from other callbacks in the application.
+  pub fn prefix<P: ToString>(&mut self, prefix: P) -> &mut Self {
+    self.prefix = prefix.to_string();
+    self
+  }
+
+  /// Show the state of checkbox and radio items as recorded in `store`.
+  /// Without a store, the items' initial states are shown.
+  pub fn checks(&mut self, store: &'a Store) -> &mut Self {
+    self.checks = Some(store);
+    self
+  }
+
+  /// Treat the items that `registry` does not consider enabled in the
+  /// application context `app` as disabled (see
+  /// [`Registry::is_enabled()`]).
+  pub fn enabled<A, R>(
+    &mut self,
+    registry: &'a Registry<'_, C, A, R>,
+    app: &'a A
+  ) -> &mut Self {
+    self.enabled = Some(registry.enabled_fn(app));
+    self
+  }
+
+  /// Set the number of item buttons per row.
+  pub fn columns(&mut self, n: usize) -> &mut Self {
+    self.columns = n.max(1);
+    self
+  }
+
+  /// Set the maximum number of item buttons per page.
+  pub fn page_size(&mut self, n: usize) -> &mut Self {
+    self.page_size = Some(n.max(1));
+    self
+  }
+
+  /// Set the maximum length, in bytes, of callback payloads.
+  pub fn max_payload(&mut self, n: usize) -> &mut Self {
+    self.max_payload = n;
+    self
+  }
+
+  /// Set the titles of the navigation buttons.
+  pub fn nav_titles<B, H, P, N>(
+    &mut self,
+    back: B,
+    home: H,
+    prev: P,
+    next: N
+  ) -> &mut Self
+  where
+    B: ToString,
+    H: ToString,
+    P: ToString,
+    N: ToString
+  {
+    self.back_title = back.to_string();
+    self.home_title = home.to_string();
+    self.prev_title = prev.to_string();
+    self.next_title = next.to_string();
+    self
+  }
+
+  /// Generate the keyboard for a page of a menu level.
+  ///
+  /// `parent` is the identifier of the item whose children should be
+  /// presented, or `None` for the root level.  Separators and headers do
+  /// not get buttons, badges are shown in parentheses after the titles, and
+  /// checkbox and radio items are marked with their state.  Disabled items,
+  /// including the ones rejected by the registry passed to
+  /// [`Builder::enabled()`], get buttons, but their payloads do not decode.
+  /// The buttons of groups show the groups' children.
+  pub fn level(
+    &self,
+    parent: Option<&str>,
+    page: usize
+  ) -> Result<Keyboard, Error> {
+    // Resolve the level and the path leading up to it
+    let (path, lst) = match parent {
+      Some(miid) => {
+        let path = self
+          .menu
+          .path_to(miid)
+          .ok_or_else(|| Error::UnknownItem(miid.to_string()))?;
+        let lst = menuitem::choices(&path[path.len() - 1].children);
+        (path, lst)
+      }
+      None => (Vec::new(), menuitem::choices(&self.menu.rootlst))
+    };
+
+    let page_size = self.page_size.unwrap_or_else(|| lst.len().max(1));
+    let start = (page * page_size).min(lst.len());
+    let end = (start + page_size).min(lst.len());
+
+    let mut rows = Vec::new();
+    for chunk in lst[start..end].chunks(self.columns) {
+      let mut row = Vec::new();
+      for mi in chunk {
+        let callback = if mi.kind() == Kind::Group {
+          self.level_payload(Some(mi.id()), 0)?
+        } else {
+          self.item_payload(mi)?
+        };
+        row.push(Button {
+          text: checks::label(mi, self.checks),
+          callback
+        });
+      }
+      rows.push(row);
+    }
+
+    // Pagination row
+    let mut nav = Vec::new();
+    if start > 0 {
+      nav.push(Button {
+        text: self.prev_title.clone(),
+        callback: self.level_payload(parent, page - 1)?
+      });
+    }
+    if end < lst.len() {
+      nav.push(Button {
+        text: self.next_title.clone(),
+        callback: self.level_payload(parent, page + 1)?
+      });
+    }
+    if !nav.is_empty() {
+      rows.push(nav);
+    }
+
+    // Back/Home row
+    if !path.is_empty() {
+      let mut nav = Vec::new();
+      let grandparent = if path.len() > 1 {
+        Some(path[path.len() - 2].id())
+      } else {
+        None
+      };
+      nav.push(Button {
+        text: self.back_title.clone(),
+        callback: self.level_payload(grandparent, 0)?
+      });
+      if path.len() > 1 {
+        nav.push(Button {
+          text: self.home_title.clone(),
+          callback: self.level_payload(None, 0)?
+        });
+      }
+      rows.push(nav);
+    }
+
+    Ok(Keyboard { rows })
+  }
+
+  /// Map a callback payload back to the menu item or level it refers to.
+  ///
+  /// Returns `None` if the payload was not generated by this builder, or if it
+  /// refers to items that do not exist or can not be selected: separators,
+  /// headers, groups and disabled items.
+  pub fn decode(&self, payload: &str) -> Option<Callback<'a, C>> {
+    let payload = payload.strip_prefix(self.prefix.as_str())?;
+    let (tag, rest) = payload.split_at(payload.find(':')?);
+    let rest = &rest[1..];
+
+    match tag {
+      "i" | "I" => {
+        let mi = if tag == "i" {
+          self.menu.get(rest)?
+        } else {
+          self.by_position(rest)?
+        };
+        if mi.is_selectable() && action::is_enabled(mi, &self.enabled) {
+          Some(Callback::Item(mi))
+        } else {
+          None
+        }
+      }
+      "l" | "L" => {
+        let (page, rest) = rest.split_at(rest.find(':')?);
+        let page = page.parse::<usize>().ok()?;
+        let rest = &rest[1..];
+        let parent = if rest.is_empty() {
+          None
+        } else if tag == "l" {
+          Some(self.menu.get(rest)?)
+        } else {
+          Some(self.by_position(rest)?)
+        };
+        Some(Callback::Level { parent, page })
+      }
+      _ => None
+    }
+  }
+
+  fn item_payload(&self, mi: &MenuItem<C>) -> Result<String, Error> {
+    let s = format!("{}i:{}", self.prefix, mi.id());
+    if s.len() <= self.max_payload {
+      return Ok(s);
+    }
+    let s = format!("{}I:{}", self.prefix, self.position(mi.id()));
+    self.checked(s, mi.id())
+  }
+
+  fn level_payload(
+    &self,
+    parent: Option<&str>,
+    page: usize
+  ) -> Result<String, Error> {
+    let miid = parent.unwrap_or("");
+    let s = format!("{}l:{}:{}", self.prefix, page, miid);
+    if s.len() <= self.max_payload {
+      return Ok(s);
+    }
+    let s = format!("{}L:{}:{}", self.prefix, page, self.position(miid));
+    self.checked(s, miid)
+  }
+
+  fn checked(&self, s: String, miid: &str) -> Result<String, Error> {
+    if s.len() <= self.max_payload {
+      Ok(s)
+    } else {
+      Err(Error::PayloadTooLong(miid.to_string()))
+    }
+  }
+
+  /// Generate the positional reference (dot-separated child indexes) of an
+  /// item.
+  fn position(&self, miid: &str) -> String {
+    let mut lst = &self.menu.rootlst;
+    let mut idxs = Vec::new();
+    if let Some(path) = self.menu.path_to(miid) {
+      for mi in path {
+        if let Some(idx) = lst.iter().position(|c| c.id() == mi.id()) {
+          idxs.push(idx.to_string());
+        }
+        lst = &mi.children;
+      }
+    }
+    idxs.join(".")
+  }
+
+  fn by_position(&self, pos: &str) -> Option<&'a MenuItem<C>> {
+    let mut lst = &self.menu.rootlst;
+    let mut cur = None;
+    for idx in pos.split('.') {
+      let mi = lst.get(idx.parse::<usize>().ok()?)?;
+      lst = &mi.children;
+      cur = Some(mi);
+    }
+    cur
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
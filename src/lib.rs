@@ -1,8 +1,28 @@
+pub mod accel;
+pub mod action;
+pub mod cache;
+pub mod cheatsheet;
+pub mod checks;
+pub mod collect;
+pub mod cursor;
+pub mod diff;
+pub mod edit;
 pub mod flatiter;
+pub mod history;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "json")]
+pub mod keyboard;
 pub mod menu;
 pub mod menuitem;
+pub mod order;
+pub mod patch;
+pub mod perms;
 pub mod recfiltiter;
 pub mod reciter;
+pub mod rule;
+pub mod search;
+pub mod session;
 
 pub mod iter {
   pub use crate::reciter::Event;
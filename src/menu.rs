@@ -1,38 +1,114 @@
-use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
-use std::rc::Rc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::collect::{self, Collector, Token};
 use crate::menuitem::{self, MenuItem};
+use crate::order::Order;
+use crate::perms::Principal;
+use crate::rule::Env;
 use crate::{flatiter, recfiltiter, reciter};
 
-pub struct Builder<C>
-where
-  C: Clone + Default
-{
-  items: HashMap<String, menuitem::Builder<C>>
+pub use crate::diff::{diff, diff_by};
+
+/// Errors that can occur when building a menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+  /// The placement constraints of the items in a list of siblings can not
+  /// all be satisfied.  `parent` is `None` for the root items.
+  Contradiction {
+    parent: Option<String>,
+    ids: Vec<String>
+  }
 }
 
-impl<C> Builder<C>
-where
-  C: Clone + Default
-{
+impl std::error::Error for Error {}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::Contradiction { parent, ids } => write!(
+        f,
+        "Contradictory placement of {} under {}",
+        ids.join(", "),
+        parent.as_deref().unwrap_or("the root")
+      )
+    }
+  }
+}
+
+pub struct Builder<C> {
+  pub(crate) items: HashMap<String, menuitem::Builder<C>>,
+  order: Order<C>,
+  seq: usize
+}
+
+impl<C> Builder<C> {
   pub fn new() -> Self {
     Builder {
-      items: HashMap::new()
+      items: HashMap::new(),
+      order: Order::default(),
+      seq: 1
     }
   }
 
   /// Add a menu item to this menu.
-  pub fn add(&mut self, mib: menuitem::Builder<C>) -> &mut Self {
+  pub fn add(&mut self, mut mib: menuitem::Builder<C>) -> &mut Self {
+    mib.seq = self.seq;
+    self.seq += 1;
     self.items.insert(mib.miid.to_string(), mib);
     self
   }
 
+  /// Set the strategy used to order siblings.  The menu keeps using it when
+  /// it is edited.
+  pub fn order(&mut self, order: Order<C>) -> &mut Self {
+    self.order = order;
+    self
+  }
+
+  /// Build the menu.
+  ///
+  /// Contradictory placement constraints are not reported.  A list of
+  /// siblings whose constraints contradict each other is ordered using the
+  /// ordering strategy alone -- by default weight, then title, then
+  /// identifier -- and all placement constraints within that list are
+  /// ignored.  Other lists of siblings are not affected.  Use
+  /// [`Builder::try_build()`] to detect such contradictions.
   pub fn build(self) -> Menu<C> {
+    self.build_checked().0
+  }
+
+  /// Build the menu, failing if the placement constraints of any list of
+  /// siblings contradict each other.
+  ///
+  /// On failure, one error is returned for each list of siblings with
+  /// contradictory constraints, with the root list first and the others
+  /// ordered by the identifiers of their parents.
+  pub fn try_build(self) -> Result<Menu<C>, Vec<Error>> {
+    let (menu, mut errors) = self.build_checked();
+    if errors.is_empty() {
+      Ok(menu)
+    } else {
+      errors.sort_by(|a, b| match (a, b) {
+        (
+          Error::Contradiction { parent: pa, .. },
+          Error::Contradiction { parent: pb, .. }
+        ) => pa.cmp(pb)
+      });
+      Err(errors)
+    }
+  }
+
+  fn build_checked(self) -> (Menu<C>, Vec<Error>) {
+    let mut errors = Vec::new();
     let mut rootitems: Vec<String> = Vec::new();
     let mut parents: HashMap<String, Vec<String>> = HashMap::new();
-    let mut menuitems: HashMap<String, Rc<RefCell<MenuItem<C>>>> =
-      HashMap::new();
+    let mut parent_of: HashMap<String, String> = HashMap::new();
+    let mut menuitems: HashMap<String, MenuItem<C>> = HashMap::new();
 
     // Iterate over all nodes to generate:
     // - a map of all parent identifiers to a list of their submenu identifiers
@@ -43,6 +119,8 @@ where
       // parent-to-children container.
       // Otherwise add it to the list of root menu items.
       if let Some(ref parent_id) = mib.parent {
+        parent_of.insert(id.to_string(), parent_id.to_string());
+
         // Make sure parent exists in dictionary of all parents and their
         // subitems
         if !parents.contains_key(parent_id) {
@@ -63,7 +141,7 @@ where
       }
 
       // Build MenuItem object from this builder
-      menuitems.insert(id.to_string(), Rc::new(RefCell::new(mib.build())));
+      menuitems.insert(id.to_string(), mib.build());
     }
 
     // At this point:
@@ -109,93 +187,426 @@ where
 
 
     // At this point "stack" is an stack of all parents.
-    // Take the nodes off the stack one by one and clone the child nodes onto
+    // Take the nodes off the stack one by one and move the child nodes into
     // their parents' child list.
     while let Some(parent_id) = stack.pop() {
-      let parent = match menuitems.get(&parent_id) {
+      let mut parent = match menuitems.remove(&parent_id) {
         Some(parent) => parent,
         None => continue
       };
 
-      let child_ids = match parents.get(&parent_id) {
-        Some(v) => v,
-        None => continue
-      };
-
-      //println!("Adding children {:?} to parent {:?}", child_ids, parent);
-      for child_id in child_ids {
-        let child = match menuitems.get(child_id) {
-          Some(child) => child,
-          None => continue
-        };
-        //println!("Adding child {} to parent {}", child_id, parent_id);
-        let mut parent = parent.borrow_mut();
-
-        // Clone child node into child list.
-        // The order is important here, which is why the stack is ordered the
-        // way it is.
-        parent.children.push(child.borrow().clone());
-        //println!("{:?}", parent);
+      if let Some(child_ids) = parents.get(&parent_id) {
+        for child_id in child_ids {
+          // Move child node into child list.
+          // The order is important here, which is why the stack is ordered
+          // the way it is; children are complete before their parents.
+          if let Some(child) = menuitems.remove(child_id) {
+            parent.children.push(child);
+          }
+        }
       }
-      //println!("Parent2: {:?}\n", parent);
 
       // Sort child items
-      let mut parent = parent.borrow_mut();
-      parent.children.sort_by(|a, b| a.order_cmp(b));
+      if let Err(ids) =
+        menuitem::sort_siblings(&mut parent.children, &self.order)
+      {
+        errors.push(Error::Contradiction {
+          parent: Some(parent_id.clone()),
+          ids
+        });
+      }
+      menuitems.insert(parent_id, parent);
     }
 
     //println!("\nParents: {:?}\n", parents);
     //println!("menuitems: {:?}\n", menuitems);
 
 
+    // Any item that is not reachable from the root items is an orphan; it
+    // either refers to a parent that does not exist or is part of a cycle.
+    let mut reachable: HashSet<&String> = HashSet::new();
+    let mut pending: Vec<&String> = rootitems.iter().collect();
+    while let Some(id) = pending.pop() {
+      reachable.insert(id);
+      if let Some(child_ids) = parents.get(id) {
+        pending.extend(child_ids.iter());
+      }
+    }
+    let orphans: HashMap<String, String> = parent_of
+      .iter()
+      .filter(|(id, _)| !reachable.contains(id))
+      .map(|(id, parent_id)| (id.clone(), parent_id.clone()))
+      .collect();
+
     let mut rootmis = Vec::new();
 
     // Put root nodes into root list
     for root_id in rootitems {
-      if let Some(mi) = menuitems.get(&root_id) {
-        rootmis.push(mi.borrow().clone());
+      if let Some(mi) = menuitems.remove(&root_id) {
+        rootmis.push(mi);
       }
     }
 
     // sort root items
-    rootmis.sort_by(|a, b| a.order_cmp(b));
+    if let Err(ids) = menuitem::sort_siblings(&mut rootmis, &self.order) {
+      errors.push(Error::Contradiction { parent: None, ids });
+    }
 
     //println!("\nFinal: {:?}\n", rootmis);
 
-    Menu { rootlst: rootmis }
+    let menu = Menu {
+      rootlst: rootmis,
+      orphans,
+      order: self.order
+    };
+    (menu, errors)
   }
 }
 
 
-pub struct Menu<C>
-where
-  C: Clone + Default
-{
-  pub(crate) rootlst: Vec<MenuItem<C>>
+impl<C> Default for Builder<C> {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
-impl<C> Menu<C>
-where
-  C: Clone + Default
-{
+
+/// The result of explaining the visibility of a menu item in a filtered
+/// menu.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Visibility {
+  /// The item is visible.
+  Visible,
+
+  /// The item was rejected by the filter predicate.
+  Rejected,
+
+  /// The item was accepted by the filter predicate, but is hidden because
+  /// the ancestor with the specified identifier was rejected.
+  PrunedBy(String),
+
+  /// The item was dropped when the menu was built, because its parent
+  /// (specified) does not exist, or because it is part of a parent cycle.
+  Orphan { parent: String },
+
+  /// The item was accepted by the filter predicate, but is hidden because
+  /// it is a separator, header or group with no visible items to separate,
+  /// introduce or group.
+  Redundant,
+
+  /// There is no item with the specified identifier.
+  Unknown
+}
+
+
+#[derive(Debug)]
+pub struct Menu<C> {
+  pub(crate) rootlst: Vec<MenuItem<C>>,
+
+  /// Identifiers of menu items that could not be attached to the menu tree,
+  /// mapped to the identifiers of the parents they declared.
+  pub(crate) orphans: HashMap<String, String>,
+
+  /// The sibling ordering strategy.
+  pub(crate) order: Order<C>
+}
+
+impl<C> Menu<C> {
   pub fn get_rootitems(&self) -> &Vec<MenuItem<C>> {
     &self.rootlst
   }
 
-  pub fn iter_root(&self) -> flatiter::MenuIter<C> {
+  /// Return the menu items that were dropped at build time because they
+  /// could not be attached to the menu tree, mapped to the identifiers of
+  /// the parents they declared.
+  pub fn orphans(&self) -> &HashMap<String, String> {
+    &self.orphans
+  }
+
+  pub fn iter_root(&self) -> flatiter::MenuIter<'_, C> {
     flatiter::MenuIter::new(&self.rootlst)
   }
 
-  pub fn iter_hier(&self) -> reciter::MenuIter<C> {
+  pub fn iter_hier(&self) -> reciter::MenuIter<'_, C> {
     reciter::MenuIter::new(self)
   }
 
-  pub fn filtiter_hier<F>(&self, p: F) -> recfiltiter::MenuIter<C, F>
+  pub fn filtiter_hier<F>(&self, p: F) -> recfiltiter::MenuIter<'_, C, F>
   where
     F: Fn(&MenuItem<C>) -> bool
   {
     recfiltiter::MenuIter::new(self, p)
   }
+
+  /// Return a filtered hierarchical iterator which only yields the menu
+  /// items that the principal `p` may access (see [`Menu::permits()`]).
+  ///
+  /// An item that does not inherit its parent's requirements can be
+  /// accessible even though its parent is not.  In that case the parent is
+  /// still yielded, using `Event::Container`, so that the accessible items
+  /// can be reached; the parent's other children are skipped.
+  pub fn for_principal<'a, P>(
+    &'a self,
+    p: &'a P
+  ) -> recfiltiter::MenuIter<'a, C, impl Fn(&MenuItem<C>) -> bool + 'a>
+  where
+    P: Principal + ?Sized
+  {
+    let mut visible = HashSet::new();
+    let mut accessible = HashSet::new();
+    permitted(&self.rootlst, p, true, &mut visible, &mut accessible);
+    let mut it =
+      recfiltiter::MenuIter::new(self, move |mi| visible.contains(mi.id()));
+    it.containers(move |mi| !accessible.contains(mi.id()));
+    it
+  }
+
+  /// Return a filtered hierarchical iterator which only yields the menu
+  /// items whose visibility rules hold in the environment `env`.  Items
+  /// without a visibility rule are always visible.
+  pub fn for_env<'a, E>(
+    &'a self,
+    env: &'a E
+  ) -> recfiltiter::MenuIter<'a, C, impl Fn(&MenuItem<C>) -> bool + 'a>
+  where
+    E: Env + ?Sized
+  {
+    recfiltiter::MenuIter::new(self, move |mi| match mi.visibility() {
+      Some(rule) => rule.eval(env),
+      None => true
+    })
+  }
+
+  /// Construct a new menu from a stream of hierarchical events, such as the
+  /// events generated by the hierarchical iterators.
+  ///
+  /// See the [`collect`](crate::collect) module for details.
+  pub fn from_events<'a, I, T>(it: I) -> Result<Menu<C>, collect::Error>
+  where
+    I: IntoIterator<Item = T>,
+    T: Into<Token<'a, C>>,
+    C: Clone + 'a
+  {
+    let mut coll = Collector::new();
+    coll.extend(it)?;
+    coll.finish()
+  }
+
+  /// Convert the menu to a menu with a different context type.
+  ///
+  /// `f` is called for every menu item, parents before their children, and
+  /// returns the new context of the item.  Identifiers, titles, weights,
+  /// access requirements and structure are preserved.
+  ///
+  /// The sibling ordering strategy is carried over, except for a custom
+  /// comparison ([`Order::Custom`]), which operates on the old context type.
+  /// The new menu is then ordered using [`Order::Weight`] instead; use
+  /// [`Menu::set_order()`] to give it a new strategy.
+  pub fn map_ctx<D, F>(self, mut f: F) -> Menu<D>
+  where
+    F: FnMut(&MenuItem<C>) -> D
+  {
+    match self.try_map_ctx(|mi| Ok::<D, Infallible>(f(mi))) {
+      Ok(menu) => menu,
+      Err(e) => match e {}
+    }
+  }
+
+  /// Fallible version of [`Menu::map_ctx()`].  The conversion is aborted at
+  /// the first error returned by `f`.
+  pub fn try_map_ctx<D, E, F>(self, mut f: F) -> Result<Menu<D>, E>
+  where
+    F: FnMut(&MenuItem<C>) -> Result<D, E>
+  {
+    let rootlst = self
+      .rootlst
+      .into_iter()
+      .map(|mi| mi.try_map_ctx(&mut f))
+      .collect::<Result<Vec<_>, E>>()?;
+    Ok(Menu {
+      rootlst,
+      orphans: self.orphans,
+      order: self.order.convert()
+    })
+  }
+
+  /// Call `f` for every menu item, parents before their children.
+  ///
+  /// This can be used to update the items' contexts in place, using
+  /// [`MenuItem::appctx_mut()`].
+  pub fn for_each_mut<F>(&mut self, mut f: F)
+  where
+    F: FnMut(&mut MenuItem<C>)
+  {
+    for mi in self.rootlst.iter_mut() {
+      mi.for_each_mut(&mut f);
+    }
+  }
+
+  /// Materialize a filtered view of this menu into a new, independent, menu.
+  ///
+  /// The new menu contains clones of all the items accepted by the
+  /// predicate `p`.  As with the filtered hierarchical iterator, the
+  /// children of rejected items are dropped as well, and so are separators,
+  /// headers and groups that are left with nothing to separate, introduce or
+  /// group.
+  pub fn filtered<F>(&self, p: F) -> Menu<C>
+  where
+    C: Clone,
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    Menu {
+      rootlst: filter_items(&self.rootlst, &p),
+      orphans: self.orphans.clone(),
+      order: self.order.clone()
+    }
+  }
+
+  /// Explain whether the menu item with the identifier `miid` is visible when
+  /// the menu is filtered using the predicate `p`, and if not, why.
+  pub fn explain<F>(&self, miid: &str, p: F) -> Visibility
+  where
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    let path = match self.path_to(miid) {
+      Some(path) => path,
+      None => {
+        return match self.orphans.get(miid) {
+          Some(parent) => Visibility::Orphan {
+            parent: parent.clone()
+          },
+          None => Visibility::Unknown
+        };
+      }
+    };
+
+    // The topmost rejected item is the one that causes the pruning
+    for (i, mi) in path.iter().enumerate() {
+      if !p(mi) {
+        return if i == path.len() - 1 {
+          Visibility::Rejected
+        } else {
+          Visibility::PrunedBy(mi.id().to_string())
+        };
+      }
+    }
+
+    let mut lst = &self.rootlst;
+    for (i, mi) in path.iter().enumerate() {
+      let idx = lst.iter().position(|x| x.id() == mi.id()).unwrap_or(0);
+      if !menuitem::shown_mask(lst, &p)[idx] {
+        return if i == path.len() - 1 {
+          Visibility::Redundant
+        } else {
+          Visibility::PrunedBy(mi.id().to_string())
+        };
+      }
+      lst = &mi.children;
+    }
+    Visibility::Visible
+  }
+
+  /// Return `true` if the principal `p` may access the menu item with the
+  /// identifier `miid`.
+  ///
+  /// The requirements of the item's ancestors are checked as well, for as
+  /// long as the items inherit their parents' requirements.  Returns `false`
+  /// if the item does not exist.
+  pub fn permits<P>(&self, miid: &str, p: &P) -> bool
+  where
+    P: Principal + ?Sized
+  {
+    let path = match self.path_to(miid) {
+      Some(path) => path,
+      None => return false
+    };
+    for mi in path.iter().rev() {
+      if !mi.requirements().check(p) {
+        return false;
+      }
+      if !mi.requirements().inherits() {
+        break;
+      }
+    }
+    true
+  }
+
+  /// Look up a menu item, anywhere in the menu, by its identifier.
+  pub fn get(&self, miid: &str) -> Option<&MenuItem<C>> {
+    self.path_to(miid).and_then(|mut path| path.pop())
+  }
+
+  /// Return the chain of menu items leading to the menu item with the
+  /// identifier `miid`.  The first element is a root item and the last
+  /// element is the requested item itself.
+  pub fn path_to(&self, miid: &str) -> Option<Vec<&MenuItem<C>>> {
+    let mut path = Vec::new();
+    if find_path(&self.rootlst, miid, &mut path) {
+      Some(path)
+    } else {
+      None
+    }
+  }
+}
+
+fn filter_items<C, F>(lst: &[MenuItem<C>], p: &F) -> Vec<MenuItem<C>>
+where
+  C: Clone,
+  F: Fn(&MenuItem<C>) -> bool
+{
+  menuitem::shown(lst, p)
+    .map(|mi| {
+      let mut newmi = mi.clone_shallow();
+      newmi.children = filter_items(&mi.children, p);
+      newmi
+    })
+    .collect()
+}
+
+/// Collect the identifiers of the items in `lst`, and their descendants,
+/// that the principal `p` may access into `accessible`, and those as well as
+/// the identifiers of the items that lead to them into `visible`.
+/// `parent_ok` tells whether the principal may access the items' parent.
+/// Returns `true` if any identifiers were collected.
+fn permitted<C, P>(
+  lst: &[MenuItem<C>],
+  p: &P,
+  parent_ok: bool,
+  visible: &mut HashSet<String>,
+  accessible: &mut HashSet<String>
+) -> bool
+where
+  P: Principal + ?Sized
+{
+  let mut any = false;
+  for mi in lst {
+    let reqs = mi.requirements();
+    let ok = reqs.check(p) && (parent_ok || !reqs.inherits());
+    if ok {
+      accessible.insert(mi.id().to_string());
+    }
+    if permitted(&mi.children, p, ok, visible, accessible) || ok {
+      visible.insert(mi.id().to_string());
+      any = true;
+    }
+  }
+  any
+}
+
+fn find_path<'a, C>(
+  lst: &'a [MenuItem<C>],
+  miid: &str,
+  path: &mut Vec<&'a MenuItem<C>>
+) -> bool {
+  for mi in lst {
+    path.push(mi);
+    if mi.id() == miid || find_path(&mi.children, miid, path) {
+      return true;
+    }
+    path.pop();
+  }
+  false
 }
 
 // vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
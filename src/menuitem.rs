@@ -1,37 +1,137 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::accel::{self, Accel, Mnemonic};
+use crate::order::Order;
+use crate::perms::Requirements;
+use crate::rule::Rule;
+
+/// The kind of a menu item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Kind {
+  /// A regular item, which links to a page or triggers an action.
+  #[default]
+  Link,
+
+  /// A non-selectable section header, which introduces the items that follow
+  /// it, up to the next separator or header.
+  Header,
+
+  /// A separator between groups of sibling items.  Its title is not shown.
+  Separator,
+
+  /// A non-selectable item which only exists to group its children.
+  Group,
+
+  /// An item that can be checked and unchecked.
+  Checkbox,
+
+  /// An item of which only one in its radio group can be checked.  See
+  /// [`Builder::radio_group()`].
+  Radio
+}
+
+impl fmt::Display for Kind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Kind::Link => write!(f, "link"),
+      Kind::Header => write!(f, "header"),
+      Kind::Separator => write!(f, "separator"),
+      Kind::Group => write!(f, "group"),
+      Kind::Checkbox => write!(f, "checkbox"),
+      Kind::Radio => write!(f, "radio")
+    }
+  }
+}
+
+/// Standard presentation metadata of a menu item, understood by the built-in
+/// renderers and exporters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Presentation {
+  /// Name of an icon, interpreted by the application's front end.
+  pub icon: Option<String>,
+
+  /// Short help text, typically shown when hovering over the item.
+  pub tooltip: Option<String>,
+
+  /// Longer description of the item.
+  pub description: Option<String>,
+
+  /// Badge text, such as a counter of unread messages.
+  pub badge: Option<String>,
+
+  /// Disabled items are shown, but can not be selected.
+  pub disabled: bool
+}
+
+/// Pin a menu item to either end of its siblings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) enum Pin {
+  First,
+  Last
+}
+
+/// Constraints on the position of a menu item relative to its siblings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Placement {
+  before: Vec<String>,
+  after: Vec<String>,
+  pin: Option<Pin>
+}
+
+impl Placement {
+  fn is_empty(&self) -> bool {
+    self.before.is_empty() && self.after.is_empty() && self.pin.is_none()
+  }
+}
+
 /// Representation of a menu item builder.
 ///
 /// Instances of this object are added to the `Menu` builder.
-#[derive(Default)]
-pub struct Builder<C>
-where
-  C: Clone
-{
+pub struct Builder<C> {
   pub(crate) miid: String,
   pub(crate) title: String,
   pub(crate) parent: Option<String>,
   pub(crate) weight: isize,
+  pub(crate) kind: Kind,
+  pub(crate) pres: Presentation,
+  pub(crate) radio: Option<String>,
+  pub(crate) checked: bool,
+  pub(crate) accel: Option<Accel>,
+  pub(crate) mnemonic: Option<Mnemonic>,
+  pub(crate) reqs: Requirements,
+  pub(crate) visible_if: Option<Rule>,
+  pub(crate) placement: Placement,
+
+  /// Declaration sequence number, used by `Order::Declaration`.  Zero means
+  /// that the item has not been added to a menu yet.
+  pub(crate) seq: usize,
   pub(crate) appctx: C
 }
 
 impl<C> Builder<C>
 where
-  C: Clone + Default
+  C: Default
 {
   pub fn new<M, T>(miid: M, title: T) -> Self
   where
     M: ToString,
     T: ToString
   {
-    Builder {
-      miid: miid.to_string(),
-      title: title.to_string(),
-      weight: 100,
-      ..Default::default()
-    }
+    Self::new_ctx(miid, title, C::default())
   }
+}
+
+impl<C> Builder<C> {
   pub fn new_ctx<M, T>(miid: M, title: T, appctx: C) -> Self
   where
     M: ToString,
@@ -40,9 +140,19 @@ where
     Builder {
       miid: miid.to_string(),
       title: title.to_string(),
+      parent: None,
       weight: 100,
-      appctx,
-      ..Default::default()
+      kind: Kind::Link,
+      pres: Presentation::default(),
+      radio: None,
+      checked: false,
+      accel: None,
+      mnemonic: None,
+      reqs: Requirements::default(),
+      visible_if: None,
+      placement: Placement::default(),
+      seq: 0,
+      appctx
     }
   }
 
@@ -55,21 +165,175 @@ where
     self
   }
 
+  /// Set the kind of the menu item.  Items are links by default.
+  pub fn kind(&mut self, kind: Kind) -> &mut Self {
+    self.kind = kind;
+    self
+  }
+
+  /// Make the menu item a radio item in the radio group `group`.
+  ///
+  /// Radio items that are not given a group form an implicit group with
+  /// their sibling radio items that have no group either.
+  pub fn radio_group<G: ToString>(&mut self, group: G) -> &mut Self {
+    self.kind = Kind::Radio;
+    self.radio = Some(group.to_string());
+    self
+  }
+
+  /// Set the initial state of a checkbox or radio item.  See
+  /// [`checks::Store`](crate::checks::Store).
+  pub fn checked(&mut self, checked: bool) -> &mut Self {
+    self.checked = checked;
+    self
+  }
+
+  /// Set the title from a string in which the mnemonic is marked with an
+  /// ampersand, as in `&File`.  See [`accel::parse_mnemonic()`].
+  pub fn mnemonic_title<T: AsRef<str>>(&mut self, title: T) -> &mut Self {
+    let (title, mnemonic) = accel::parse_mnemonic(title.as_ref());
+    self.title = title;
+    self.mnemonic = mnemonic;
+    self
+  }
+
+  /// Set the mnemonic of the menu item.
+  pub fn mnemonic(&mut self, key: char) -> &mut Self {
+    self.mnemonic = Some(Mnemonic {
+      key: key.to_lowercase().next().unwrap_or(key),
+      offset: None
+    });
+    self
+  }
+
+  /// Set the keyboard accelerator of the menu item.
+  pub fn accel(&mut self, accel: Accel) -> &mut Self {
+    self.accel = Some(accel);
+    self
+  }
+
+  /// Set the name of the menu item's icon.
+  pub fn icon<I: ToString>(&mut self, icon: I) -> &mut Self {
+    self.pres.icon = Some(icon.to_string());
+    self
+  }
+
+  /// Set the menu item's tooltip, a short help text.
+  pub fn tooltip<T: ToString>(&mut self, tooltip: T) -> &mut Self {
+    self.pres.tooltip = Some(tooltip.to_string());
+    self
+  }
+
+  /// Set the menu item's description, a longer text than the tooltip.
+  pub fn description<D: ToString>(&mut self, description: D) -> &mut Self {
+    self.pres.description = Some(description.to_string());
+    self
+  }
+
+  /// Set the menu item's badge, for instance a counter.
+  pub fn badge<B: ToString>(&mut self, badge: B) -> &mut Self {
+    self.pres.badge = Some(badge.to_string());
+    self
+  }
+
+  /// Mark the menu item as disabled.  Disabled items are shown, but can not
+  /// be selected.
+  pub fn disabled(&mut self, disabled: bool) -> &mut Self {
+    self.pres.disabled = disabled;
+    self
+  }
+
+  /// Require at least one of the permissions in `perms` to access this menu
+  /// item.
+  pub fn require_any<I, S>(&mut self, perms: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: ToString
+  {
+    self
+      .reqs
+      .any
+      .extend(perms.into_iter().map(|s| s.to_string()));
+    self
+  }
+
+  /// Require all of the permissions in `perms` to access this menu item.
+  pub fn require_all<I, S>(&mut self, perms: I) -> &mut Self
+  where
+    I: IntoIterator<Item = S>,
+    S: ToString
+  {
+    self
+      .reqs
+      .all
+      .extend(perms.into_iter().map(|s| s.to_string()));
+    self
+  }
+
+  /// Control whether this menu item inherits the access requirements of its
+  /// parent.  Requirements are inherited by default.
+  pub fn inherit_requirements(&mut self, inherit: bool) -> &mut Self {
+    self.reqs.inherit = inherit;
+    self
+  }
+
+  /// Only show this menu item if the visibility rule `rule` holds.
+  pub fn visible_if(&mut self, rule: Rule) -> &mut Self {
+    self.visible_if = Some(rule);
+    self
+  }
+
+  /// Place this menu item before its sibling `miid`.
+  ///
+  /// Placement constraints take precedence over weights.  Constraints that
+  /// refer to items which are not siblings of this item are ignored.
+  pub fn before<M: ToString>(&mut self, miid: M) -> &mut Self {
+    self.placement.before.push(miid.to_string());
+    self
+  }
+
+  /// Place this menu item after its sibling `miid`.
+  pub fn after<M: ToString>(&mut self, miid: M) -> &mut Self {
+    self.placement.after.push(miid.to_string());
+    self
+  }
+
+  /// Place this menu item before all its siblings that are not placed first
+  /// themselves.
+  pub fn first(&mut self) -> &mut Self {
+    self.placement.pin = Some(Pin::First);
+    self
+  }
+
+  /// Place this menu item after all its siblings that are not placed last
+  /// themselves.
+  pub fn last(&mut self) -> &mut Self {
+    self.placement.pin = Some(Pin::Last);
+    self
+  }
+
   pub(crate) fn build(self) -> MenuItem<C> {
     MenuItem {
       miid: self.miid,
       title: self.title,
       weight: self.weight,
+      kind: self.kind,
+      pres: self.pres,
+      radio: self.radio,
+      checked: self.checked,
+      accel: self.accel,
+      mnemonic: self.mnemonic,
       children: Vec::new(),
+      reqs: self.reqs,
+      visible_if: self.visible_if,
+      placement: self.placement,
+      seq: self.seq,
       appctx: self.appctx
     }
   }
 }
 
-impl<C> Hash for Builder<C>
-where
-  C: Clone + Default
-{
+impl<C> Hash for Builder<C> {
   fn hash<H: Hasher>(&self, state: &mut H) {
     self.miid.hash(state);
   }
@@ -77,23 +341,58 @@ where
 
 
 /// Representation of a single menu item.
+///
+/// With the `serde` feature enabled menu items, along with their children,
+/// can be serialized.  The position of an item in declaration order is not
+/// part of its serialized form.
 #[derive(Default, Clone, Debug)]
-pub struct MenuItem<C>
-where
-  C: Clone + Default
-{
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MenuItem<C> {
+  #[cfg_attr(feature = "serde", serde(rename = "id"))]
   miid: String,
   title: String,
   weight: isize,
+  kind: Kind,
+  #[cfg_attr(feature = "serde", serde(rename = "presentation"))]
+  pres: Presentation,
+  radio: Option<String>,
+  checked: bool,
+  accel: Option<Accel>,
+  mnemonic: Option<Mnemonic>,
   pub(crate) children: Vec<MenuItem<C>>,
+  #[cfg_attr(feature = "serde", serde(rename = "requirements"))]
+  reqs: Requirements,
+  visible_if: Option<Rule>,
+  placement: Placement,
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub(crate) seq: usize,
+  #[cfg_attr(feature = "serde", serde(rename = "ctx"))]
   appctx: C
 }
 
+impl<C: PartialEq> PartialEq for MenuItem<C> {
+  /// Compare all the properties of two menu items, and of their children,
+  /// except for their positions in declaration order.
+  fn eq(&self, other: &Self) -> bool {
+    self.miid == other.miid
+      && self.title == other.title
+      && self.weight == other.weight
+      && self.kind == other.kind
+      && self.pres == other.pres
+      && self.radio == other.radio
+      && self.checked == other.checked
+      && self.accel == other.accel
+      && self.mnemonic == other.mnemonic
+      && self.children == other.children
+      && self.reqs == other.reqs
+      && self.visible_if == other.visible_if
+      && self.placement == other.placement
+      && self.appctx == other.appctx
+  }
+}
+
 
-impl<C> MenuItem<C>
-where
-  C: Clone + Default
-{
+impl<C> MenuItem<C> {
   /// Return a string reference to the menu item's internal identifier.
   pub fn id(&self) -> &str {
     &self.miid
@@ -104,6 +403,64 @@ where
     &self.title
   }
 
+  /// Return the menu item's sort weight.
+  pub fn weight(&self) -> isize {
+    self.weight
+  }
+
+  /// Return the kind of the menu item.
+  pub fn kind(&self) -> Kind {
+    self.kind
+  }
+
+  /// Return `true` if the menu item can be selected, i.e. if it is an
+  /// enabled link, checkbox or radio item.
+  pub fn is_selectable(&self) -> bool {
+    (self.kind == Kind::Link || self.is_checkable()) && !self.pres.disabled
+  }
+
+  /// Return `true` if the menu item is a checkbox or radio item.
+  pub fn is_checkable(&self) -> bool {
+    matches!(self.kind, Kind::Checkbox | Kind::Radio)
+  }
+
+  /// Return the name of the radio group the menu item was declared to be a
+  /// member of.
+  pub fn radio_group(&self) -> Option<&str> {
+    self.radio.as_deref()
+  }
+
+  /// Return the keyboard accelerator of the menu item.
+  pub fn accel(&self) -> Option<&Accel> {
+    self.accel.as_ref()
+  }
+
+  /// Return the mnemonic of the menu item.
+  pub fn mnemonic(&self) -> Option<&Mnemonic> {
+    self.mnemonic.as_ref()
+  }
+
+  /// Return the initial state of a checkbox or radio item.
+  pub fn default_checked(&self) -> bool {
+    self.checked
+  }
+
+  /// Return the menu item's presentation metadata.
+  pub fn presentation(&self) -> &Presentation {
+    &self.pres
+  }
+
+  /// Return a mutable reference to the menu item's presentation metadata,
+  /// for instance to update a badge counter.
+  pub fn presentation_mut(&mut self) -> &mut Presentation {
+    &mut self.pres
+  }
+
+  /// Return `true` if the menu item is disabled.
+  pub fn is_disabled(&self) -> bool {
+    self.pres.disabled
+  }
+
   /// Return a reference to a Vec of all the child menu items.
   pub fn children(&self) -> &Vec<MenuItem<C>> {
     &self.children
@@ -113,11 +470,120 @@ where
     self.children.is_empty()
   }
 
+  /// Return the permissions required to access this menu item.
+  pub fn requirements(&self) -> &Requirements {
+    &self.reqs
+  }
+
+  /// Return the menu item's visibility rule, if it has one.
+  pub fn visibility(&self) -> Option<&Rule> {
+    self.visible_if.as_ref()
+  }
+
   /// Return a reference to the application defined menu item context.
   pub fn appctx(&self) -> &C {
     &self.appctx
   }
 
+  /// Return a mutable reference to the application defined menu item
+  /// context.
+  pub fn appctx_mut(&mut self) -> &mut C {
+    &mut self.appctx
+  }
+
+  /// Convert the menu item, and all its children, to a menu item with a
+  /// different context type.  `f` is called for each item in pre-order.
+  pub(crate) fn try_map_ctx<D, E, F>(self, f: &mut F) -> Result<MenuItem<D>, E>
+  where
+    F: FnMut(&MenuItem<C>) -> Result<D, E>
+  {
+    let appctx = f(&self)?;
+    let children = self
+      .children
+      .into_iter()
+      .map(|mi| mi.try_map_ctx(f))
+      .collect::<Result<Vec<_>, E>>()?;
+    Ok(MenuItem {
+      miid: self.miid,
+      title: self.title,
+      weight: self.weight,
+      kind: self.kind,
+      pres: self.pres,
+      radio: self.radio,
+      checked: self.checked,
+      accel: self.accel,
+      mnemonic: self.mnemonic,
+      children,
+      reqs: self.reqs,
+      visible_if: self.visible_if,
+      placement: self.placement,
+      seq: self.seq,
+      appctx
+    })
+  }
+
+  /// Call `f` for this menu item and all its children, in pre-order.
+  pub(crate) fn for_each_mut<F>(&mut self, f: &mut F)
+  where
+    F: FnMut(&mut MenuItem<C>)
+  {
+    f(self);
+    for mi in self.children.iter_mut() {
+      mi.for_each_mut(f);
+    }
+  }
+
+  /// Return the title, followed by the badge in parentheses if there is one,
+  /// for renderers that only present plain text.
+  pub(crate) fn label(&self) -> String {
+    match self.pres.badge {
+      Some(ref badge) => format!("{} ({})", self.title, badge),
+      None => self.title.clone()
+    }
+  }
+
+  /// Turn the menu item back into a builder with the parent `parent`.  The
+  /// item's children are returned separately.
+  pub(crate) fn into_builder(
+    self,
+    parent: Option<String>
+  ) -> (Builder<C>, Vec<MenuItem<C>>) {
+    let mib = Builder {
+      miid: self.miid,
+      title: self.title,
+      parent,
+      weight: self.weight,
+      kind: self.kind,
+      pres: self.pres,
+      radio: self.radio,
+      checked: self.checked,
+      accel: self.accel,
+      mnemonic: self.mnemonic,
+      reqs: self.reqs,
+      visible_if: self.visible_if,
+      placement: self.placement,
+      seq: 0,
+      appctx: self.appctx
+    };
+    (mib, self.children)
+  }
+
+  pub(crate) fn set_title(&mut self, title: String) {
+    self.title = title;
+  }
+
+  pub(crate) fn set_weight(&mut self, weight: isize) {
+    self.weight = weight;
+  }
+
+  pub(crate) fn set_kind(&mut self, kind: Kind) {
+    self.kind = kind;
+  }
+
+  pub(crate) fn set_accel(&mut self, accel: Option<Accel>) {
+    self.accel = accel;
+  }
+
   pub(crate) fn order_cmp(&self, other: &Self) -> Ordering {
     if self.weight == other.weight {
       return self.title.cmp(&other.title);
@@ -126,10 +592,257 @@ where
   }
 }
 
-impl<C> Hash for MenuItem<C>
+/// Sort a list of sibling menu items.
+///
+/// Items are ordered using `order`, unless placement constraints require
+/// otherwise.  If the constraints contradict each other, the items are
+/// ordered using `order` only, and the identifiers of the items whose
+/// constraints could not be satisfied are returned.
+pub(crate) fn sort_siblings<C>(
+  lst: &mut Vec<MenuItem<C>>,
+  order: &Order<C>
+) -> Result<(), Vec<String>> {
+  lst.sort_by(|a, b| order.compare(a, b));
+  if lst.iter().all(|mi| mi.placement.is_empty()) {
+    return Ok(());
+  }
+
+  let n = lst.len();
+  let pos: HashMap<&str, usize> =
+    lst.iter().enumerate().map(|(i, mi)| (mi.id(), i)).collect();
+
+  // Edges from items to the items that must come after them
+  let mut succ = vec![Vec::new(); n];
+  let mut indeg = vec![0; n];
+  let mut edge = |a: usize, b: usize| {
+    if a != b {
+      succ[a].push(b);
+      indeg[b] += 1;
+    }
+  };
+  for (i, mi) in lst.iter().enumerate() {
+    let pl = &mi.placement;
+    for j in pl.before.iter().filter_map(|id| pos.get(id.as_str())) {
+      edge(i, *j);
+    }
+    for j in pl.after.iter().filter_map(|id| pos.get(id.as_str())) {
+      edge(*j, i);
+    }
+    if let Some(pin) = pl.pin {
+      for (j, other) in lst.iter().enumerate() {
+        if other.placement.pin != Some(pin) {
+          match pin {
+            Pin::First => edge(i, j),
+            Pin::Last => edge(j, i)
+          }
+        }
+      }
+    }
+  }
+
+  // Items with relative constraints are sorted as if they had the position
+  // of the item they are anchored to, so they end up next to it.
+  let mut rank: Vec<Option<usize>> = vec![None; n];
+  for i in 0..n {
+    anchor_rank(lst, &pos, i, &mut rank, &mut vec![false; n]);
+  }
+
+  // Topological sort which, among the items that are free to go next, picks
+  // the one that comes first by anchored position and then by `order`.
+  let mut order = Vec::with_capacity(n);
+  let mut done = vec![false; n];
+  while let Some(i) = (0..n)
+    .filter(|&i| !done[i] && indeg[i] == 0)
+    .min_by_key(|&i| (rank[i], i))
+  {
+    done[i] = true;
+    order.push(i);
+    for &j in &succ[i] {
+      indeg[j] -= 1;
+    }
+  }
+  if order.len() < n {
+    // Only report the items that are part of, or lie between, cycles by
+    // repeatedly discarding items that have nothing left to precede.
+    let mut left: Vec<bool> = done.iter().map(|d| !d).collect();
+    while let Some(i) =
+      (0..n).find(|&i| left[i] && succ[i].iter().all(|&j| !left[j]))
+    {
+      left[i] = false;
+    }
+    return Err(
+      (0..n)
+        .filter(|&i| left[i])
+        .map(|i| lst[i].id().to_string())
+        .collect()
+    );
+  }
+
+  let mut items: Vec<Option<MenuItem<C>>> = lst.drain(..).map(Some).collect();
+  lst.extend(order.into_iter().filter_map(|i| items[i].take()));
+  Ok(())
+}
+
+/// Determine the position used for sorting item `i`: the position of the
+/// last item it is placed after, or else the position of the first item it is
+/// placed before, or else its own position.
+fn anchor_rank<C>(
+  lst: &[MenuItem<C>],
+  pos: &HashMap<&str, usize>,
+  i: usize,
+  rank: &mut Vec<Option<usize>>,
+  visiting: &mut Vec<bool>
+) -> usize {
+  if let Some(r) = rank[i] {
+    return r;
+  }
+  if visiting[i] {
+    // Contradictory constraints; reported by the caller
+    return i;
+  }
+  visiting[i] = true;
+
+  let pl = &lst[i].placement;
+  let targets = |ids: &[String]| -> Vec<usize> {
+    ids
+      .iter()
+      .filter_map(|id| pos.get(id.as_str()).copied())
+      .filter(|&j| j != i)
+      .collect()
+  };
+  let after = targets(&pl.after);
+  let before = targets(&pl.before);
+  let r = if !after.is_empty() {
+    after
+      .into_iter()
+      .map(|j| anchor_rank(lst, pos, j, rank, visiting))
+      .max()
+      .unwrap()
+  } else if !before.is_empty() {
+    before
+      .into_iter()
+      .map(|j| anchor_rank(lst, pos, j, rank, visiting))
+      .min()
+      .unwrap()
+  } else {
+    i
+  };
+  rank[i] = Some(r);
+  r
+}
+
+/// Determine which items of a list of siblings are shown when the menu is
+/// filtered using `keep`, as one flag per item.
+///
+/// Besides being accepted by `keep`, a separator is only shown if there are
+/// shown items on both sides of it and it does not directly follow another
+/// shown separator, a header is only shown if it is followed by a shown item
+/// before the next separator or header, and a group is only shown if it has
+/// shown children.
+pub(crate) fn shown_mask<C, F>(lst: &[MenuItem<C>], keep: &F) -> Vec<bool>
 where
-  C: Clone + Default
+  F: Fn(&MenuItem<C>) -> bool
 {
+  let content: Vec<bool> =
+    lst.iter().map(|mi| has_content(mi, keep)).collect();
+  let mut mask = content.clone();
+
+  // Whether the closest significant item after a header is a shown item,
+  // rather than another separator or header
+  let mut next = false;
+  for (i, mi) in lst.iter().enumerate().rev() {
+    if mi.kind == Kind::Header {
+      mask[i] = next && keep(mi);
+    }
+    if content[i] {
+      next = true;
+    } else if matches!(mi.kind, Kind::Header | Kind::Separator) && keep(mi) {
+      next = false;
+    }
+  }
+
+  // Likewise for the closest significant item before a separator
+  let mut prev = false;
+  let mut remaining = content.iter().filter(|&&c| c).count();
+  for (i, mi) in lst.iter().enumerate() {
+    if mi.kind == Kind::Separator {
+      mask[i] = prev && remaining > 0 && keep(mi);
+    }
+    if content[i] {
+      prev = true;
+      remaining -= 1;
+    } else if mi.kind == Kind::Separator && keep(mi) {
+      prev = false;
+    }
+  }
+
+  mask
+}
+
+/// Return the items of a list of siblings that are shown when the menu is
+/// filtered using `keep`.  See [`shown_mask()`].
+pub(crate) fn shown<'a, C, F>(
+  lst: &'a [MenuItem<C>],
+  keep: &F
+) -> impl Iterator<Item = &'a MenuItem<C>>
+where
+  F: Fn(&MenuItem<C>) -> bool
+{
+  lst
+    .iter()
+    .zip(shown_mask(lst, keep))
+    .filter(|(_, shown)| *shown)
+    .map(|(mi, _)| mi)
+}
+
+/// Return the items of a list of siblings that can be presented as choices:
+/// the shown items that are neither separators nor headers.  Disabled items
+/// are included; it is up to the caller to make them non-selectable.
+pub(crate) fn choices<C>(lst: &[MenuItem<C>]) -> Vec<&MenuItem<C>> {
+  shown(lst, &|_: &MenuItem<C>| true)
+    .filter(|mi| !matches!(mi.kind, Kind::Header | Kind::Separator))
+    .collect()
+}
+
+/// Return `true` if `mi` is a link, checkable item or group that is shown
+/// when the menu is filtered using `keep`.
+fn has_content<C, F>(mi: &MenuItem<C>, keep: &F) -> bool
+where
+  F: Fn(&MenuItem<C>) -> bool
+{
+  match mi.kind {
+    Kind::Link | Kind::Checkbox | Kind::Radio => keep(mi),
+    Kind::Group => {
+      keep(mi) && mi.children.iter().any(|c| has_content(c, keep))
+    }
+    Kind::Header | Kind::Separator => false
+  }
+}
+
+impl<C: Clone> MenuItem<C> {
+  /// Clone the menu item, but not its children.
+  pub(crate) fn clone_shallow(&self) -> Self {
+    MenuItem {
+      miid: self.miid.clone(),
+      title: self.title.clone(),
+      weight: self.weight,
+      kind: self.kind,
+      pres: self.pres.clone(),
+      radio: self.radio.clone(),
+      checked: self.checked,
+      accel: self.accel.clone(),
+      mnemonic: self.mnemonic.clone(),
+      children: Vec::new(),
+      reqs: self.reqs.clone(),
+      visible_if: self.visible_if.clone(),
+      placement: self.placement.clone(),
+      seq: self.seq,
+      appctx: self.appctx.clone()
+    }
+  }
+}
+
+impl<C> Hash for MenuItem<C> {
   fn hash<H: Hasher>(&self, state: &mut H) {
     self.miid.hash(state);
   }
@@ -151,7 +864,7 @@ mod tests {
     bldr.weight(10);
     let mi2 = bldr.build();
 
-    let mut menuitems = vec![Arc::new(mi1), Arc::new(mi2)];
+    let mut menuitems = [Arc::new(mi1), Arc::new(mi2)];
 
     assert_eq!(menuitems.len(), 2);
     assert_eq!(menuitems[0].title(), "Second");
@@ -172,7 +885,7 @@ mod tests {
     let bldr = Builder::new("test-2", "B menu item");
     let mi2 = bldr.build();
 
-    let mut menuitems = vec![Arc::new(mi2), Arc::new(mi1)];
+    let mut menuitems = [Arc::new(mi2), Arc::new(mi1)];
 
     assert_eq!(menuitems.len(), 2);
     assert_eq!(menuitems[0].title(), "B menu item");
@@ -0,0 +1,268 @@
+//! Sibling ordering strategies.
+//!
+//! By default siblings are ordered by weight, and items with equal weights
+//! by title, comparing the titles byte by byte.  An [`Order`] passed to
+//! [`menu::Builder::order()`](crate::menu::Builder::order) replaces the
+//! comparison of titles with declaration order, natural ordering or a locale
+//! aware collation, or replaces the entire comparison with a custom one.
+//!
+//! Whatever the strategy, items that still compare equal are ordered by
+//! their identifiers, so the resulting order is always deterministic.
+//! Placement constraints (see
+//! [`menuitem::Builder::before()`](crate::menuitem::Builder::before)) take
+//! precedence over the ordering strategy.
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::menuitem::MenuItem;
+
+type TitleCmp = Arc<dyn Fn(&str, &str) -> Ordering + Send + Sync>;
+type ItemCmp<C> =
+  Arc<dyn Fn(&MenuItem<C>, &MenuItem<C>) -> Ordering + Send + Sync>;
+
+/// Sibling ordering strategy.
+#[derive(Default)]
+pub enum Order<C> {
+  /// Weight, then title, compared byte by byte.
+  #[default]
+  Weight,
+
+  /// Weight, then the order in which the items were added to the menu
+  /// builder.
+  Declaration,
+
+  /// Weight, then title, using [`natural_cmp()`].
+  Natural,
+
+  /// Weight, then title, using a collation function.
+  Collate(TitleCmp),
+
+  /// A custom comparison of menu items.  The weights are not considered
+  /// unless the comparison does so itself.
+  Custom(ItemCmp<C>)
+}
+
+impl<C> Order<C> {
+  /// Order titles using the built-in collation for `locale`.  See
+  /// [`Collation`].
+  pub fn collate(locale: &str) -> Self {
+    let coll = Collation::new(locale);
+    Order::Collate(Arc::new(move |a, b| coll.compare(a, b)))
+  }
+
+  /// Order titles using the collation function `f`, for instance one
+  /// provided by a full Unicode collation library.
+  pub fn collate_with<F>(f: F) -> Self
+  where
+    F: Fn(&str, &str) -> Ordering + Send + Sync + 'static
+  {
+    Order::Collate(Arc::new(f))
+  }
+
+  /// Order items using the comparison function `f`.
+  pub fn custom<F>(f: F) -> Self
+  where
+    F: Fn(&MenuItem<C>, &MenuItem<C>) -> Ordering + Send + Sync + 'static
+  {
+    Order::Custom(Arc::new(f))
+  }
+
+  /// Compare two sibling menu items.
+  pub fn compare(&self, a: &MenuItem<C>, b: &MenuItem<C>) -> Ordering {
+    let ord = match self {
+      Order::Weight => a.order_cmp(b),
+      Order::Custom(f) => f(a, b),
+      _ => a.weight().cmp(&b.weight()).then_with(|| match self {
+        Order::Declaration => a.seq.cmp(&b.seq),
+        Order::Natural => natural_cmp(a.title(), b.title()),
+        Order::Collate(f) => f(a.title(), b.title()),
+        _ => Ordering::Equal
+      })
+    };
+    ord.then_with(|| a.id().cmp(b.id()))
+  }
+
+  /// Convert the strategy for use with a different context type.  Custom
+  /// comparisons can not be converted, and are replaced by [`Order::Weight`].
+  pub(crate) fn convert<D>(&self) -> Order<D> {
+    match self {
+      Order::Weight | Order::Custom(_) => Order::Weight,
+      Order::Declaration => Order::Declaration,
+      Order::Natural => Order::Natural,
+      Order::Collate(f) => Order::Collate(Arc::clone(f))
+    }
+  }
+}
+
+impl<C> Clone for Order<C> {
+  fn clone(&self) -> Self {
+    match self {
+      Order::Custom(f) => Order::Custom(Arc::clone(f)),
+      _ => self.convert()
+    }
+  }
+}
+
+impl<C> fmt::Debug for Order<C> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Order::Weight => write!(f, "Weight"),
+      Order::Declaration => write!(f, "Declaration"),
+      Order::Natural => write!(f, "Natural"),
+      Order::Collate(_) => write!(f, "Collate(..)"),
+      Order::Custom(_) => write!(f, "Custom(..)")
+    }
+  }
+}
+
+
+/// Compare two strings, treating runs of digits as numbers and ignoring case
+/// differences, so that `"Item 2"` comes before `"Item 10"` and `"apple"`
+/// before `"Zebra"`.
+///
+/// Strings that only differ in case or in leading zeros are compared byte by
+/// byte.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+  let mut ai = a.chars().peekable();
+  let mut bi = b.chars().peekable();
+  loop {
+    match (ai.peek().copied(), bi.peek().copied()) {
+      (None, None) => return a.cmp(b),
+      (None, Some(_)) => return Ordering::Less,
+      (Some(_), None) => return Ordering::Greater,
+      (Some(ac), Some(bc)) if ac.is_ascii_digit() && bc.is_ascii_digit() => {
+        let an = digits(&mut ai);
+        let bn = digits(&mut bi);
+        let ord = an.len().cmp(&bn.len()).then_with(|| an.cmp(&bn));
+        if ord != Ordering::Equal {
+          return ord;
+        }
+      }
+      (Some(ac), Some(bc)) => {
+        let ord = ac.to_lowercase().cmp(bc.to_lowercase());
+        if ord != Ordering::Equal {
+          return ord;
+        }
+        ai.next();
+        bi.next();
+      }
+    }
+  }
+}
+
+/// Consume a run of digits, and return it without leading zeros.
+fn digits<I>(it: &mut std::iter::Peekable<I>) -> String
+where
+  I: Iterator<Item = char>
+{
+  let mut s = String::new();
+  while let Some(c) = it.peek().copied().filter(char::is_ascii_digit) {
+    if !(s.is_empty() && c == '0') {
+      s.push(c);
+    }
+    it.next();
+  }
+  s
+}
+
+
+/// A simple locale aware collation for Latin script titles.
+///
+/// Letters are compared without regard to accents and case first; accents
+/// and then case only decide between strings that are otherwise equal.
+/// Locale tailorings place letters that have a position of their own in the
+/// alphabet after `z`:
+///
+/// - `sv`, `fi`: å, ä, ö (æ and ø sort as ä and ö)
+/// - `da`, `nb`, `nn`, `no`: æ, ø, å (ä and ö sort as æ and ø)
+///
+/// Other locales treat accented letters as their base letters.  For full
+/// Unicode collation, use [`Order::collate_with()`] with an external
+/// collation library.
+#[derive(Clone, Debug)]
+pub struct Collation {
+  extra: &'static [(char, u32)]
+}
+
+const SWEDISH: &[(char, u32)] =
+  &[('å', 1), ('ä', 2), ('æ', 2), ('ö', 3), ('ø', 3)];
+const DANISH: &[(char, u32)] =
+  &[('æ', 1), ('ä', 1), ('ø', 2), ('ö', 2), ('å', 3)];
+
+impl Collation {
+  /// Create a collation for `locale`, which is a language tag such as `sv`
+  /// or `sv-SE`.
+  pub fn new(locale: &str) -> Self {
+    let lang = locale
+      .split(['-', '_'])
+      .next()
+      .unwrap_or("")
+      .to_ascii_lowercase();
+    let extra = match lang.as_str() {
+      "sv" | "fi" => SWEDISH,
+      "da" | "nb" | "nn" | "no" => DANISH,
+      _ => &[]
+    };
+    Collation { extra }
+  }
+
+  pub fn compare(&self, a: &str, b: &str) -> Ordering {
+    let ka = self.keys(a);
+    let kb = self.keys(b);
+    ka.cmp(&kb).then_with(|| a.cmp(b))
+  }
+
+  /// Generate the collation keys of a string.  Keys are compared level by
+  /// level: base letters, then accents, then case.
+  ///
+  /// Base letters are keyed by their code point, and a tailoring rank which
+  /// is zero for all but the tailored letters.  Tailored letters are keyed as
+  /// `z` with a non-zero rank, so they sort after `z` but before any
+  /// character that follows it.
+  fn keys(&self, s: &str) -> (Vec<(u32, u32)>, Vec<u32>, Vec<bool>) {
+    let mut primary = Vec::new();
+    let mut secondary = Vec::new();
+    let mut tertiary = Vec::new();
+    for c in s.chars() {
+      let lc = c.to_lowercase().next().unwrap_or(c);
+      tertiary.push(c != lc);
+      if let Some((_, n)) = self.extra.iter().find(|(x, _)| *x == lc) {
+        primary.push(('z' as u32, *n));
+        secondary.push(0);
+        continue;
+      }
+      let (base, accent) = fold(lc);
+      primary.extend(base.chars().map(|b| (b as u32, 0)));
+      secondary.push(accent);
+    }
+    (primary, secondary, tertiary)
+  }
+}
+
+/// Map a lower case letter to its base letter(s) and an accent weight.
+fn fold(c: char) -> (String, u32) {
+  let base = match c {
+    'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+    'ç' | 'ć' | 'č' => "c",
+    'ď' | 'đ' => "d",
+    'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ę' | 'ě' => "e",
+    'ì' | 'í' | 'î' | 'ï' | 'ī' => "i",
+    'ł' => "l",
+    'ñ' | 'ń' | 'ň' => "n",
+    'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+    'ř' => "r",
+    'ś' | 'š' => "s",
+    'ť' => "t",
+    'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+    'ý' | 'ÿ' => "y",
+    'ź' | 'ż' | 'ž' => "z",
+    'æ' => "ae",
+    'œ' => "oe",
+    'ß' => "ss",
+    _ => return (c.to_string(), 0)
+  };
+  (base.to_string(), c as u32)
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,697 @@
+//! Menu patches and three-way merges.
+//!
+//! A [`Patch`] is a list of [`Command`]s which can be applied to a built
+//! [`Menu`] or to a [`menu::Builder`](crate::menu::Builder).  Menu items are
+//! referred to by their identifiers, so a patch generated from one copy of a
+//! menu can be applied to another copy, or to a customized version of it.
+//!
+//! [`merge()`] carries the changes between two versions of a menu over to a
+//! third version which has been derived from the first.  The typical use is
+//! to apply an updated default menu to a menu which a customer has
+//! customized.  Changes that do not interfere with each other are combined;
+//! changes that do are reported as [`Conflict`]s, and resolved in favour of
+//! the customized menu.
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::accel::Accel;
+use crate::edit::{Error, Removal};
+use crate::history::{self, Command};
+use crate::menu::{self, Menu};
+use crate::menuitem::{self, Kind, MenuItem, Presentation};
+
+/// A sequence of changes to a menu.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Patch<C> {
+  pub ops: Vec<Command<C>>
+}
+
+impl<C> Default for Patch<C> {
+  fn default() -> Self {
+    Patch { ops: Vec::new() }
+  }
+}
+
+impl<C> Patch<C> {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.ops.is_empty()
+  }
+
+  /// Generate the patch which turns `old` into `new`.
+  ///
+  /// Items that exist in `new` but not in `old` are added as copies of the
+  /// items in `new`.  Of the existing items, changes to the parent, title,
+  /// weight, kind, presentation metadata and accelerator are part of the
+  /// patch; other changes, such as context changes, are not.
+  pub fn between(old: &Menu<C>, new: &Menu<C>) -> Self
+  where
+    C: Clone
+  {
+    plan(&nodes(old), &nodes(new), |id| copy(new, id))
+  }
+
+  /// Apply the patch to a built menu.
+  ///
+  /// Stops at the first operation that fails; the operations before it
+  /// remain applied.
+  pub fn apply(self, menu: &mut Menu<C>) -> Result<(), Error> {
+    history::replay(menu, self.ops)
+  }
+
+  /// Apply the patch to a menu builder.
+  ///
+  /// The operations are validated the same way as when they are applied to a
+  /// built menu.  Stops at the first operation that fails; the operations
+  /// before it remain applied.
+  pub fn apply_to_builder(
+    self,
+    mb: &mut menu::Builder<C>
+  ) -> Result<(), Error> {
+    for op in self.ops {
+      apply_builder(mb, op)?;
+    }
+    Ok(())
+  }
+}
+
+fn apply_builder<C>(
+  mb: &mut menu::Builder<C>,
+  op: Command<C>
+) -> Result<(), Error> {
+  let items = &mut mb.items;
+  let known = |items: &HashMap<String, menuitem::Builder<C>>, id: &str| {
+    if items.contains_key(id) {
+      Ok(())
+    } else {
+      Err(Error::UnknownItem(id.to_string()))
+    }
+  };
+  let known_parent = |items: &HashMap<String, menuitem::Builder<C>>,
+                      parent: &Option<String>| {
+    match parent {
+      Some(p) if !items.contains_key(p) => {
+        Err(Error::UnknownParent(p.to_string()))
+      }
+      _ => Ok(())
+    }
+  };
+
+  match op {
+    Command::Add { parent, item } => {
+      known_parent(items, &parent)?;
+      // The item is added along with its children, as separate builders,
+      // parents first
+      let mut pending = VecDeque::from(vec![(parent, *item)]);
+      let mut ids = HashSet::new();
+      let mut mibs = Vec::new();
+      while let Some((parent, mi)) = pending.pop_front() {
+        let id = mi.id().to_string();
+        if items.contains_key(&id) || !ids.insert(id.clone()) {
+          return Err(Error::DuplicateId(id));
+        }
+        let (mib, children) = mi.into_builder(parent);
+        mibs.push(mib);
+        pending.extend(children.into_iter().map(|c| (Some(id.clone()), c)));
+      }
+      for mib in mibs {
+        mb.add(mib);
+      }
+    }
+    Command::Remove { id, policy } => {
+      known(items, &id)?;
+      let children: Vec<String> = items
+        .values()
+        .filter(|mib| mib.parent.as_deref() == Some(id.as_str()))
+        .map(|mib| mib.miid.clone())
+        .collect();
+      match policy {
+        Removal::Reject if !children.is_empty() => {
+          return Err(Error::HasChildren(id));
+        }
+        Removal::Reparent => {
+          let parent = items[&id].parent.clone();
+          for child in children {
+            if let Some(mib) = items.get_mut(&child) {
+              mib.parent = parent.clone();
+            }
+          }
+        }
+        _ => {
+          let mut pending = children;
+          while let Some(child) = pending.pop() {
+            pending.extend(
+              items
+                .values()
+                .filter(|mib| mib.parent.as_deref() == Some(child.as_str()))
+                .map(|mib| mib.miid.clone())
+            );
+            items.remove(&child);
+          }
+        }
+      }
+      items.remove(&id);
+    }
+    Command::Move { id, parent } => {
+      known(items, &id)?;
+      known_parent(items, &parent)?;
+      // Walk up from the new parent to make sure the item is not one of its
+      // ancestors.
+      let mut cur = parent.clone();
+      while let Some(p) = cur {
+        if p == id {
+          return Err(Error::Cycle(id));
+        }
+        cur = items.get(&p).and_then(|mib| mib.parent.clone());
+      }
+      items.get_mut(&id).unwrap().parent = parent;
+    }
+    Command::Retitle { id, title } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().title = title;
+    }
+    Command::Reweight { id, weight } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().weight = weight;
+    }
+    Command::SetContext { id, ctx } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().appctx = ctx;
+    }
+    Command::SetKind { id, kind } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().kind = kind;
+    }
+    Command::SetPresentation { id, presentation } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().pres = presentation;
+    }
+    Command::SetAccel { id, accel } => {
+      known(items, &id)?;
+      items.get_mut(&id).unwrap().accel = accel;
+    }
+  }
+  Ok(())
+}
+
+
+/// The properties of a menu item that patches keep track of.
+#[derive(Clone, PartialEq)]
+struct Node {
+  parent: Option<String>,
+  title: String,
+  weight: isize,
+  kind: Kind,
+  pres: Presentation,
+  accel: Option<Accel>
+}
+
+type Nodes = HashMap<String, Node>;
+
+/// Copy the menu item `id` of `menu`, without its children, for adding it
+/// to another menu.
+fn copy<C>(menu: &Menu<C>, id: &str) -> MenuItem<C>
+where
+  C: Clone
+{
+  let mut mi = menu.get(id).unwrap().clone_shallow();
+  mi.seq = 0;
+  mi
+}
+
+fn nodes<C>(menu: &Menu<C>) -> Nodes {
+  fn rec<C>(lst: &[MenuItem<C>], parent: Option<&str>, out: &mut Nodes) {
+    for mi in lst {
+      out.insert(
+        mi.id().to_string(),
+        Node {
+          parent: parent.map(|s| s.to_string()),
+          title: mi.title().to_string(),
+          weight: mi.weight(),
+          kind: mi.kind(),
+          pres: mi.presentation().clone(),
+          accel: mi.accel().cloned()
+        }
+      );
+      rec(&mi.children, Some(mi.id()), out);
+    }
+  }
+  let mut out = HashMap::new();
+  rec(&menu.rootlst, None, &mut out);
+  out
+}
+
+fn depth(nodes: &Nodes, id: &str) -> usize {
+  let mut d = 0;
+  let mut cur = nodes.get(id).and_then(|n| n.parent.as_deref());
+  while let Some(p) = cur {
+    d += 1;
+    cur = nodes.get(p).and_then(|n| n.parent.as_deref());
+  }
+  d
+}
+
+/// Generate the operations which turn `from` into `to`.
+///
+/// Items are added and moved parents first, so every parent is in place
+/// before its children are attached to it.  Items are removed last, children
+/// first, so each removed item is a leaf by the time it is removed.
+fn plan<C, F>(from: &Nodes, to: &Nodes, mut item: F) -> Patch<C>
+where
+  F: FnMut(&str) -> MenuItem<C>
+{
+  let mut ops = Vec::new();
+
+  let mut ids: Vec<(usize, &String)> =
+    to.keys().map(|id| (depth(to, id), id)).collect();
+  ids.sort();
+  for (_, id) in ids {
+    let n = &to[id];
+    let o = match from.get(id) {
+      Some(o) => o,
+      None => {
+        ops.push(Command::Add {
+          parent: n.parent.clone(),
+          item: Box::new(item(id))
+        });
+        continue;
+      }
+    };
+    if o.parent != n.parent {
+      ops.push(Command::Move {
+        id: id.clone(),
+        parent: n.parent.clone()
+      });
+    }
+    if o.title != n.title {
+      ops.push(Command::Retitle {
+        id: id.clone(),
+        title: n.title.clone()
+      });
+    }
+    if o.weight != n.weight {
+      ops.push(Command::Reweight {
+        id: id.clone(),
+        weight: n.weight
+      });
+    }
+    if o.kind != n.kind {
+      ops.push(Command::SetKind {
+        id: id.clone(),
+        kind: n.kind
+      });
+    }
+    if o.pres != n.pres {
+      ops.push(Command::SetPresentation {
+        id: id.clone(),
+        presentation: n.pres.clone()
+      });
+    }
+    if o.accel != n.accel {
+      ops.push(Command::SetAccel {
+        id: id.clone(),
+        accel: n.accel.clone()
+      });
+    }
+  }
+
+  let mut ids: Vec<(usize, &String)> = from
+    .keys()
+    .filter(|id| !to.contains_key(*id))
+    .map(|id| (depth(from, id), id))
+    .collect();
+  ids.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+  for (_, id) in ids {
+    ops.push(Command::Remove {
+      id: id.clone(),
+      policy: Removal::Reject
+    });
+  }
+
+  Patch { ops }
+}
+
+
+/// One of the two derived menus in a three-way merge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Side {
+  Ours,
+  Theirs
+}
+
+impl fmt::Display for Side {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Side::Ours => write!(f, "ours"),
+      Side::Theirs => write!(f, "theirs")
+    }
+  }
+}
+
+/// A change which could not be merged.  All conflicts are resolved by
+/// keeping "our" version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Conflict {
+  /// Both sides placed the item under different parents.
+  Parent {
+    id: String,
+    ours: Option<String>,
+    theirs: Option<String>
+  },
+
+  /// Both sides changed the item's title.
+  Title {
+    id: String,
+    ours: String,
+    theirs: String
+  },
+
+  /// Both sides changed the item's weight.
+  Weight {
+    id: String,
+    ours: isize,
+    theirs: isize
+  },
+
+  /// Both sides changed the item's kind.
+  Kind {
+    id: String,
+    ours: Kind,
+    theirs: Kind
+  },
+
+  /// Both sides changed the item's presentation metadata.
+  Presentation {
+    id: String,
+    ours: Presentation,
+    theirs: Presentation
+  },
+
+  /// Both sides changed the item's accelerator.
+  Accel {
+    id: String,
+    ours: Option<Accel>,
+    theirs: Option<Accel>
+  },
+
+  /// One side removed the item, while the other side modified it or added
+  /// children to it.
+  Removed { id: String, by: Side },
+
+  /// Their side placed the item under a parent which our side removed.
+  MissingParent { id: String, parent: String },
+
+  /// Combining the moves of both sides would make the item its own ancestor.
+  Cycle { id: String }
+}
+
+impl fmt::Display for Conflict {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let parent_str = |p: &Option<String>| match p {
+      Some(p) => p.clone(),
+      None => "<root>".to_string()
+    };
+    let accel_str = |a: &Option<Accel>| match a {
+      Some(a) => a.to_string(),
+      None => "none".to_string()
+    };
+    match self {
+      Conflict::Parent { id, ours, theirs } => write!(
+        f,
+        "{}: moved to {} by ours and to {} by theirs",
+        id,
+        parent_str(ours),
+        parent_str(theirs)
+      ),
+      Conflict::Title { id, ours, theirs } => write!(
+        f,
+        "{}: retitled to {:?} by ours and to {:?} by theirs",
+        id, ours, theirs
+      ),
+      Conflict::Weight { id, ours, theirs } => write!(
+        f,
+        "{}: reweighted to {} by ours and to {} by theirs",
+        id, ours, theirs
+      ),
+      Conflict::Kind { id, ours, theirs } => write!(
+        f,
+        "{}: changed to a {} by ours and to a {} by theirs",
+        id, ours, theirs
+      ),
+      Conflict::Presentation { id, .. } => {
+        write!(f, "{}: presentation changed by both sides", id)
+      }
+      Conflict::Accel { id, ours, theirs } => write!(
+        f,
+        "{}: accelerator set to {} by ours and to {} by theirs",
+        id,
+        accel_str(ours),
+        accel_str(theirs)
+      ),
+      Conflict::Removed { id, by } => {
+        write!(
+          f,
+          "{}: removed by {} but modified by the other side",
+          id, by
+        )
+      }
+      Conflict::MissingParent { id, parent } => {
+        write!(
+          f,
+          "{}: placed under {}, which was removed by ours",
+          id, parent
+        )
+      }
+      Conflict::Cycle { id } => {
+        write!(f, "{}: combined moves would form a cycle", id)
+      }
+    }
+  }
+}
+
+/// The result of a three-way merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge<C> {
+  /// The patch which applies the merged changes to "our" menu.
+  pub patch: Patch<C>,
+  pub conflicts: Vec<Conflict>
+}
+
+impl<C> Merge<C> {
+  /// Return `true` if the merge had no conflicts.
+  pub fn is_clean(&self) -> bool {
+    self.conflicts.is_empty()
+  }
+}
+
+/// Merge the changes between `base` and `theirs` into `ours`.
+///
+/// `ours` and `theirs` are both assumed to be derived from `base`.  The
+/// returned patch turns `ours` into the merged menu.  Items added by their
+/// side are added as copies of the items in `theirs`.
+pub fn merge<C>(base: &Menu<C>, ours: &Menu<C>, theirs: &Menu<C>) -> Merge<C>
+where
+  C: Clone
+{
+  let b = nodes(base);
+  let o = nodes(ours);
+  let t = nodes(theirs);
+
+  let mut merged = o.clone();
+  let mut conflicts = Vec::new();
+
+  let ids: BTreeSet<&String> =
+    b.keys().chain(o.keys()).chain(t.keys()).collect();
+  for id in ids {
+    match (b.get(id), o.get(id), t.get(id)) {
+      (Some(bn), Some(on), None) => {
+        if on == bn {
+          merged.remove(id);
+        } else {
+          conflicts.push(Conflict::Removed {
+            id: id.clone(),
+            by: Side::Theirs
+          });
+        }
+      }
+      (Some(bn), None, Some(tn)) if tn != bn => {
+        conflicts.push(Conflict::Removed {
+          id: id.clone(),
+          by: Side::Ours
+        });
+      }
+      (None, None, Some(tn)) => {
+        merged.insert(id.clone(), tn.clone());
+      }
+      (bn, Some(on), Some(tn)) => {
+        let m = merged.get_mut(id).unwrap();
+        if let Some(v) = pick(bn.map(|n| &n.parent), &on.parent, &tn.parent) {
+          m.parent = v;
+        } else {
+          conflicts.push(Conflict::Parent {
+            id: id.clone(),
+            ours: on.parent.clone(),
+            theirs: tn.parent.clone()
+          });
+        }
+        if let Some(v) = pick(bn.map(|n| &n.title), &on.title, &tn.title) {
+          m.title = v;
+        } else {
+          conflicts.push(Conflict::Title {
+            id: id.clone(),
+            ours: on.title.clone(),
+            theirs: tn.title.clone()
+          });
+        }
+        if let Some(v) = pick(bn.map(|n| &n.weight), &on.weight, &tn.weight) {
+          m.weight = v;
+        } else {
+          conflicts.push(Conflict::Weight {
+            id: id.clone(),
+            ours: on.weight,
+            theirs: tn.weight
+          });
+        }
+        if let Some(v) = pick(bn.map(|n| &n.kind), &on.kind, &tn.kind) {
+          m.kind = v;
+        } else {
+          conflicts.push(Conflict::Kind {
+            id: id.clone(),
+            ours: on.kind,
+            theirs: tn.kind
+          });
+        }
+        if let Some(v) = pick(bn.map(|n| &n.pres), &on.pres, &tn.pres) {
+          m.pres = v;
+        } else {
+          conflicts.push(Conflict::Presentation {
+            id: id.clone(),
+            ours: on.pres.clone(),
+            theirs: tn.pres.clone()
+          });
+        }
+        if let Some(v) = pick(bn.map(|n| &n.accel), &on.accel, &tn.accel) {
+          m.accel = v;
+        } else {
+          conflicts.push(Conflict::Accel {
+            id: id.clone(),
+            ours: on.accel.clone(),
+            theirs: tn.accel.clone()
+          });
+        }
+      }
+      _ => {}
+    }
+  }
+
+  while repair_parents(&mut merged, &o, &mut conflicts)
+    || repair_cycles(&mut merged, &o, &mut conflicts)
+  {}
+
+  let patch = plan(&o, &merged, |id| copy(theirs, id));
+  Merge { patch, conflicts }
+}
+
+/// Pick the merged value of a property, or `None` if both sides changed it
+/// in different ways.
+fn pick<T>(base: Option<&T>, ours: &T, theirs: &T) -> Option<T>
+where
+  T: PartialEq + Clone
+{
+  if ours == theirs || base == Some(theirs) {
+    Some(ours.clone())
+  } else if base == Some(ours) {
+    Some(theirs.clone())
+  } else {
+    None
+  }
+}
+
+/// Fix the first item whose parent does not exist in the merged menu.
+/// Returns `false` if there was none.
+fn repair_parents(
+  merged: &mut Nodes,
+  ours: &Nodes,
+  conflicts: &mut Vec<Conflict>
+) -> bool {
+  let missing = merged
+    .iter()
+    .filter_map(|(id, n)| {
+      n.parent
+        .as_ref()
+        .filter(|p| !merged.contains_key(*p))
+        .map(|p| (id.clone(), p.clone()))
+    })
+    .min();
+  let (id, parent) = match missing {
+    Some(m) => m,
+    None => return false
+  };
+
+  if let Some(pn) = ours.get(&parent) {
+    // Their side removed an item which our side still uses; keep it
+    merged.insert(parent.clone(), pn.clone());
+    conflicts.push(Conflict::Removed {
+      id: parent,
+      by: Side::Theirs
+    });
+  } else {
+    // Our side removed the parent; leave the item where we had it, if we
+    // had it at all.
+    match ours.get(&id) {
+      Some(on) => merged.get_mut(&id).unwrap().parent = on.parent.clone(),
+      None => {
+        merged.remove(&id);
+      }
+    }
+    conflicts.push(Conflict::MissingParent { id, parent });
+  }
+  true
+}
+
+/// Fix the first cycle in the merged menu by restoring our parents for all
+/// the items in it.  Returns `false` if there was no cycle.
+fn repair_cycles(
+  merged: &mut Nodes,
+  ours: &Nodes,
+  conflicts: &mut Vec<Conflict>
+) -> bool {
+  let mut ids: Vec<String> = merged.keys().cloned().collect();
+  ids.sort();
+  for id in ids {
+    let mut cycle = vec![id.clone()];
+    let mut cur = merged[&id].parent.clone();
+    while let Some(p) = cur {
+      if p == id {
+        for member in &cycle {
+          if let Some(on) = ours.get(member) {
+            merged.get_mut(member).unwrap().parent = on.parent.clone();
+          }
+        }
+        conflicts.push(Conflict::Cycle {
+          id: cycle[0].clone()
+        });
+        return true;
+      }
+      if cycle.len() > merged.len() {
+        // Part of a cycle that does not include `id`; it will be found when
+        // its members are visited.
+        break;
+      }
+      cur = merged.get(&p).and_then(|n| n.parent.clone());
+      cycle.push(p);
+    }
+  }
+  false
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,109 @@
+//! Access requirements for menu items.
+//!
+//! Menu items can declare permissions (roles, capabilities, ...) that are
+//! required to access them, using `menuitem::Builder::require_any()` and
+//! `menuitem::Builder::require_all()`.  The requirements are evaluated
+//! against a [`Principal`], which represents the current user (or whatever
+//! entity permissions are checked for).
+use std::collections::{BTreeSet, HashSet};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An entity which may hold permissions.
+pub trait Principal {
+  /// Return `true` if the principal holds the permission `perm`.
+  fn has_perm(&self, perm: &str) -> bool;
+}
+
+impl Principal for HashSet<String> {
+  fn has_perm(&self, perm: &str) -> bool {
+    self.contains(perm)
+  }
+}
+
+impl Principal for HashSet<&str> {
+  fn has_perm(&self, perm: &str) -> bool {
+    self.contains(perm)
+  }
+}
+
+impl Principal for BTreeSet<String> {
+  fn has_perm(&self, perm: &str) -> bool {
+    self.contains(perm)
+  }
+}
+
+impl Principal for [&str] {
+  fn has_perm(&self, perm: &str) -> bool {
+    self.contains(&perm)
+  }
+}
+
+impl<F> Principal for F
+where
+  F: Fn(&str) -> bool
+{
+  fn has_perm(&self, perm: &str) -> bool {
+    self(perm)
+  }
+}
+
+
+/// The permissions required to access a menu item.
+///
+/// A principal meets the requirements if it holds at least one of the `any`
+/// permissions (unless there are none) and all of the `all` permissions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Requirements {
+  pub(crate) any: Vec<String>,
+  pub(crate) all: Vec<String>,
+  pub(crate) inherit: bool
+}
+
+impl Default for Requirements {
+  fn default() -> Self {
+    Requirements {
+      any: Vec::new(),
+      all: Vec::new(),
+      inherit: true
+    }
+  }
+}
+
+impl Requirements {
+  /// Permissions of which at least one is required.
+  pub fn any(&self) -> &[String] {
+    &self.any
+  }
+
+  /// Permissions which are all required.
+  pub fn all(&self) -> &[String] {
+    &self.all
+  }
+
+  /// Whether the item also requires its parent's requirements to be met.
+  pub fn inherits(&self) -> bool {
+    self.inherit
+  }
+
+  /// Return `true` if no permissions are required.
+  pub fn is_empty(&self) -> bool {
+    self.any.is_empty() && self.all.is_empty()
+  }
+
+  /// Return `true` if the principal `p` meets these requirements.
+  ///
+  /// This only checks the item's own requirements; see `Menu::permits()` for
+  /// a check that takes inheritance into account.
+  pub fn check<P>(&self, p: &P) -> bool
+  where
+    P: Principal + ?Sized
+  {
+    (self.any.is_empty() || self.any.iter().any(|perm| p.has_perm(perm)))
+      && self.all.iter().all(|perm| p.has_perm(perm))
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -1,56 +1,129 @@
-use crate::menu::Menu;
-use crate::menuitem::MenuItem;
-
-pub enum Event<'a, C>
-where
-  C: Clone + Default
-{
+use crate::checks::{self, Store};
+use crate::menu::{Menu, Visibility};
+use crate::menuitem::{self, MenuItem};
+use crate::reciter::{Expand, Expansion, Item};
+
+/// Iteration events.
+///
+/// New kinds of events may be added in future versions, so matches on events
+/// need a wildcard arm.
+#[non_exhaustive]
+pub enum Event<'a, C> {
   EnterScope,
   LeaveScope,
-  MenuItem(&'a MenuItem<C>)
+  MenuItem(Item<'a, C>),
+
+  /// A menu item that has children, but which the iterator will not step
+  /// into because the expansion policy says it is collapsed.
+  Collapsed(Item<'a, C>),
+
+  /// A menu item that is only shown because it leads to other items (see
+  /// [`MenuIter::containers()`]).  It must not be acted on.  If it is
+  /// expanded its children follow in a scope of their own.
+  Container(Item<'a, C>)
 }
 
-struct IterNode<'a, C>
-where
-  C: Clone + Default
-{
+type ContainerFn<'a, C> = Box<dyn Fn(&MenuItem<C>) -> bool + 'a>;
+
+struct IterNode<'a, C> {
   lst: &'a Vec<MenuItem<C>>,
   idx: usize,
+  depth: usize,
+
+  /// Which items of `lst` are shown, computed when the list is first visited
+  shown: Option<Vec<bool>>,
   did_enter_scope: bool,
   did_leave_scope: bool
 }
 
 pub struct MenuIter<'a, C, F>
 where
-  C: Clone + Default,
   F: Fn(&MenuItem<C>) -> bool
 {
+  menu: &'a Menu<C>,
   stack: Vec<IterNode<'a, C>>,
-  myfilter: F
+  myfilter: F,
+  expansion: Expansion<'a, C>,
+  checks: Option<&'a Store>,
+  containers: Option<ContainerFn<'a, C>>
 }
 
 impl<'a, C, F> MenuIter<'a, C, F>
 where
-  C: Clone + Default,
   F: Fn(&MenuItem<C>) -> bool
 {
   pub fn new(menu: &'a Menu<C>, p: F) -> Self {
-    let mut iterstack = Vec::new();
-
     // Default to not scoping the root nodes
-    iterstack.push(IterNode {
+    let iterstack = vec![IterNode {
       lst: &menu.rootlst,
       idx: 0,
+      depth: 0,
+      shown: None,
       did_enter_scope: true,
       did_leave_scope: true
-    });
+    }];
 
     MenuIter {
+      menu,
       stack: iterstack,
-      myfilter: p
+      myfilter: p,
+      expansion: Expansion::default(),
+      checks: None,
+      containers: None
+    }
+  }
+
+  /// Report the shown menu items for which `g` returns `true` using
+  /// `Event::Container` rather than `Event::MenuItem` or `Event::Collapsed`.
+  ///
+  /// # Constraints
+  /// This setting must only be changed before iteration has started.
+  pub fn containers<G>(&mut self, g: G) -> &mut Self
+  where
+    G: Fn(&MenuItem<C>) -> bool + 'a
+  {
+    self.containers = Some(Box::new(g));
+    self
+  }
+
+  fn is_container(&self, mi: &MenuItem<C>) -> bool {
+    match self.containers {
+      Some(ref g) => g(mi),
+      None => false
     }
   }
 
+  /// Set the expansion policy.  Menu items with children that the policy
+  /// considers collapsed are reported using `Event::Collapsed` and their
+  /// children are skipped.
+  ///
+  /// # Constraints
+  /// This setting must only be changed before iteration has started.
+  pub fn expansion(&mut self, expand: Expand<'a, C>) -> &mut Self {
+    self.expansion = Expansion::new(self.menu, expand);
+    self
+  }
+
+  /// Report the state of checkbox and radio items as recorded in `store`,
+  /// in the `checked` field of the events' [`Item`]s.
+  pub fn checks(&mut self, store: &'a Store) -> &mut Self {
+    self.checks = Some(store);
+    self
+  }
+
+  /// Return `true` if `mi` is a checkbox or radio item that is checked.  The
+  /// state is taken from the store set using [`MenuIter::checks()`], or else
+  /// is the item's initial state.
+  pub fn is_checked(&self, mi: &MenuItem<C>) -> bool {
+    checks::is_checked(mi, self.checks)
+  }
+
+  /// Explain whether the menu item with the identifier `miid` is visible
+  /// using this iterator's filter, and if not, why.
+  pub fn explain(&self, miid: &str) -> Visibility {
+    self.menu.explain(miid, &self.myfilter)
+  }
+
   /// Tell the iterator to scope the root items.  By default the iterator will
   /// not generate scope events for the root elements.
   ///
@@ -68,22 +141,21 @@ where
 
 impl<'a, C, F> Iterator for MenuIter<'a, C, F>
 where
-  C: Clone + Default,
   F: Fn(&MenuItem<C>) -> bool
 {
   type Item = Event<'a, C>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    while !self.stack.is_empty() {
-      // last_mut()
-      let mut it = self.stack.pop().unwrap();
-
-      // If there's a filter defined then call it
+    while let Some(mut it) = self.stack.pop() {
+      // Skip entries that the filter rejects, and separators, headers and
+      // groups that have nothing left to separate, introduce or group
       if it.idx < it.lst.len() {
-        let mi = &it.lst[it.idx];
-
-        // If the filter returns false then skip this entry
-        if (self.myfilter)(mi) == false {
+        let lst = it.lst;
+        let keep = &self.myfilter;
+        let shown = it
+          .shown
+          .get_or_insert_with(|| menuitem::shown_mask(lst, keep));
+        if !shown[it.idx] {
           it.idx += 1;
           self.stack.push(it);
           continue;
@@ -91,7 +163,7 @@ where
       }
 
       // Let application know that iterator entered a new scope
-      if it.did_enter_scope == false {
+      if !it.did_enter_scope {
         it.did_enter_scope = true;
         self.stack.push(it);
         return Some(Event::EnterScope);
@@ -102,7 +174,7 @@ where
         // Before backing out make sure the application knows that a scope has
         // been left
 
-        if it.did_enter_scope == true && it.did_leave_scope == false {
+        if it.did_enter_scope && !it.did_leave_scope {
           it.did_leave_scope = true;
           self.stack.push(it);
           return Some(Event::LeaveScope);
@@ -112,18 +184,40 @@ where
       }
 
 
-      if it.lst[it.idx].children.is_empty() {
+      let mi = &it.lst[it.idx];
+      let container = self.is_container(mi);
+      if mi.children.is_empty() {
         // Don't step into -- return next child in list
-        let ret = Event::MenuItem(&it.lst[it.idx]);
+        let ret = if container {
+          Event::Container(Item::new(mi, self.checks))
+        } else {
+          Event::MenuItem(Item::new(mi, self.checks))
+        };
+        it.idx += 1;
+        self.stack.push(it);
+        return Some(ret);
+      } else if !self.expansion.is_expanded(mi, it.depth) {
+        // Collapsed parent -- report it but don't step into
+        let ret = if container {
+          Event::Container(Item::new(mi, self.checks))
+        } else {
+          Event::Collapsed(Item::new(mi, self.checks))
+        };
         it.idx += 1;
         self.stack.push(it);
         return Some(ret);
       } else {
-        let ret = Event::MenuItem(&it.lst[it.idx]);
+        let ret = if container {
+          Event::Container(Item::new(mi, self.checks))
+        } else {
+          Event::MenuItem(Item::new(mi, self.checks))
+        };
 
         self.stack.push(IterNode {
-          lst: &it.lst,
+          lst: it.lst,
           idx: it.idx + 1,
+          depth: it.depth,
+          shown: it.shown,
           did_enter_scope: it.did_enter_scope,
           did_leave_scope: it.did_leave_scope
         });
@@ -132,6 +226,8 @@ where
         self.stack.push(IterNode {
           lst: &it.lst[it.idx].children,
           idx: 0,
+          depth: it.depth + 1,
+          shown: None,
           did_enter_scope: false,
           did_leave_scope: false
         });
@@ -1,53 +1,190 @@
+use std::collections::HashSet;
+use std::ops::Deref;
+
+use crate::checks::{self, Store};
 use crate::menu::Menu;
-use crate::menuitem::MenuItem;
+use crate::menuitem::{self, MenuItem};
 
-pub enum Event<'a, C>
-where
-  C: Clone + Default
-{
+/// Iteration events.
+///
+/// New kinds of events may be added in future versions, so matches on events
+/// need a wildcard arm.
+#[non_exhaustive]
+pub enum Event<'a, C> {
   EnterScope,
   LeaveScope,
-  MenuItem(&'a MenuItem<C>)
+  MenuItem(Item<'a, C>),
+
+  /// A menu item that has children, but which the iterator will not step
+  /// into because the expansion policy says it is collapsed.
+  Collapsed(Item<'a, C>)
+}
+
+/// A menu item reported by an iteration event, along with its state.
+///
+/// Dereferences to the menu item itself.
+pub struct Item<'a, C> {
+  pub item: &'a MenuItem<C>,
+
+  /// Whether a checkbox or radio item is checked; `None` for other items.
+  /// The state is taken from the store set using the iterator's `checks()`
+  /// method, or else is the item's initial state.
+  pub checked: Option<bool>
+}
+
+impl<'a, C> Item<'a, C> {
+  pub(crate) fn new(item: &'a MenuItem<C>, store: Option<&Store>) -> Self {
+    let checked = if item.is_checkable() {
+      Some(checks::is_checked(item, store))
+    } else {
+      None
+    };
+    Item { item, checked }
+  }
+}
+
+impl<C> Clone for Item<'_, C> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<C> Copy for Item<'_, C> {}
+
+impl<C> Deref for Item<'_, C> {
+  type Target = MenuItem<C>;
+
+  fn deref(&self) -> &MenuItem<C> {
+    self.item
+  }
+}
+
+type ExpandFn<'a, C> = Box<dyn Fn(&MenuItem<C>, usize) -> bool + 'a>;
+
+/// Expansion policy, which determines which menu items with children the
+/// iterator steps into.
+pub enum Expand<'a, C> {
+  /// Step into all items (the default).
+  All,
+
+  /// Only step into the items whose identifiers are in the set.
+  Ids(HashSet<String>),
+
+  /// Only step into the items leading to the (active) item with the
+  /// specified identifier, and into the item itself.
+  Trail(String),
+
+  /// Only step into items whose depth is less than the specified depth.
+  /// Root items have depth 0, so `Depth(1)` only expands the root items.
+  Depth(usize),
+
+  /// Let a closure decide.  The closure is passed the item and its depth.
+  Fn(ExpandFn<'a, C>)
+}
+
+impl<'a, C> Expand<'a, C> {
+  /// Create an `Expand::Ids` policy from a list of identifiers.
+  pub fn ids<I, S>(ids: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: ToString
+  {
+    Expand::Ids(ids.into_iter().map(|s| s.to_string()).collect())
+  }
+
+  /// Create an `Expand::Trail` policy for the active item `miid`.
+  pub fn trail<M: ToString>(miid: M) -> Self {
+    Expand::Trail(miid.to_string())
+  }
+
+  /// Create an `Expand::Fn` policy from a closure.
+  pub fn func<F>(f: F) -> Self
+  where
+    F: Fn(&MenuItem<C>, usize) -> bool + 'a
+  {
+    Expand::Fn(Box::new(f))
+  }
 }
 
-struct IterNode<'a, C>
-where
-  C: Clone + Default
-{
+/// An expansion policy resolved against a menu.
+pub(crate) struct Expansion<'a, C> {
+  expand: Expand<'a, C>,
+  trail: HashSet<String>
+}
+
+impl<'a, C> Expansion<'a, C> {
+  pub(crate) fn new(menu: &Menu<C>, expand: Expand<'a, C>) -> Self {
+    let trail = match expand {
+      Expand::Trail(ref miid) => match menu.path_to(miid) {
+        Some(path) => path.iter().map(|mi| mi.id().to_string()).collect(),
+        None => HashSet::new()
+      },
+      _ => HashSet::new()
+    };
+    Expansion { expand, trail }
+  }
+
+  /// Return `true` if the iterator should step into `mi`, which is at depth
+  /// `depth`.
+  pub(crate) fn is_expanded(&self, mi: &MenuItem<C>, depth: usize) -> bool {
+    match self.expand {
+      Expand::All => true,
+      Expand::Ids(ref ids) => ids.contains(mi.id()),
+      Expand::Trail(_) => self.trail.contains(mi.id()),
+      Expand::Depth(n) => depth < n,
+      Expand::Fn(ref f) => f(mi, depth)
+    }
+  }
+}
+
+impl<C> Default for Expansion<'_, C> {
+  fn default() -> Self {
+    Expansion {
+      expand: Expand::All,
+      trail: HashSet::new()
+    }
+  }
+}
+
+struct IterNode<'a, C> {
   lst: &'a Vec<MenuItem<C>>,
   idx: usize,
+  depth: usize,
+
+  /// Which items of `lst` are shown, computed when the list is first visited
+  shown: Option<Vec<bool>>,
   did_enter_scope: bool,
   did_leave_scope: bool
 }
 
 type FilterFn<C> = fn(mi: &MenuItem<C>) -> bool;
 
-pub struct MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+pub struct MenuIter<'a, C> {
+  menu: &'a Menu<C>,
   stack: Vec<IterNode<'a, C>>,
-  filter: Option<FilterFn<C>>
+  filter: Option<FilterFn<C>>,
+  expansion: Expansion<'a, C>,
+  checks: Option<&'a Store>
 }
 
-impl<'a, C> MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+impl<'a, C> MenuIter<'a, C> {
   pub fn new(menu: &'a Menu<C>) -> Self {
-    let mut iterstack = Vec::new();
-
     // Default to not scoping the root nodes
-    iterstack.push(IterNode {
+    let iterstack = vec![IterNode {
       lst: &menu.rootlst,
       idx: 0,
+      depth: 0,
+      shown: None,
       did_enter_scope: true,
       did_leave_scope: true
-    });
+    }];
 
     MenuIter {
+      menu,
       stack: iterstack,
-      filter: None
+      filter: None,
+      expansion: Expansion::default(),
+      checks: None
     }
   }
 
@@ -56,6 +193,31 @@ where
     self
   }
 
+  /// Set the expansion policy.  Menu items with children that the policy
+  /// considers collapsed are reported using `Event::Collapsed` and their
+  /// children are skipped.
+  ///
+  /// # Constraints
+  /// This setting must only be changed before iteration has started.
+  pub fn expansion(&mut self, expand: Expand<'a, C>) -> &mut Self {
+    self.expansion = Expansion::new(self.menu, expand);
+    self
+  }
+
+  /// Report the state of checkbox and radio items as recorded in `store`,
+  /// in the `checked` field of the events' [`Item`]s.
+  pub fn checks(&mut self, store: &'a Store) -> &mut Self {
+    self.checks = Some(store);
+    self
+  }
+
+  /// Return `true` if `mi` is a checkbox or radio item that is checked.  The
+  /// state is taken from the store set using [`MenuIter::checks()`], or else
+  /// is the item's initial state.
+  pub fn is_checked(&self, mi: &MenuItem<C>) -> bool {
+    checks::is_checked(mi, self.checks)
+  }
+
   /// Tell the iterator to scope the root items.  By default the iterator will
   /// not generate scope events for the root elements.
   ///
@@ -71,33 +233,33 @@ where
 }
 
 
-impl<'a, C> Iterator for MenuIter<'a, C>
-where
-  C: Clone + Default
-{
+impl<'a, C> Iterator for MenuIter<'a, C> {
   type Item = Event<'a, C>;
 
   fn next(&mut self) -> Option<Self::Item> {
-    while !self.stack.is_empty() {
-      // last_mut()
-      let mut it = self.stack.pop().unwrap();
-
-      // If there's a filter defined then call it
-      if let Some(f) = self.filter {
-        if it.idx < it.lst.len() {
-          let mi = &it.lst[it.idx];
-
-          // If the filter returns false then skip this entry
-          if f(mi) == false {
-            it.idx += 1;
-            self.stack.push(it);
-            continue;
-          }
+    while let Some(mut it) = self.stack.pop() {
+      // Skip entries that the filter, if there is one, rejects, and
+      // separators, headers and groups that have nothing left to separate,
+      // introduce or group
+      if it.idx < it.lst.len() {
+        let filter = self.filter;
+        let keep = |mi: &MenuItem<C>| match filter {
+          Some(f) => f(mi),
+          None => true
+        };
+        let lst = it.lst;
+        let shown = it
+          .shown
+          .get_or_insert_with(|| menuitem::shown_mask(lst, &keep));
+        if !shown[it.idx] {
+          it.idx += 1;
+          self.stack.push(it);
+          continue;
         }
       }
 
       // Let application know that iterator entered a new scope
-      if it.did_enter_scope == false {
+      if !it.did_enter_scope {
         it.did_enter_scope = true;
         self.stack.push(it);
         return Some(Event::EnterScope);
@@ -108,7 +270,7 @@ where
         // Before backing out make sure the application knows that a scope has
         // been left
 
-        if it.did_enter_scope == true && it.did_leave_scope == false {
+        if it.did_enter_scope && !it.did_leave_scope {
           it.did_leave_scope = true;
           self.stack.push(it);
           return Some(Event::LeaveScope);
@@ -120,16 +282,24 @@ where
 
       if it.lst[it.idx].children.is_empty() {
         // Don't step into -- return next child in list
-        let ret = Event::MenuItem(&it.lst[it.idx]);
+        let ret = Event::MenuItem(Item::new(&it.lst[it.idx], self.checks));
+        it.idx += 1;
+        self.stack.push(it);
+        return Some(ret);
+      } else if !self.expansion.is_expanded(&it.lst[it.idx], it.depth) {
+        // Collapsed parent -- report it but don't step into
+        let ret = Event::Collapsed(Item::new(&it.lst[it.idx], self.checks));
         it.idx += 1;
         self.stack.push(it);
         return Some(ret);
       } else {
-        let ret = Event::MenuItem(&it.lst[it.idx]);
+        let ret = Event::MenuItem(Item::new(&it.lst[it.idx], self.checks));
 
         self.stack.push(IterNode {
-          lst: &it.lst,
+          lst: it.lst,
           idx: it.idx + 1,
+          depth: it.depth,
+          shown: it.shown,
           did_enter_scope: it.did_enter_scope,
           did_leave_scope: it.did_leave_scope
         });
@@ -138,6 +308,8 @@ where
         self.stack.push(IterNode {
           lst: &it.lst[it.idx].children,
           idx: 0,
+          depth: it.depth + 1,
+          shown: None,
           did_enter_scope: false,
           did_leave_scope: false
         });
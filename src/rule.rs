@@ -0,0 +1,264 @@
+//! Visibility rule expressions, for menus loaded from configuration files.
+//!
+//! A rule is a boolean expression over _atoms_ of the form `kind:value`,
+//! combined using `!`, `&&`, `||` and parentheses.  `&&` binds tighter than
+//! `||`, so
+//!
+//! ```text
+//! role:admin && !flag:beta || env:staging
+//! ```
+//!
+//! is equivalent to `(role:admin && !flag:beta) || env:staging`.  The
+//! constants `true` and `false` are also accepted.
+//!
+//! Rules are parsed (and optionally checked against a set of known atom
+//! kinds) when the menu is loaded, and evaluated against an application
+//! provided [`Env`] when the menu is displayed.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An evaluation environment, which determines the truth value of atoms.
+pub trait Env {
+  /// Return the truth value of the atom `kind:value`.
+  fn eval(&self, kind: &str, value: &str) -> bool;
+}
+
+impl<F> Env for F
+where
+  F: Fn(&str, &str) -> bool
+{
+  fn eval(&self, kind: &str, value: &str) -> bool {
+    self(kind, value)
+  }
+}
+
+
+/// An error encountered while parsing a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  /// Character offset, within the rule, at which the error was detected.
+  pub pos: usize,
+  pub msg: String
+}
+
+impl ParseError {
+  /// Render the error together with the rule source, with a marker pointing
+  /// out the location of the error.
+  pub fn explain(&self, src: &str) -> String {
+    format!("{}\n{}\n{}^", self, src, " ".repeat(self.pos))
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at position {}", self.msg, self.pos)
+  }
+}
+
+
+/// A parsed visibility rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Rule {
+  Const(bool),
+  Atom { kind: String, value: String },
+  Not(Box<Rule>),
+  And(Box<Rule>, Box<Rule>),
+  Or(Box<Rule>, Box<Rule>)
+}
+
+impl Rule {
+  /// Parse a rule, accepting atoms of any kind.
+  pub fn parse(src: &str) -> Result<Rule, ParseError> {
+    Parser::new().parse(src)
+  }
+
+  /// Evaluate the rule in the environment `env`.
+  pub fn eval<E>(&self, env: &E) -> bool
+  where
+    E: Env + ?Sized
+  {
+    match self {
+      Rule::Const(b) => *b,
+      Rule::Atom { kind, value } => env.eval(kind, value),
+      Rule::Not(r) => !r.eval(env),
+      Rule::And(a, b) => a.eval(env) && b.eval(env),
+      Rule::Or(a, b) => a.eval(env) || b.eval(env)
+    }
+  }
+}
+
+impl fmt::Display for Rule {
+  /// Write the rule in its canonical, fully parenthesized, form.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Rule::Const(b) => write!(f, "{}", b),
+      Rule::Atom { kind, value } => write!(f, "{}:{}", kind, value),
+      Rule::Not(r) => write!(f, "!{}", r),
+      Rule::And(a, b) => write!(f, "({} && {})", a, b),
+      Rule::Or(a, b) => write!(f, "({} || {})", a, b)
+    }
+  }
+}
+
+
+/// Rule parser.
+///
+/// By default atoms of all kinds are accepted.  Registering kinds using
+/// [`Parser::kind()`] restricts the parser to only accept the registered
+/// kinds, which catches misspelled kinds when the rules are loaded.
+#[derive(Default)]
+pub struct Parser {
+  kinds: Vec<String>
+}
+
+impl Parser {
+  pub fn new() -> Self {
+    Parser::default()
+  }
+
+  /// Register a known atom kind.
+  pub fn kind<K: ToString>(&mut self, kind: K) -> &mut Self {
+    self.kinds.push(kind.to_string());
+    self
+  }
+
+  pub fn parse(&self, src: &str) -> Result<Rule, ParseError> {
+    let mut st = State {
+      parser: self,
+      chars: src.chars().collect(),
+      pos: 0
+    };
+    let rule = st.or()?;
+    st.skip_ws();
+    if st.pos < st.chars.len() {
+      return Err(st.err(format!("Unexpected '{}'", st.chars[st.pos])));
+    }
+    Ok(rule)
+  }
+}
+
+struct State<'p> {
+  parser: &'p Parser,
+  chars: Vec<char>,
+  pos: usize
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/')
+}
+
+impl State<'_> {
+  fn err<S: ToString>(&self, msg: S) -> ParseError {
+    ParseError {
+      pos: self.pos,
+      msg: msg.to_string()
+    }
+  }
+
+  fn skip_ws(&mut self) {
+    while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+      self.pos += 1;
+    }
+  }
+
+  /// Skip whitespace and consume `tok` if it comes next.
+  fn eat(&mut self, tok: &str) -> bool {
+    self.skip_ws();
+    let end = self.pos + tok.chars().count();
+    if end <= self.chars.len()
+      && self.chars[self.pos..end].iter().copied().eq(tok.chars())
+    {
+      self.pos = end;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn word(&mut self) -> String {
+    let start = self.pos;
+    while self.pos < self.chars.len() && is_word_char(self.chars[self.pos]) {
+      self.pos += 1;
+    }
+    self.chars[start..self.pos].iter().collect()
+  }
+
+  fn or(&mut self) -> Result<Rule, ParseError> {
+    let mut lhs = self.and()?;
+    while self.eat("||") {
+      let rhs = self.and()?;
+      lhs = Rule::Or(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+  }
+
+  fn and(&mut self) -> Result<Rule, ParseError> {
+    let mut lhs = self.unary()?;
+    while self.eat("&&") {
+      let rhs = self.unary()?;
+      lhs = Rule::And(Box::new(lhs), Box::new(rhs));
+    }
+    Ok(lhs)
+  }
+
+  fn unary(&mut self) -> Result<Rule, ParseError> {
+    if self.eat("!") {
+      return Ok(Rule::Not(Box::new(self.unary()?)));
+    }
+    if self.eat("(") {
+      let rule = self.or()?;
+      if !self.eat(")") {
+        return Err(self.err("Expected ')'"));
+      }
+      return Ok(rule);
+    }
+    self.atom()
+  }
+
+  fn atom(&mut self) -> Result<Rule, ParseError> {
+    self.skip_ws();
+    let start = self.pos;
+    let kind = self.word();
+    if kind.is_empty() {
+      return Err(match self.chars.get(self.pos) {
+        Some(c) => self.err(format!("Expected expression, found '{}'", c)),
+        None => self.err("Unexpected end of rule")
+      });
+    }
+
+    if self.pos >= self.chars.len() || self.chars[self.pos] != ':' {
+      return match kind.as_str() {
+        "true" => Ok(Rule::Const(true)),
+        "false" => Ok(Rule::Const(false)),
+        _ => Err(self.err(format!("Expected ':' after '{}'", kind)))
+      };
+    }
+
+    if !self.parser.kinds.is_empty() && !self.parser.kinds.contains(&kind) {
+      return Err(ParseError {
+        pos: start,
+        msg: format!(
+          "Unknown kind '{}' (expected one of: {})",
+          kind,
+          self.parser.kinds.join(", ")
+        )
+      });
+    }
+
+    // Skip ':'
+    self.pos += 1;
+    let value = self.word();
+    if value.is_empty() {
+      return Err(self.err(format!("Expected value after '{}:'", kind)));
+    }
+
+    Ok(Rule::Atom { kind, value })
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
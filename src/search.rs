@@ -0,0 +1,262 @@
+//! Fuzzy search over menu items, for "go to anything" command palettes.
+//!
+//! Queries are matched as case insensitive subsequences against each item's
+//! title and, optionally, against a list of keywords/aliases extracted from
+//! the item's application context.  Matches are scored with bonuses for
+//! consecutive characters and for characters at the start of words, and
+//! items closer to the root are ranked higher than deeper ones with the same
+//! match quality.
+//!
+//! Only items that can be selected are returned as hits.
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use crate::menu::Menu;
+use crate::menuitem::MenuItem;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_WORD_START: i64 = 10;
+const BONUS_FIRST_CHAR: i64 = 6;
+const PENALTY_GAP: i64 = 1;
+const PENALTY_DEPTH: i64 = 4;
+
+/// A single search hit.
+pub struct Hit<'a, C> {
+  pub item: &'a MenuItem<C>,
+
+  /// The match score.  Higher is better.
+  pub score: i64,
+
+  /// The titles of the item and all its ancestors, joined by the index's
+  /// path separator.
+  pub path: String,
+
+  /// The byte ranges of the item's title that matched the query.  Empty if
+  /// the hit is a keyword match.
+  pub ranges: Vec<Range<usize>>,
+
+  /// The keyword that matched the query, if the hit was not a title match.
+  pub keyword: Option<String>
+}
+
+struct Entry<'a, C> {
+  /// The item's ancestors, followed by the item itself.
+  path: Vec<&'a MenuItem<C>>,
+  keywords: Vec<String>
+}
+
+/// Search index over a [`Menu`].
+pub struct Index<'a, C> {
+  entries: Vec<Entry<'a, C>>,
+  separator: String
+}
+
+impl<'a, C> Index<'a, C> {
+  /// Create a search index which only matches against item titles.
+  pub fn new(menu: &'a Menu<C>) -> Self {
+    Self::with_keywords(menu, |_| Vec::new())
+  }
+
+  /// Create a search index which, in addition to the titles, matches against
+  /// the keywords returned by `kwfn` for each item's application context.
+  pub fn with_keywords<F>(menu: &'a Menu<C>, kwfn: F) -> Self
+  where
+    F: Fn(&C) -> Vec<String>
+  {
+    let mut entries = Vec::new();
+    let mut path = Vec::new();
+    collect(&menu.rootlst, &mut path, &kwfn, &mut entries);
+    Index {
+      entries,
+      separator: " › ".to_string()
+    }
+  }
+
+  /// Set the separator used to join the titles in [`Hit::path`].
+  pub fn separator<S: ToString>(&mut self, sep: S) -> &mut Self {
+    self.separator = sep.to_string();
+    self
+  }
+
+  /// Search for `query` among all items.  Hits are returned best first.
+  pub fn search(&self, query: &str) -> Vec<Hit<'a, C>> {
+    self.search_filtered(query, |_| true)
+  }
+
+  /// Search for `query` among the items accepted by the predicate `p`.
+  ///
+  /// As with the filtered hierarchical iterator, an item is only searched if
+  /// the predicate accepts it and all of its ancestors.
+  pub fn search_filtered<F>(&self, query: &str, p: F) -> Vec<Hit<'a, C>>
+  where
+    F: Fn(&MenuItem<C>) -> bool
+  {
+    let query: Vec<char> = query.trim().chars().collect();
+    if query.is_empty() {
+      return Vec::new();
+    }
+
+    let mut hits = Vec::new();
+    for entry in &self.entries {
+      if !entry.path.iter().all(|mi| p(mi)) {
+        continue;
+      }
+      let mi = entry.path[entry.path.len() - 1];
+      let depth = (entry.path.len() - 1) as i64;
+
+      // Title matches take precedence over keyword matches of equal score
+      let mut best = fuzzy_match(&query, mi.title())
+        .map(|(score, ranges)| (score, ranges, None));
+      for kw in &entry.keywords {
+        if let Some((score, _)) = fuzzy_match(&query, kw) {
+          if !matches!(best, Some(ref b) if score <= b.0) {
+            best = Some((score, Vec::new(), Some(kw.clone())));
+          }
+        }
+      }
+
+      if let Some((score, ranges, keyword)) = best {
+        let titles: Vec<&str> =
+          entry.path.iter().map(|mi| mi.title()).collect();
+        hits.push(Hit {
+          item: mi,
+          score: score - depth * PENALTY_DEPTH,
+          path: titles.join(&self.separator),
+          ranges,
+          keyword
+        });
+      }
+    }
+
+    hits.sort_by(|a, b| match b.score.cmp(&a.score) {
+      Ordering::Equal => a.path.cmp(&b.path),
+      ord => ord
+    });
+    hits
+  }
+}
+
+fn collect<'a, C, F>(
+  lst: &'a [MenuItem<C>],
+  path: &mut Vec<&'a MenuItem<C>>,
+  kwfn: &F,
+  entries: &mut Vec<Entry<'a, C>>
+) where
+  F: Fn(&C) -> Vec<String>
+{
+  for mi in lst {
+    path.push(mi);
+    // Separators, headers, groups and disabled items can not be chosen, so
+    // they are only indexed as part of their descendants' paths
+    if mi.is_selectable() {
+      entries.push(Entry {
+        path: path.clone(),
+        keywords: kwfn(mi.appctx())
+      });
+    }
+    collect(&mi.children, path, kwfn, entries);
+    path.pop();
+  }
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+  a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Fuzzy match `query` against `text`.
+///
+/// Returns the best score and the matched byte ranges of `text`, or `None`
+/// if `query` is not a subsequence of `text`.
+fn fuzzy_match(
+  query: &[char],
+  text: &str
+) -> Option<(i64, Vec<Range<usize>>)> {
+  let chars: Vec<(usize, char)> = text.char_indices().collect();
+  let n = chars.len();
+  let m = query.len();
+  if m > n {
+    return None;
+  }
+
+  // Per-position bonus for matching at the start of a word
+  let bonus: Vec<i64> = (0..n)
+    .map(|j| {
+      let c = chars[j].1;
+      if j == 0 {
+        BONUS_WORD_START + BONUS_FIRST_CHAR
+      } else {
+        let prev = chars[j - 1].1;
+        if (!prev.is_alphanumeric() && c.is_alphanumeric())
+          || (prev.is_lowercase() && c.is_uppercase())
+        {
+          BONUS_WORD_START
+        } else {
+          0
+        }
+      }
+    })
+    .collect();
+
+  // dp[i][j]: best score with query[i] matched at text position j
+  let mut dp: Vec<Vec<Option<i64>>> = vec![vec![None; n]; m];
+  let mut from: Vec<Vec<usize>> = vec![vec![0; n]; m];
+
+  for j in 0..n {
+    if chars_eq(query[0], chars[j].1) {
+      dp[0][j] = Some(SCORE_MATCH + bonus[j] - j as i64 * PENALTY_GAP);
+    }
+  }
+  for i in 1..m {
+    for j in i..n {
+      if !chars_eq(query[i], chars[j].1) {
+        continue;
+      }
+      let mut best: Option<(i64, usize)> = None;
+      for (k, prev) in dp[i - 1].iter().enumerate().take(j).skip(i - 1) {
+        if let Some(prev) = *prev {
+          let s = if k + 1 == j {
+            prev + BONUS_CONSECUTIVE
+          } else {
+            prev - (j - k - 1) as i64 * PENALTY_GAP
+          };
+          if !matches!(best, Some((b, _)) if s <= b) {
+            best = Some((s, k));
+          }
+        }
+      }
+      if let Some((s, k)) = best {
+        dp[i][j] = Some(s + SCORE_MATCH + bonus[j]);
+        from[i][j] = k;
+      }
+    }
+  }
+
+  // Find the best end position and walk back to collect the positions
+  let (mut j, score) = dp[m - 1]
+    .iter()
+    .enumerate()
+    .filter_map(|(j, s)| s.map(|s| (j, s)))
+    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+  let mut positions = vec![0; m];
+  for i in (0..m).rev() {
+    positions[i] = j;
+    j = from[i][j];
+  }
+
+  // Merge consecutive positions into byte ranges
+  let mut ranges: Vec<Range<usize>> = Vec::new();
+  for &p in &positions {
+    let start = chars[p].0;
+    let end = start + chars[p].1.len_utf8();
+    match ranges.last_mut() {
+      Some(r) if r.end == start => r.end = end,
+      _ => ranges.push(start..end)
+    }
+  }
+
+  Some((score, ranges))
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
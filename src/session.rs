@@ -0,0 +1,393 @@
+//! Numbered-choice navigation sessions, for IVR, USSD and SMS style menus.
+//!
+//! A session presents one level of a menu at a time as a list of numbered
+//! choices.  The caller feeds user input to the session, which descends into
+//! submenus, pages through long levels and handles the reserved keys for
+//! going back, going to the top level and repeating the current screen.
+//!
+//! All navigation state lives in a [`State`] object, which can be extracted
+//! from a session and used to resume it later, which makes it possible to
+//! use sessions across stateless requests.
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::action::{self, EnabledFn, Registry};
+use crate::checks::{self, Store};
+use crate::menu::Menu;
+use crate::menuitem::{self, MenuItem};
+
+/// Navigation state of a session.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct State {
+  /// Identifiers of the items that have been entered, starting at the root.
+  pub path: Vec<String>,
+
+  /// Current page within the current level.
+  pub page: usize
+}
+
+/// A single line on a rendered screen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Choice {
+  /// The input that selects this choice.  Empty for disabled items, which
+  /// are shown but can not be chosen.
+  pub key: String,
+
+  /// The text presented to the user.
+  pub title: String,
+
+  /// The identifier of the menu item selected by this choice.  `None` for
+  /// the reserved navigation choices.
+  pub miid: Option<String>
+}
+
+/// The current level of a session, rendered as a list of choices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Screen {
+  /// Title of the level, i.e. the title of the entered item.  `None` at the
+  /// root level.
+  pub title: Option<String>,
+  pub choices: Vec<Choice>
+}
+
+impl fmt::Display for Screen {
+  /// Render the screen as one `key. title` entry per line.  Choices without
+  /// a key are rendered as just their titles.
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(ref title) = self.title {
+      writeln!(f, "{}", title)?;
+    }
+    for (i, ch) in self.choices.iter().enumerate() {
+      if i > 0 {
+        writeln!(f)?;
+      }
+      if ch.key.is_empty() {
+        write!(f, "{}", ch.title)?;
+      } else {
+        write!(f, "{}. {}", ch.key, ch.title)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The result of feeding input to a session.
+pub enum Outcome<'a, C> {
+  /// A menu item with children was chosen, and the session now presents its
+  /// children.
+  Entered(&'a MenuItem<C>),
+
+  /// A menu item without children was chosen.  The session state is left
+  /// unchanged.
+  Selected(&'a MenuItem<C>),
+
+  /// The session moved back to the previous page or the parent level.
+  Back,
+
+  /// The session moved to the first page of the root level.
+  Home,
+
+  /// The session moved to the next page of the current level.
+  More,
+
+  /// The user asked for the current screen to be repeated.
+  Repeat,
+
+  /// The input did not match any choice.
+  Invalid
+}
+
+/// Numbered-choice navigation session over a [`Menu`].
+pub struct Session<'a, C> {
+  menu: &'a Menu<C>,
+  state: State,
+  checks: Option<&'a Store>,
+  enabled: Option<EnabledFn<'a, C>>,
+  page_size: usize,
+  back_key: String,
+  home_key: String,
+  repeat_key: String,
+  more_key: String,
+  back_title: String,
+  home_title: String,
+  more_title: String
+}
+
+impl<'a, C> Session<'a, C> {
+  /// Create a new session positioned at the root level of a menu.
+  ///
+  /// By default eight items are shown per page, `9` selects the next page,
+  /// `0` goes back, `00` goes to the root level and `*` repeats the current
+  /// screen.
+  pub fn new(menu: &'a Menu<C>) -> Self {
+    Session {
+      menu,
+      state: State::default(),
+      checks: None,
+      enabled: None,
+      page_size: 8,
+      back_key: "0".to_string(),
+      home_key: "00".to_string(),
+      repeat_key: "*".to_string(),
+      more_key: "9".to_string(),
+      back_title: "Back".to_string(),
+      home_title: "Home".to_string(),
+      more_title: "More".to_string()
+    }
+  }
+
+  /// Resume a session from a previously extracted state.
+  ///
+  /// The path is validated against the menu immediately, but the page is
+  /// only clamped to the last page of the level when the session is rendered
+  /// or receives input, so that the page size may be set after resuming.
+  pub fn resume(menu: &'a Menu<C>, state: State) -> Self {
+    let mut sess = Session::new(menu);
+    sess.state = state;
+    sess.fixup();
+    sess
+  }
+
+  /// Show the state of checkbox and radio items as recorded in `store`.
+  /// Without a store, the items' initial states are shown.
+  ///
+  /// Choosing a checkable item yields [`Outcome::Selected`]; it is up to the
+  /// application to update the store.
+  pub fn checks(&mut self, store: &'a Store) -> &mut Self {
+    self.checks = Some(store);
+    self
+  }
+
+  /// Present the items that `registry` does not consider enabled in the
+  /// application context `app` as disabled (see
+  /// [`Registry::is_enabled()`]).  Without a registry, only the items' own
+  /// disabled flags are taken into account.
+  pub fn enabled<A, R>(
+    &mut self,
+    registry: &'a Registry<'_, C, A, R>,
+    app: &'a A
+  ) -> &mut Self {
+    self.enabled = Some(registry.enabled_fn(app));
+    self
+  }
+
+  /// Set the maximum number of items shown per page.
+  ///
+  /// # Constraints
+  /// The item numbers must not collide with the reserved keys; reserved keys
+  /// take precedence over item numbers.
+  pub fn page_size(&mut self, n: usize) -> &mut Self {
+    self.page_size = n.max(1);
+    self
+  }
+
+  pub fn back_key<K: ToString>(&mut self, key: K) -> &mut Self {
+    self.back_key = key.to_string();
+    self
+  }
+
+  pub fn home_key<K: ToString>(&mut self, key: K) -> &mut Self {
+    self.home_key = key.to_string();
+    self
+  }
+
+  pub fn repeat_key<K: ToString>(&mut self, key: K) -> &mut Self {
+    self.repeat_key = key.to_string();
+    self
+  }
+
+  pub fn more_key<K: ToString>(&mut self, key: K) -> &mut Self {
+    self.more_key = key.to_string();
+    self
+  }
+
+  /// Set the titles used for the back, home and more choices.
+  pub fn nav_titles<B, H, M>(&mut self, back: B, home: H, more: M) -> &mut Self
+  where
+    B: ToString,
+    H: ToString,
+    M: ToString
+  {
+    self.back_title = back.to_string();
+    self.home_title = home.to_string();
+    self.more_title = more.to_string();
+    self
+  }
+
+  /// Return a reference to the current navigation state.
+  pub fn state(&self) -> &State {
+    &self.state
+  }
+
+  /// Consume the session and return its navigation state.
+  pub fn into_state(self) -> State {
+    self.state
+  }
+
+  /// Return the item whose children make up the current level, or `None` at
+  /// the root level.
+  pub fn current(&self) -> Option<&'a MenuItem<C>> {
+    let mut lst = &self.menu.rootlst;
+    let mut cur = None;
+    for miid in &self.state.path {
+      let mi = lst.iter().find(|mi| mi.id() == miid)?;
+      lst = &mi.children;
+      cur = Some(mi);
+    }
+    cur
+  }
+
+  /// Render the current level.
+  ///
+  /// Disabled items are listed without a key, and the items after them are
+  /// numbered as if they were not there.
+  pub fn render(&self) -> Screen {
+    let (items, more) = self.page_items();
+
+    let mut n = 0;
+    let mut choices: Vec<Choice> = items
+      .iter()
+      .map(|mi| Choice {
+        key: if !action::is_enabled(mi, &self.enabled) {
+          String::new()
+        } else {
+          n += 1;
+          n.to_string()
+        },
+        title: checks::label(mi, self.checks),
+        miid: Some(mi.id().to_string())
+      })
+      .collect();
+
+    if more {
+      choices.push(self.nav_choice(&self.more_key, &self.more_title));
+    }
+    if !self.at_top() {
+      choices.push(self.nav_choice(&self.back_key, &self.back_title));
+    }
+    if !self.state.path.is_empty() {
+      choices.push(self.nav_choice(&self.home_key, &self.home_title));
+    }
+
+    Screen {
+      title: self.current().map(|mi| mi.title().to_string()),
+      choices
+    }
+  }
+
+  /// Process a single user input.
+  pub fn input(&mut self, key: &str) -> Outcome<'a, C> {
+    let key = key.trim();
+    self.state.page = self.page();
+
+    if key == self.repeat_key {
+      return Outcome::Repeat;
+    }
+
+    if key == self.home_key && !self.state.path.is_empty() {
+      self.state = State::default();
+      return Outcome::Home;
+    }
+
+    if key == self.back_key && !self.at_top() {
+      if self.state.page > 0 {
+        self.state.page -= 1;
+      } else {
+        self.state.path.pop();
+      }
+      return Outcome::Back;
+    }
+
+    let (mut items, more) = self.page_items();
+
+    if key == self.more_key && more {
+      self.state.page += 1;
+      return Outcome::More;
+    }
+
+    items.retain(|mi| action::is_enabled(mi, &self.enabled));
+    let idx = match key.parse::<usize>() {
+      Ok(n) if n >= 1 && n <= items.len() => n - 1,
+      _ => return Outcome::Invalid
+    };
+
+    let mi = items[idx];
+    if mi.children.is_empty() {
+      Outcome::Selected(mi)
+    } else {
+      self.state.path.push(mi.id().to_string());
+      self.state.page = 0;
+      Outcome::Entered(mi)
+    }
+  }
+
+  fn nav_choice(&self, key: &str, title: &str) -> Choice {
+    Choice {
+      key: key.to_string(),
+      title: title.to_string(),
+      miid: None
+    }
+  }
+
+  /// Return `true` if the session is at the first page of the root level.
+  fn at_top(&self) -> bool {
+    self.state.path.is_empty() && self.page() == 0
+  }
+
+  /// Return the current page, clamped to the last page of the level.
+  fn page(&self) -> usize {
+    let pages = self.level().len().div_ceil(self.page_size);
+    self.state.page.min(pages.saturating_sub(1))
+  }
+
+  /// Return the items in the current level.  Separators and headers are not
+  /// presented as choices.
+  fn level(&self) -> Vec<&'a MenuItem<C>> {
+    match self.current() {
+      Some(mi) => menuitem::choices(&mi.children),
+      None => menuitem::choices(&self.menu.rootlst)
+    }
+  }
+
+  /// Return the items on the current page, and whether there are more pages
+  /// after it.
+  fn page_items(&self) -> (Vec<&'a MenuItem<C>>, bool) {
+    let mut lst = self.level();
+    let start = (self.page() * self.page_size).min(lst.len());
+    let end = (start + self.page_size).min(lst.len());
+    let more = end < lst.len();
+    lst.truncate(end);
+    lst.drain(..start);
+    (lst, more)
+  }
+
+  /// Make sure a resumed state refers to an existing level.
+  ///
+  /// The menu may have changed since the state was extracted; the path is
+  /// truncated at the first identifier that can no longer be found.
+  fn fixup(&mut self) {
+    let mut lst = &self.menu.rootlst;
+    let mut valid = 0;
+    for miid in &self.state.path {
+      match lst
+        .iter()
+        .find(|mi| mi.id() == miid && !mi.children.is_empty())
+      {
+        Some(mi) => {
+          lst = &mi.children;
+          valid += 1;
+        }
+        None => break
+      }
+    }
+    if valid < self.state.path.len() {
+      self.state.path.truncate(valid);
+      self.state.page = 0;
+    }
+  }
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
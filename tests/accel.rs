@@ -0,0 +1,230 @@
+use menugen::accel::{
+  parse_mnemonic, suggest_mnemonics, Accel, Conflict, Error, Keymap, Mnemonic
+};
+use menugen::{menu, menuitem};
+
+fn accel(s: &str) -> Accel {
+  s.parse().unwrap()
+}
+
+fn item(
+  id: &str,
+  title: &str,
+  parent: Option<&str>
+) -> menuitem::Builder<bool> {
+  let mut mib = menuitem::Builder::new_ctx(id, "", true);
+  mib.mnemonic_title(title);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  mib
+}
+
+fn build_menu() -> menu::Builder<bool> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = item("file", "&File", None);
+  mib.weight(1);
+  mb.add(mib);
+  let mut mib = item("edit", "&Edit", None);
+  mib.weight(2);
+  mb.add(mib);
+
+  let mut mib = item("file-save", "&Save", Some("file"));
+  mib.weight(1).accel(accel("ctrl+s"));
+  mb.add(mib);
+  let mut mib = item("file-save-as", "Save &As...", Some("file"));
+  mib.weight(2).accel(accel("Shift+Control+S"));
+  mb.add(mib);
+  let mut mib = item("file-quit", "&Quit", Some("file"));
+  mib.weight(3).accel(accel("Ctrl+Q"));
+  mb.add(mib);
+
+  let mut mib = item("edit-select", "&Select all", Some("edit"));
+  mib.weight(1).accel(accel("Ctrl+A"));
+  mb.add(mib);
+  mb
+}
+
+
+#[test]
+fn parse_accel() {
+  let a = accel(" control + shift + s ");
+  assert!(a.mods.ctrl && a.mods.shift && !a.mods.alt && !a.mods.meta);
+  assert_eq!(a.key, "S");
+  assert_eq!(a.to_string(), "Ctrl+Shift+S");
+
+  assert_eq!(
+    accel("Shift+Cmd+Opt+Ctrl+esc").to_string(),
+    "Ctrl+Alt+Shift+Meta+Escape"
+  );
+  assert_eq!(accel("f5").to_string(), "F5");
+  assert_eq!(accel("Ctrl++").to_string(), "Ctrl++");
+  assert_eq!(accel("Ctrl+Plus"), accel("Ctrl++"));
+  assert_eq!(accel("ctrl+minus").to_string(), "Ctrl+-");
+  assert_eq!(accel("alt+pgdn"), accel("Alt+PageDown"));
+
+  assert_eq!(Accel::parse(""), Err(Error::MissingKey));
+  assert_eq!(Accel::parse("Ctrl+"), Err(Error::MissingKey));
+  assert_eq!(Accel::parse("Ctrl+Shift"), Err(Error::MissingKey));
+  assert_eq!(Accel::parse("alt"), Err(Error::MissingKey));
+  assert_eq!(
+    Accel::parse("Hyper+X"),
+    Err(Error::UnknownModifier("Hyper".to_string()))
+  );
+  assert_eq!(
+    Accel::parse("Ctrl+F25"),
+    Err(Error::UnknownKey("F25".to_string()))
+  );
+  assert_eq!(
+    Accel::parse("Ctrl+Control+X"),
+    Err(Error::DuplicateModifier("Control".to_string()))
+  );
+}
+
+#[test]
+fn primary_modifier() {
+  let a = accel("Shift+mod+s");
+  assert!(a.mods.primary && a.mods.shift && !a.mods.ctrl && !a.mods.meta);
+  assert_eq!(a.to_string(), "Mod+Shift+S");
+  assert_eq!(accel("CmdOrCtrl+S"), accel("Mod+S"));
+  assert_eq!(accel("Primary+S"), accel("Mod+S"));
+  assert_ne!(accel("Mod+S"), accel("Ctrl+S"));
+  assert_eq!(accel("Ctrl+Mod+S").to_string(), "Mod+Ctrl+S");
+
+  assert_eq!(Accel::parse("Mod"), Err(Error::MissingKey));
+  assert_eq!(
+    Accel::parse("Mod+Primary+X"),
+    Err(Error::DuplicateModifier("Primary".to_string()))
+  );
+}
+
+#[test]
+fn parse_mnemonics() {
+  assert_eq!(
+    parse_mnemonic("Save &As..."),
+    (
+      "Save As...".to_string(),
+      Some(Mnemonic {
+        key: 'a',
+        offset: Some(5)
+      })
+    )
+  );
+  assert_eq!(
+    parse_mnemonic("Fish && &Chips &X"),
+    (
+      "Fish & Chips X".to_string(),
+      Some(Mnemonic {
+        key: 'c',
+        offset: Some(7)
+      })
+    )
+  );
+  assert_eq!(parse_mnemonic("Plain &"), ("Plain &".to_string(), None));
+
+  let mut mib = menuitem::Builder::<()>::new("x", "");
+  mib.mnemonic('Q');
+  let mut mb = menu::Builder::new();
+  mb.add(mib);
+  let menu = mb.build();
+  assert_eq!(menu.get("x").unwrap().mnemonic().unwrap().key, 'q');
+}
+
+#[test]
+fn lookup() {
+  let menu = build_menu().build();
+  assert_eq!(menu.get("file-save-as").unwrap().title(), "Save As...");
+
+  let km = Keymap::new(&menu);
+  assert!(km.is_valid());
+  assert_eq!(km.lookup(&accel("Ctrl+Shift+S")), Some("file-save-as"));
+  assert_eq!(km.lookup(&accel("Ctrl+S")), Some("file-save"));
+  assert_eq!(km.lookup(&accel("Ctrl+X")), None);
+}
+
+#[test]
+fn conflicts() {
+  let mut mb = build_menu();
+  let mut mib = item("file-select", "&Select", Some("file"));
+  mib.weight(4).accel(accel("Ctrl+A"));
+  mb.add(mib);
+  let mut mib = item("file-secret", "&Secret", Some("file"));
+  mib.weight(5).accel(accel("Ctrl+Q"));
+  mb.add(mib);
+  let menu = mb.build();
+
+  let km = Keymap::new(&menu);
+  assert_eq!(
+    km.conflicts(),
+    [
+      Conflict::Accel {
+        accel: accel("Ctrl+Q"),
+        ids: vec!["file-quit".to_string(), "file-secret".to_string()]
+      },
+      Conflict::Accel {
+        accel: accel("Ctrl+A"),
+        ids: vec!["file-select".to_string(), "edit-select".to_string()]
+      },
+      Conflict::Mnemonic {
+        parent: Some("file".to_string()),
+        key: 's',
+        ids: vec![
+          "file-save".to_string(),
+          "file-select".to_string(),
+          "file-secret".to_string()
+        ]
+      },
+    ]
+  );
+  assert_eq!(km.lookup(&accel("Ctrl+A")), Some("file-select"));
+
+  // Different spellings of the same key conflict
+  let mut mb = build_menu();
+  let mut mib = item("edit-zoom-in", "Zoom &in", Some("edit"));
+  mib.weight(2).accel(accel("Ctrl++"));
+  mb.add(mib);
+  let mut mib = item("edit-bigger", "&Bigger", Some("edit"));
+  mib.weight(3).accel(accel("Ctrl+Plus"));
+  mb.add(mib);
+  let km = Keymap::new(&mb.build());
+  assert_eq!(
+    km.conflicts(),
+    [Conflict::Accel {
+      accel: accel("Ctrl++"),
+      ids: vec!["edit-zoom-in".to_string(), "edit-bigger".to_string()]
+    }]
+  );
+
+  // Hidden items do not conflict
+  let km = Keymap::filtered(&menu, |mi| mi.id() != "file-secret");
+  assert_eq!(km.conflicts().len(), 2);
+  let km = Keymap::filtered(&menu, |mi| {
+    mi.id() != "file-select" && mi.id() != "file-secret"
+  });
+  assert!(km.is_valid());
+}
+
+#[test]
+fn suggestions() {
+  let mut mb = menu::Builder::<()>::new();
+  for (i, title) in ["&Save", "&Search", "Save as", "Settings", "&Print", "ss"]
+    .iter()
+    .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(format!("i{}", i), "");
+    mib.mnemonic_title(title).weight(i as isize);
+    mb.add(mib);
+  }
+  let menu = mb.build();
+  assert_eq!(
+    suggest_mnemonics(menu.get_rootitems()),
+    [
+      ("i1".to_string(), 'e'),
+      ("i2".to_string(), 'a'),
+      ("i3".to_string(), 't'),
+    ]
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
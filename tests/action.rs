@@ -0,0 +1,338 @@
+use menugen::accel::Platform;
+use menugen::action::{Error, Registry};
+use menugen::cheatsheet::Sheet;
+use menugen::cursor::MenuCursor;
+use menugen::edit::Removal;
+use menugen::menuitem::Kind;
+use menugen::session::{Outcome, Session};
+use menugen::{menu, menuitem};
+
+#[derive(Default)]
+struct App {
+  dirty: bool,
+  log: Vec<String>
+}
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+  mb.add(menuitem::Builder::new("file", "File"));
+
+  for (i, (id, accel)) in [
+    ("file-new", "Ctrl+N"),
+    ("file-save", "Ctrl+S"),
+    ("file-quit", "Ctrl+Q")
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(*id, *id);
+    mib
+      .parent("file")
+      .weight(i as isize)
+      .accel(accel.parse().unwrap());
+    mb.add(mib);
+  }
+
+  let mut mib = menuitem::Builder::new("file-sep", "");
+  mib.parent("file").weight(10).kind(Kind::Separator);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-print", "Print");
+  mib.parent("file").weight(20).disabled(true);
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn registry<'a>(menu: &menu::Menu<()>) -> Registry<'a, (), App> {
+  let mut reg = Registry::new(menu);
+  for id in ["file-new", "file-save", "file-quit", "file-print"] {
+    reg
+      .bind(id, |mi, app: &mut App| app.log.push(mi.id().to_string()))
+      .unwrap();
+  }
+  reg.enabled("file-save", |_, app| app.dirty).unwrap();
+  reg
+}
+
+
+#[test]
+fn activate() {
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let mut app = App::default();
+
+  menu.activate(&reg, "file-new", &mut app).unwrap();
+  assert_eq!(app.log, ["file-new"]);
+
+  assert_eq!(
+    menu.activate(&reg, "file-save", &mut app),
+    Err(Error::Disabled("file-save".to_string()))
+  );
+  app.dirty = true;
+  menu.activate(&reg, "file-save", &mut app).unwrap();
+  assert_eq!(app.log, ["file-new", "file-save"]);
+
+  assert_eq!(
+    menu.activate(&reg, "file-print", &mut app),
+    Err(Error::Disabled("file-print".to_string()))
+  );
+  assert_eq!(
+    menu.activate(&reg, "file", &mut app),
+    Err(Error::Unhandled("file".to_string()))
+  );
+  assert_eq!(
+    menu.activate(&reg, "nope", &mut app),
+    Err(Error::UnknownItem("nope".to_string()))
+  );
+}
+
+#[test]
+fn handler_results() {
+  let menu = build_menu();
+  let mut reg: Registry<(), u32, Result<u32, String>> = Registry::new(&menu);
+  reg
+    .bind("file-new", |_, n| {
+      *n += 1;
+      Ok(*n)
+    })
+    .unwrap()
+    .bind("file-quit", |_, _| Err("unsaved changes".to_string()))
+    .unwrap();
+
+  let mut n = 0;
+  assert_eq!(menu.activate(&reg, "file-new", &mut n), Ok(Ok(1)));
+  assert_eq!(
+    menu.activate(&reg, "file-quit", &mut n),
+    Ok(Err("unsaved changes".to_string()))
+  );
+}
+
+#[test]
+fn is_enabled() {
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let mut app = App::default();
+
+  // Predicates are evaluated when asked, without touching the menu
+  let save = menu.get("file-save").unwrap();
+  assert!(!reg.is_enabled(save, &app));
+  assert!(reg.is_enabled(menu.get("file-new").unwrap(), &app));
+  assert!(!reg.is_enabled(menu.get("file-print").unwrap(), &app));
+
+  app.dirty = true;
+  assert!(reg.is_enabled(save, &app));
+  assert!(!save.is_disabled());
+
+  // A predicate can not enable a disabled item
+  let mut reg = registry(&menu);
+  reg.enabled("file-print", |_, _| true).unwrap();
+  assert!(!reg.is_enabled(menu.get("file-print").unwrap(), &app));
+  assert_eq!(
+    menu.activate(&reg, "file-print", &mut app),
+    Err(Error::Disabled("file-print".to_string()))
+  );
+}
+
+#[test]
+fn session() {
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let app = App::default();
+
+  let mut sess = Session::new(&menu);
+  sess.enabled(&reg, &app);
+  assert!(matches!(sess.input("1"), Outcome::Entered(_)));
+  let keys: Vec<String> =
+    sess.render().choices.into_iter().map(|ch| ch.key).collect();
+  assert_eq!(keys, ["1", "", "2", "", "0", "00"]);
+  match sess.input("2") {
+    Outcome::Selected(mi) => assert_eq!(mi.id(), "file-quit"),
+    _ => panic!("expected a selection")
+  }
+
+  let app = App {
+    dirty: true,
+    ..App::default()
+  };
+  let mut sess = Session::new(&menu);
+  sess.enabled(&reg, &app);
+  sess.input("1");
+  match sess.input("2") {
+    Outcome::Selected(mi) => assert_eq!(mi.id(), "file-save"),
+    _ => panic!("expected a selection")
+  }
+}
+
+#[test]
+fn cursor() {
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let app = App::default();
+
+  let mut cur = MenuCursor::new(&menu);
+  assert!(cur.select("file-save"));
+  cur.enabled(&reg, &app);
+  assert_eq!(cur.selected().unwrap().id(), "file");
+
+  cur.expand_all();
+  let focusable: Vec<&str> = cur
+    .rows()
+    .iter()
+    .filter(|r| r.focusable)
+    .map(|r| r.item.id())
+    .collect();
+  assert_eq!(focusable, ["file", "file-new", "file-quit"]);
+  assert!(!cur.select("file-save"));
+  assert!(cur.select("file-new"));
+  assert!(cur.down());
+  assert_eq!(cur.selected().unwrap().id(), "file-quit");
+}
+
+#[test]
+fn cheatsheet() {
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let mut app = App::default();
+
+  let mut sheet = Sheet::new(&menu);
+  assert!(sheet.sections[0].entries.iter().all(|e| !e.disabled));
+  sheet.enabled(&menu, &reg, &app);
+  let disabled: Vec<&str> = sheet.sections[0]
+    .entries
+    .iter()
+    .filter(|e| e.disabled)
+    .map(|e| e.id.as_str())
+    .collect();
+  assert_eq!(disabled, ["file-save"]);
+  assert_eq!(
+    sheet.text(Platform::Linux),
+    concat!(
+      "File\n",
+      "  file-new   Ctrl+N\n",
+      "  file-save  Ctrl+S (disabled)\n",
+      "  file-quit  Ctrl+Q\n"
+    )
+  );
+  assert!(sheet.html(Platform::Linux).contains(
+    "<tr class=\"disabled\"><td>file-save</td><td><kbd>Ctrl+S</kbd></td></tr>"
+  ));
+  assert!(sheet
+    .markdown(Platform::Linux)
+    .contains("| file-save (disabled) | `Ctrl+S` |"));
+
+  app.dirty = true;
+  sheet.enabled(&menu, &reg, &app);
+  assert!(sheet.sections[0].entries.iter().all(|e| !e.disabled));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn keyboard() {
+  use menugen::keyboard::{Builder, Callback};
+
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let app = App::default();
+
+  let mut kb = Builder::new(&menu);
+  assert!(matches!(kb.decode("i:file-save"), Some(Callback::Item(_))));
+  kb.enabled(&reg, &app);
+  assert!(kb.decode("i:file-save").is_none());
+  assert!(matches!(kb.decode("i:file-new"), Some(Callback::Item(_))));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json() {
+  use menugen::json::Exporter;
+
+  let menu = build_menu();
+  let reg = registry(&menu);
+  let app = App::default();
+
+  let mut exp = Exporter::new(&menu, |_| serde_json::Value::Null);
+  exp.enabled(&reg, &app);
+  let flat = exp.flat();
+  for (id, disabled) in [
+    ("file-new", false),
+    ("file-save", true),
+    ("file-quit", false),
+    ("file-print", true)
+  ] {
+    assert_eq!(flat["nodes"][id]["disabled"], disabled, "{}", id);
+  }
+}
+
+#[test]
+fn unknown_items() {
+  let menu = build_menu();
+  let mut reg: Registry<(), App> = Registry::new(&menu);
+  assert_eq!(
+    reg.bind("file-opne", |_, _| ()).err(),
+    Some(Error::UnknownItem("file-opne".to_string()))
+  );
+  assert_eq!(
+    reg.enabled("edit-copy", |_, _| true).err(),
+    Some(Error::UnknownItem("edit-copy".to_string()))
+  );
+  assert!(!reg.is_bound("file-opne"));
+
+  // Separators can be bound to, even though they can not be activated
+  assert!(reg.bind("file-sep", |_, _| ()).is_ok());
+}
+
+#[test]
+fn validate() {
+  let mut menu = build_menu();
+  let reg = registry(&menu);
+  assert_eq!(reg.validate(&menu), Ok(()));
+
+  // The menu is edited after the registry was created
+  let mut mb = menu::Builder::new();
+  mb.add(menuitem::Builder::new("file", "File"));
+  mb.add(menuitem::Builder::new("edit", "Edit"));
+  let mut mib = menuitem::Builder::new("edit-copy", "Copy");
+  mib.parent("edit");
+  mb.add(mib);
+  for id in ["file-new", "file-open"] {
+    let mut mib = menuitem::Builder::new(id, id);
+    mib.parent("file");
+    mb.add(mib);
+  }
+  let old = mb.build();
+  let mut reg2: Registry<(), App> = Registry::new(&old);
+  reg2
+    .bind("file-new", |_, _| ())
+    .unwrap()
+    .bind("file-open", |_, _| ())
+    .unwrap()
+    .enabled("edit-copy", |_, _| true)
+    .unwrap();
+  let err = reg2.validate(&menu).unwrap_err();
+  assert_eq!(
+    err,
+    Error::Mismatch {
+      unhandled: vec![
+        "file-save".to_string(),
+        "file-quit".to_string(),
+        "file-print".to_string()
+      ],
+      unknown: vec!["edit-copy".to_string(), "file-open".to_string()]
+    }
+  );
+  assert_eq!(
+    err.to_string(),
+    concat!(
+      "Handlers do not match menu; ",
+      "unhandled items: file-save, file-quit, file-print; ",
+      "unknown items: edit-copy, file-open"
+    )
+  );
+
+  menu.remove("file-quit", Removal::Reject).unwrap();
+  assert!(reg.validate(&menu).is_err());
+  assert!(reg.is_bound("file-quit"));
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,123 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::thread;
+
+use menugen::cache::MenuCache;
+use menugen::reciter::Event;
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("adm", "Administration");
+  mib.require_any(vec!["admin"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-users", "Users");
+  mib.parent("adm");
+  mb.add(mib);
+
+  let mib = menuitem::Builder::new("home", "Home");
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn ids(menu: &menu::Menu<()>) -> Vec<String> {
+  let mut out = Vec::new();
+  for ev in menu.iter_hier() {
+    if let Event::MenuItem(mi) = ev {
+      out.push(mi.id().to_string());
+    }
+  }
+  out
+}
+
+fn fingerprint(perms: &BTreeSet<String>) -> String {
+  perms.iter().cloned().collect::<Vec<_>>().join(",")
+}
+
+
+#[test]
+fn filtered() {
+  let menu = build_menu();
+
+  let user = menu.filtered(|mi| mi.requirements().is_empty());
+  assert_eq!(ids(&user), vec!["home"]);
+
+  let admin = menu.filtered(|_| true);
+  assert_eq!(ids(&admin), vec!["adm", "adm-users", "home"]);
+  assert_eq!(ids(&menu), ids(&admin));
+}
+
+
+#[test]
+fn cache_hits_and_eviction() {
+  let menu = build_menu();
+  let cache = MenuCache::new(2);
+
+  let mut admin = BTreeSet::new();
+  admin.insert("admin".to_string());
+  let user = BTreeSet::new();
+  let mut other = BTreeSet::new();
+  other.insert("other".to_string());
+
+  let m1 = cache.materialize(&menu, 1, fingerprint(&admin), |mi| {
+    mi.requirements().check(&admin)
+  });
+  assert_eq!(ids(&m1), vec!["adm", "adm-users", "home"]);
+
+  let m2 = cache.materialize(&menu, 1, fingerprint(&user), |mi| {
+    mi.requirements().check(&user)
+  });
+  assert_eq!(ids(&m2), vec!["home"]);
+
+  // Hit
+  let m1b = cache.get_or_insert_with(fingerprint(&admin), 1, || {
+    panic!("should have been cached")
+  });
+  assert!(Arc::ptr_eq(&m1, &m1b));
+  assert_eq!(cache.len(), 2);
+
+  // Evicts the least recently used entry, which is the user menu
+  cache.materialize(&menu, 1, fingerprint(&other), |mi| {
+    mi.requirements().check(&other)
+  });
+  assert_eq!(cache.len(), 2);
+  assert!(cache.get(&fingerprint(&user), 1).is_none());
+  assert!(cache.get(&fingerprint(&admin), 1).is_some());
+
+  // Different versions are different entries
+  assert!(cache.get(&fingerprint(&admin), 2).is_none());
+  cache.retain_version(2);
+  assert!(cache.is_empty());
+}
+
+
+#[test]
+fn shared_between_threads() {
+  let menu = Arc::new(build_menu());
+  let cache = Arc::new(MenuCache::new(4));
+
+  let handles: Vec<_> = (0..4)
+    .map(|i| {
+      let menu = Arc::clone(&menu);
+      let cache = Arc::clone(&cache);
+      thread::spawn(move || {
+        let key = i % 2 == 0;
+        let m = cache.materialize(&menu, 1, key, |mi| {
+          key || mi.requirements().is_empty()
+        });
+        ids(&m).len()
+      })
+    })
+    .collect();
+
+  for (i, h) in handles.into_iter().enumerate() {
+    let n = h.join().unwrap();
+    assert_eq!(n, if i % 2 == 0 { 3 } else { 1 });
+  }
+  assert_eq!(cache.len(), 2);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,180 @@
+use menugen::accel::{Accel, Platform};
+use menugen::cheatsheet::Sheet;
+use menugen::menuitem::Kind;
+use menugen::{menu, menuitem};
+
+fn item(
+  id: &str,
+  title: &str,
+  parent: Option<&str>,
+  accel: Option<&str>
+) -> menuitem::Builder<()> {
+  let mut mib = menuitem::Builder::new(id, title);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  if let Some(accel) = accel {
+    mib.accel(accel.parse().unwrap());
+  }
+  mib
+}
+
+/// File > New (Mod+N), Save (Mod+S), Quit (Ctrl+Q)
+/// View > Zoom > In (Mod+Shift+=), Out (Mod+-); Full screen (F11)
+/// Help (no shortcuts), Search & Find (Mod+F)
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+  for (i, (id, title, accel)) in [
+    ("file", "File", None),
+    ("view", "View", None),
+    ("help", "Help", None),
+    ("search", "Search & Find", Some("Mod+F"))
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = item(id, title, None, *accel);
+    mib.weight(i as isize);
+    mb.add(mib);
+  }
+
+  for (i, (id, title, accel)) in [
+    ("file-new", "New", "Mod+N"),
+    ("file-save", "Save", "Mod+S"),
+    ("file-quit", "Quit", "Ctrl+Q")
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = item(id, title, Some("file"), Some(accel));
+    mib.weight(i as isize);
+    mb.add(mib);
+  }
+
+  let mut mib = item("zoom", "Zoom", Some("view"), None);
+  mib.weight(0);
+  mb.add(mib);
+  let mut mib = item("zoom-in", "In", Some("zoom"), Some("Mod+Shift+="));
+  mib.weight(1);
+  mb.add(mib);
+  let mut mib = item("zoom-out", "Out", Some("zoom"), Some("Mod+-"));
+  mib.weight(2);
+  mb.add(mib);
+  let mut mib = item("full", "Full screen", Some("view"), Some("F11"));
+  mib.weight(3);
+  mb.add(mib);
+
+  let mut mib = item("help-sep", "", Some("help"), None);
+  mib.kind(Kind::Separator);
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn sections() {
+  let menu = build_menu();
+  let sheet = Sheet::new(&menu);
+  assert!(!sheet.is_empty());
+
+  let titles: Vec<Option<&str>> =
+    sheet.sections.iter().map(|s| s.title.as_deref()).collect();
+  assert_eq!(titles, [None, Some("File"), Some("View")]);
+
+  let view: Vec<&str> = sheet.sections[2]
+    .entries
+    .iter()
+    .map(|e| e.title.as_str())
+    .collect();
+  assert_eq!(view, ["Zoom > In", "Zoom > Out", "Full screen"]);
+  assert_eq!(sheet.sections[2].entries[0].id, "zoom-in");
+
+  let sheet = Sheet::filtered(&menu, |mi| mi.id() != "zoom");
+  let view: Vec<&str> = sheet.sections[2]
+    .entries
+    .iter()
+    .map(|e| e.title.as_str())
+    .collect();
+  assert_eq!(view, ["Full screen"]);
+
+  let empty: menu::Menu<()> = menu::Builder::new().build();
+  assert!(Sheet::new(&empty).is_empty());
+}
+
+#[test]
+fn platforms() {
+  let a: Accel = "Shift+Ctrl+Meta+Enter".parse().unwrap();
+  assert_eq!(a.display_for(Platform::Windows), "Ctrl+Shift+Win+Enter");
+  assert_eq!(a.display_for(Platform::Linux), "Ctrl+Shift+Super+Enter");
+  assert_eq!(a.display_for(Platform::Mac), "⌃⇧⌘↩");
+
+  let a: Accel = "Alt+F4".parse().unwrap();
+  assert_eq!(a.display_for(Platform::Mac), "⌥F4");
+
+  // The primary modifier follows the platform conventions
+  let a: Accel = "Mod+Shift+S".parse().unwrap();
+  assert_eq!(a.display_for(Platform::Windows), "Ctrl+Shift+S");
+  assert_eq!(a.display_for(Platform::Linux), "Ctrl+Shift+S");
+  assert_eq!(a.display_for(Platform::Mac), "⇧⌘S");
+
+  let a: Accel = "Mod+Ctrl+F".parse().unwrap();
+  assert_eq!(a.display_for(Platform::Linux), "Ctrl+F");
+  assert_eq!(a.display_for(Platform::Mac), "⌃⌘F");
+}
+
+#[test]
+fn text() {
+  let sheet = Sheet::new(&build_menu());
+  assert_eq!(
+    sheet.text(Platform::Linux),
+    concat!(
+      "Search & Find  Ctrl+F\n",
+      "\n",
+      "File\n",
+      "  New   Ctrl+N\n",
+      "  Save  Ctrl+S\n",
+      "  Quit  Ctrl+Q\n",
+      "\n",
+      "View\n",
+      "  Zoom > In    Ctrl+Shift+=\n",
+      "  Zoom > Out   Ctrl+-\n",
+      "  Full screen  F11\n"
+    )
+  );
+}
+
+#[test]
+fn markdown() {
+  let sheet = Sheet::new(&build_menu());
+  let md = sheet.markdown(Platform::Mac);
+  assert!(md.starts_with(concat!(
+    "| Command | Shortcut |\n",
+    "|---|---|\n",
+    "| Search & Find | `⌘F` |\n",
+    "\n",
+    "## File\n",
+    "\n",
+    "| Command | Shortcut |\n"
+  )));
+  assert!(md.contains("| Quit | `⌃Q` |\n"));
+  assert!(md.contains("| Zoom > In | `⇧⌘=` |\n"));
+  assert!(md.ends_with("| Full screen | `F11` |\n"));
+}
+
+#[test]
+fn html() {
+  let sheet = Sheet::new(&build_menu());
+  let html = sheet.html(Platform::Windows);
+  assert!(html.starts_with(concat!(
+    "<table class=\"shortcuts\">\n",
+    "<tr><td>Search &amp; Find</td><td><kbd>Ctrl+F</kbd></td></tr>\n",
+    "</table>\n",
+    "<h2>File</h2>\n"
+  )));
+  assert!(html.contains(
+    "<tr><td>Zoom &gt; In</td><td><kbd>Ctrl+Shift+=</kbd></td></tr>"
+  ));
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
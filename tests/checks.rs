@@ -0,0 +1,272 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use menugen::checks::{Change, Error, Store};
+use menugen::menuitem::Kind;
+use menugen::recfiltiter;
+use menugen::reciter::Event;
+use menugen::session::Session;
+use menugen::{menu, menuitem};
+
+/// View menu:
+///   [ ] Show hidden files, ( ) Name, (*) Date, ( ) Size,
+///   Zoom > ( ) 100%, ( ) 200%
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+  mb.add(menuitem::Builder::new("view", "View"));
+
+  let mut mib = menuitem::Builder::new("hidden", "Show hidden files");
+  mib.parent("view").weight(1).kind(Kind::Checkbox);
+  mb.add(mib);
+
+  for (i, (id, title)) in
+    [("name", "Name"), ("date", "Date"), ("size", "Size")]
+      .iter()
+      .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(*id, *title);
+    mib
+      .parent("view")
+      .weight(10 + i as isize)
+      .radio_group("sort")
+      .checked(*id != "name");
+    mb.add(mib);
+  }
+
+  let mut mib = menuitem::Builder::new("zoom", "Zoom");
+  mib.parent("view").weight(20);
+  mb.add(mib);
+  for (i, pct) in ["100", "200"].iter().enumerate() {
+    let mut mib = menuitem::Builder::new(format!("zoom-{}", pct), pct);
+    mib.parent("zoom").weight(i as isize).kind(Kind::Radio);
+    mb.add(mib);
+  }
+
+  mb.build()
+}
+
+fn change(id: &str, checked: bool) -> Change {
+  Change {
+    id: id.to_string(),
+    checked
+  }
+}
+
+
+#[test]
+fn initial_state() {
+  let menu = build_menu();
+  let store = Store::new(&menu);
+
+  // Only the first initially checked radio item in a group is checked
+  assert_eq!(store.selected("sort"), Some("date"));
+  assert!(!store.is_checked("size"));
+  assert!(!store.is_checked("hidden"));
+  assert_eq!(menu.get("date").unwrap().radio_group(), Some("sort"));
+  assert_eq!(menu.get("date").unwrap().kind(), Kind::Radio);
+}
+
+#[test]
+fn single_selection_and_events() {
+  let menu = build_menu();
+  let mut store = Store::new(&menu);
+
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let l = Arc::clone(&log);
+  store.on_change(move |ch| l.lock().unwrap().push(ch.clone()));
+
+  assert_eq!(
+    store.set("name", true).unwrap(),
+    [change("date", false), change("name", true)]
+  );
+  assert_eq!(store.selected("sort"), Some("name"));
+  assert!(store.set("name", true).unwrap().is_empty());
+
+  assert_eq!(store.toggle("hidden").unwrap(), [change("hidden", true)]);
+  assert_eq!(store.toggle("hidden").unwrap(), [change("hidden", false)]);
+
+  // Implicit group of sibling radio items
+  store.toggle("zoom-100").unwrap();
+  assert_eq!(
+    store.toggle("zoom-200").unwrap(),
+    [change("zoom-100", false), change("zoom-200", true)]
+  );
+
+  assert_eq!(log.lock().unwrap().len(), 7);
+
+  assert_eq!(
+    store.set("name", false),
+    Err(Error::UncheckRadio("name".to_string()))
+  );
+  assert_eq!(
+    store.toggle("view"),
+    Err(Error::NotCheckable("view".to_string()))
+  );
+}
+
+#[test]
+fn sync() {
+  let mut menu = build_menu();
+  let mut store = Store::new(&menu);
+  store.set("hidden", true).unwrap();
+
+  menu
+    .remove("hidden", menugen::edit::Removal::Reject)
+    .unwrap();
+  let mut mib = menuitem::Builder::new("type", "Type");
+  mib.parent("view").radio_group("sort").checked(true);
+  menu.insert(mib).unwrap();
+  store.sync(&menu);
+
+  assert!(!store.is_checked("hidden"));
+  assert!(store.toggle("hidden").is_err());
+  assert_eq!(store.selected("sort"), Some("date"));
+  store.set("type", true).unwrap();
+  assert_eq!(store.selected("sort"), Some("type"));
+}
+
+#[test]
+fn sync_radio_groups() {
+  let mut menu = build_menu();
+  let mut mib = menuitem::Builder::new("scale", "Scale");
+  mib.parent("view").weight(30);
+  menu.insert(mib).unwrap();
+  let mut mib = menuitem::Builder::new("scale-50", "50%");
+  mib
+    .parent("scale")
+    .weight(0)
+    .kind(Kind::Radio)
+    .checked(true);
+  menu.insert(mib).unwrap();
+
+  let mut store = Store::new(&menu);
+  store.toggle("zoom-200").unwrap();
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let l = Arc::clone(&log);
+  store.on_change(move |ch| l.lock().unwrap().push(ch.clone()));
+
+  // Moving "zoom-200" puts two checked items in the implicit group of the
+  // radio items under "scale"
+  menu.move_item("zoom-200", Some("scale")).unwrap();
+  assert_eq!(store.sync(&menu), [change("zoom-200", false)]);
+  assert!(store.is_checked("scale-50"));
+  assert!(!store.is_checked("zoom-200"));
+  assert_eq!(*log.lock().unwrap(), [change("zoom-200", false)]);
+  assert!(store.sync(&menu).is_empty());
+}
+
+#[test]
+fn disabled() {
+  let mut menu = build_menu();
+  menu.get_mut("hidden").unwrap().presentation_mut().disabled = true;
+  menu.get_mut("size").unwrap().presentation_mut().disabled = true;
+
+  let mut store = Store::new(&menu);
+  let disabled = |id: &str| Err(Error::Disabled(id.to_string()));
+  assert_eq!(store.toggle("hidden"), disabled("hidden"));
+  assert_eq!(store.set("size", true), disabled("size"));
+  assert!(!store.is_checked("hidden"));
+  assert_eq!(store.selected("sort"), Some("date"));
+
+  // Re-enabled items can be changed after a sync
+  menu.get_mut("size").unwrap().presentation_mut().disabled = false;
+  store.sync(&menu);
+  store.set("size", true).unwrap();
+  assert_eq!(store.selected("sort"), Some("size"));
+}
+
+#[test]
+fn send() {
+  let menu = build_menu();
+  let mut store = Store::new(&menu);
+  store.on_change(|_| {});
+  let store = thread::spawn(move || {
+    store.toggle("hidden").unwrap();
+    store
+  })
+  .join()
+  .unwrap();
+  assert!(store.is_checked("hidden"));
+}
+
+#[test]
+fn iteration() {
+  let menu = build_menu();
+  let mut store = Store::new(&menu);
+  store.toggle("hidden").unwrap();
+
+  let mut it = menu.iter_hier();
+  it.checks(&store);
+  let mut checked = Vec::new();
+  let mut unchecked = Vec::new();
+  for ev in it {
+    if let Event::MenuItem(mi) = ev {
+      match mi.checked {
+        Some(true) => checked.push(mi.item.id()),
+        Some(false) => unchecked.push(mi.item.id()),
+        None => {}
+      }
+    }
+  }
+  assert_eq!(checked, ["hidden", "date"]);
+  // Items that are not checkable have no state
+  assert_eq!(unchecked, ["name", "size", "zoom-100", "zoom-200"]);
+
+  // Without a store, the initial states are reported
+  let n = menu
+    .iter_hier()
+    .filter(|ev| matches!(ev, Event::MenuItem(mi) if mi.checked == Some(true)))
+    .count();
+  assert_eq!(n, 2);
+
+  // The state can also be looked up using the iterator
+  let mut it = menu.iter_hier();
+  it.checks(&store);
+  let mut looked_up = Vec::new();
+  while let Some(ev) = it.next() {
+    if let Event::MenuItem(mi) = ev {
+      if it.is_checked(&mi) {
+        looked_up.push(mi.item.id());
+      }
+    }
+  }
+  assert_eq!(looked_up, checked);
+
+  // The filtered iterator reports the same states
+  let mut it = menu.filtiter_hier(|mi| mi.id() != "date");
+  it.checks(&store);
+  let mut checked = Vec::new();
+  for ev in it {
+    if let recfiltiter::Event::MenuItem(mi) = ev {
+      if mi.checked == Some(true) {
+        checked.push(mi.item.id());
+      }
+    }
+  }
+  assert_eq!(checked, ["hidden"]);
+}
+
+#[test]
+fn session() {
+  let menu = build_menu();
+  let mut store = Store::new(&menu);
+  store.set("size", true).unwrap();
+
+  let mut sess = Session::new(&menu);
+  sess.checks(&store).input("1");
+  assert_eq!(
+    sess.render().to_string(),
+    concat!(
+      "View\n",
+      "1. [ ] Show hidden files\n",
+      "2. ( ) Name\n",
+      "3. ( ) Date\n",
+      "4. (*) Size\n",
+      "5. Zoom\n",
+      "0. Back\n",
+      "00. Home"
+    )
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use menugen::collect::{Collector, Error, Token};
+use menugen::reciter::{Event, Expand};
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("adm", "Administration");
+  mib.require_any(vec!["admin"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-users", "Users");
+  mib.parent("adm");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib", "Library");
+  mib.weight(200);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb", "Knowledge Base");
+  mib.parent("lib");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb-arch", "Archive");
+  mib.parent("lib-kb");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-adm", "Library Admin");
+  mib.parent("lib").require_any(vec!["admin"]);
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn tree(menu: &menu::Menu<()>) -> Vec<String> {
+  let mut out = Vec::new();
+  let mut indent = 0;
+  for ev in menu.iter_hier() {
+    match ev {
+      Event::EnterScope => indent += 1,
+      Event::LeaveScope => indent -= 1,
+      Event::MenuItem(mi) | Event::Collapsed(mi) => {
+        out.push(format!("{}{}", "  ".repeat(indent), mi.id()))
+      }
+      _ => {}
+    }
+  }
+  out
+}
+
+
+#[test]
+fn roundtrip() {
+  let menu = build_menu();
+
+  let copy = menu::Menu::from_events(menu.iter_hier()).unwrap();
+  assert_eq!(tree(&copy), tree(&menu));
+
+  let mut it = menu.iter_hier();
+  it.root_scope();
+  let copy = menu::Menu::from_events(it).unwrap();
+  assert_eq!(tree(&copy), tree(&menu));
+}
+
+
+#[test]
+fn filtered() {
+  let menu = build_menu();
+  let principal: HashSet<&str> = HashSet::new();
+
+  let mut it = menu.for_principal(&principal);
+  it.root_scope();
+  let user = menu::Menu::from_events(it).unwrap();
+  assert_eq!(tree(&user), vec!["lib", "  lib-kb", "    lib-kb-arch"]);
+
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::Depth(1));
+  let shallow = menu::Menu::from_events(it).unwrap();
+  assert_eq!(
+    tree(&shallow),
+    vec!["adm", "  adm-users", "lib", "  lib-kb", "  lib-adm"]
+  );
+}
+
+
+#[test]
+fn synthesized() {
+  let mut coll = Collector::<()>::new();
+  coll.push(menuitem::Builder::new("b", "B")).unwrap();
+  coll.push(Token::EnterScope).unwrap();
+  coll.push(menuitem::Builder::new("b-1", "B1")).unwrap();
+  coll.push(Token::LeaveScope).unwrap();
+  coll.push(menuitem::Builder::new("a", "A")).unwrap();
+  let mut menu = coll.finish().unwrap();
+
+  // Order is preserved, not re-sorted, including when the menu is edited
+  assert_eq!(tree(&menu), vec!["b", "  b-1", "a"]);
+  menu.insert(menuitem::Builder::new("c", "C")).unwrap();
+  assert_eq!(tree(&menu), vec!["b", "  b-1", "a", "c"]);
+}
+
+
+#[test]
+fn errors() {
+  let mut coll = Collector::<()>::new();
+  coll.push(menuitem::Builder::new("a", "A")).unwrap();
+  coll.push(Token::EnterScope).unwrap();
+  assert_eq!(coll.push(Token::EnterScope), Err(Error::OrphanScope));
+  assert_eq!(coll.finish().err(), Some(Error::Unterminated));
+
+  let mut coll = Collector::<()>::new();
+  assert_eq!(coll.push(Token::LeaveScope), Err(Error::UnbalancedLeave));
+
+  let mut coll = Collector::<()>::new();
+  coll.push(menuitem::Builder::new("a", "A")).unwrap();
+  assert_eq!(
+    coll.push(menuitem::Builder::new("a", "A")),
+    Err(Error::DuplicateId("a".to_string()))
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,218 @@
+use menugen::cursor::MenuCursor;
+use menugen::menuitem::Kind;
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("file", "File");
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-open", "Open");
+  mib.parent("file").weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-recent", "Recent");
+  mib.parent("file").weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-recent-1", "foo.txt");
+  mib.parent("file-recent");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("edit", "Edit");
+  mib.weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("help", "Help");
+  mib.weight(30);
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn visible(cur: &MenuCursor<()>) -> Vec<String> {
+  cur
+    .rows()
+    .iter()
+    .map(|r| {
+      format!(
+        "{}{}{}",
+        "  ".repeat(r.depth),
+        if r.selected { ">" } else { "" },
+        r.item.id()
+      )
+    })
+    .collect()
+}
+
+
+#[test]
+fn up_down() {
+  let menu = build_menu();
+  let mut cur = MenuCursor::new(&menu);
+
+  assert_eq!(visible(&cur), vec![">file", "edit", "help"]);
+  assert!(!cur.up());
+  assert!(cur.down());
+  assert!(cur.down());
+  assert!(!cur.down());
+  assert_eq!(cur.selected().unwrap().id(), "help");
+
+  cur.wrap(true);
+  assert!(cur.down());
+  assert_eq!(cur.selected().unwrap().id(), "file");
+  assert!(cur.up());
+  assert_eq!(cur.selected().unwrap().id(), "help");
+
+  assert!(cur.home());
+  assert_eq!(cur.selected_index(), Some(0));
+  assert!(cur.end());
+  assert_eq!(cur.selected_index(), Some(2));
+}
+
+
+#[test]
+fn expand_collapse() {
+  let menu = build_menu();
+  let mut cur = MenuCursor::new(&menu);
+
+  assert!(cur.right());
+  assert_eq!(
+    visible(&cur),
+    vec![">file", "  file-open", "  file-recent", "edit", "help"]
+  );
+  assert!(cur.right());
+  assert_eq!(cur.selected().unwrap().id(), "file-open");
+  assert!(!cur.right());
+
+  assert!(cur.down());
+  assert!(cur.right());
+  assert!(cur.right());
+  assert_eq!(
+    visible(&cur),
+    vec![
+      "file",
+      "  file-open",
+      "  file-recent",
+      "    >file-recent-1",
+      "edit",
+      "help"
+    ]
+  );
+
+  assert!(cur.left());
+  assert_eq!(cur.selected().unwrap().id(), "file-recent");
+  assert!(cur.left());
+  assert!(cur.left());
+  assert_eq!(cur.selected().unwrap().id(), "file");
+
+  cur.expand_all();
+  assert!(cur.select("file-recent-1"));
+  assert!(cur.collapse("file"));
+  assert_eq!(visible(&cur), vec![">file", "edit", "help"]);
+  assert!(cur.is_expanded("file-recent"));
+
+  cur.select("file-recent-1");
+  cur.collapse_all();
+  assert_eq!(visible(&cur), vec![">file", "edit", "help"]);
+}
+
+
+#[test]
+fn paging_and_letters() {
+  let menu = build_menu();
+  let mut cur = MenuCursor::new(&menu);
+  cur.page_height(2).expand_all();
+
+  assert!(cur.page_down());
+  assert_eq!(cur.selected().unwrap().id(), "file-recent");
+  assert!(cur.page_down());
+  assert!(cur.page_down());
+  assert_eq!(cur.selected().unwrap().id(), "help");
+  assert!(cur.page_up());
+  assert_eq!(cur.selected().unwrap().id(), "file-recent-1");
+
+  assert!(cur.jump_to_letter('F'));
+  assert_eq!(cur.selected().unwrap().id(), "file");
+  assert!(cur.jump_to_letter('h'));
+  assert_eq!(cur.selected().unwrap().id(), "help");
+  assert!(!cur.jump_to_letter('x'));
+}
+
+
+#[test]
+fn skip_unfocusable() {
+  let mut mb = menu::Builder::<()>::new();
+  for (i, (id, kind, disabled)) in [
+    ("hdr", Kind::Header, false),
+    ("new", Kind::Link, false),
+    ("sep", Kind::Separator, false),
+    ("print", Kind::Link, true),
+    ("quit", Kind::Link, false)
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(id, id);
+    mib.kind(*kind).disabled(*disabled).weight(i as isize);
+    mb.add(mib);
+  }
+  let menu = mb.build();
+  let mut cur = MenuCursor::new(&menu);
+
+  // Separators and headers are rows, but are never selected
+  assert_eq!(visible(&cur), vec!["hdr", ">new", "sep", "print", "quit"]);
+  assert!(cur.down());
+  assert_eq!(cur.selected().unwrap().id(), "quit");
+  assert!(!cur.down());
+  assert!(cur.home());
+  assert_eq!(cur.selected().unwrap().id(), "new");
+  assert!(!cur.up());
+  assert!(cur.end());
+  assert_eq!(cur.selected().unwrap().id(), "quit");
+
+  assert!(!cur.jump_to_letter('p'));
+  assert!(!cur.jump_to_letter('s'));
+  assert!(!cur.select("sep"));
+  assert!(!cur.select("print"));
+  assert_eq!(cur.selected().unwrap().id(), "quit");
+}
+
+
+#[test]
+fn unfocusable_ancestors() {
+  let mut mb = menu::Builder::<()>::new();
+  for (i, (id, kind, disabled)) in [
+    ("hdr", Kind::Header, false),
+    ("file", Kind::Link, true),
+    ("quit", Kind::Link, false)
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(*id, *id);
+    mib.kind(*kind).disabled(*disabled).weight(i as isize);
+    mb.add(mib);
+  }
+  let mut mib = menuitem::Builder::new("file-new", "New");
+  mib.parent("file");
+  mb.add(mib);
+  let menu = mb.build();
+  let mut cur = MenuCursor::new(&menu);
+
+  // The disabled parent is never selected on the way out
+  assert!(cur.select("file-new"));
+  assert!(!cur.left());
+  assert_eq!(cur.selected().unwrap().id(), "file-new");
+
+  assert!(cur.collapse("file"));
+  assert_eq!(visible(&cur), vec!["hdr", "file", ">quit"]);
+
+  assert!(cur.select("file-new"));
+  cur.collapse_all();
+  assert_eq!(visible(&cur), vec!["hdr", "file", ">quit"]);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
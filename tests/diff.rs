@@ -0,0 +1,234 @@
+use menugen::accel::Accel;
+use menugen::diff::Change;
+use menugen::menuitem::{Kind, Presentation};
+use menugen::{menu, menuitem};
+
+fn item(
+  id: &str,
+  title: &str,
+  parent: Option<&str>,
+  weight: isize,
+  url: &str
+) -> menuitem::Builder<String> {
+  let mut mib = menuitem::Builder::new_ctx(id, title, url.to_string());
+  mib.weight(weight);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  mib
+}
+
+fn old_menu() -> menu::Menu<String> {
+  let mut mb = menu::Builder::new();
+  mb.add(item("file", "File", None, 10, "/file"));
+  mb.add(item("file-new", "New", Some("file"), 10, "/file/new"));
+  mb.add(item("file-open", "Open", Some("file"), 20, "/file/open"));
+  mb.add(item("file-save", "Save", Some("file"), 30, "/file/save"));
+  mb.add(item("file-quit", "Quit", Some("file"), 40, "/file/quit"));
+  mb.add(item("edit", "Edit", None, 20, "/edit"));
+  mb.add(item("help", "Help", None, 30, "/help"));
+  mb.build()
+}
+
+fn new_menu() -> menu::Menu<String> {
+  let mut mb = menu::Builder::new();
+  mb.add(item("file", "Files", None, 10, "/file"));
+  mb.add(item("file-new", "New", Some("file"), 10, "/file/new"));
+  mb.add(item("file-open", "Open", Some("file"), 20, "/files/open"));
+  mb.add(item("file-save", "Save", Some("file"), 50, "/file/save"));
+  mb.add(item("file-quit", "Quit", Some("file"), 40, "/file/quit"));
+  mb.add(item("edit", "Edit", None, 20, "/edit"));
+  mb.add(item("edit-copy", "Copy", Some("edit"), 10, "/edit/copy"));
+  mb.add(item("edit-new", "New", Some("edit"), 20, "/file/new"));
+  mb.add(item("view", "View", None, 25, "/view"));
+  mb.build()
+}
+
+
+#[test]
+fn identical() {
+  let d = menu::diff(&old_menu(), &old_menu());
+  assert!(d.is_empty());
+  assert_eq!(d.to_string(), "");
+}
+
+#[test]
+fn changes() {
+  let d = menu::diff(&old_menu(), &new_menu());
+  assert_eq!(
+    d.changes,
+    vec![
+      Change::Removed {
+        id: "help".to_string(),
+        parent: None
+      },
+      Change::Retitled {
+        id: "file".to_string(),
+        from: "File".to_string(),
+        to: "Files".to_string()
+      },
+      Change::ContextChanged {
+        id: "file-open".to_string()
+      },
+      Change::Reordered {
+        id: "file-save".to_string(),
+        parent: Some("file".to_string()),
+        from: 2,
+        to: 3
+      },
+      Change::Reweighted {
+        id: "file-save".to_string(),
+        from: 30,
+        to: 50
+      },
+      Change::Added {
+        id: "edit-copy".to_string(),
+        parent: Some("edit".to_string())
+      },
+      Change::Added {
+        id: "edit-new".to_string(),
+        parent: Some("edit".to_string())
+      },
+      Change::Added {
+        id: "view".to_string(),
+        parent: None
+      },
+    ]
+  );
+  assert_eq!(d.for_item("file-save").count(), 2);
+}
+
+#[test]
+fn moved_not_reordered() {
+  let old = old_menu();
+  let mut new = old_menu();
+  new.move_item("file-open", Some("edit")).unwrap();
+
+  let d = menu::diff(&old, &new);
+  assert_eq!(
+    d.changes,
+    vec![Change::Moved {
+      id: "file-open".to_string(),
+      from: Some("file".to_string()),
+      to: Some("edit".to_string())
+    }]
+  );
+}
+
+#[test]
+fn custom_equality() {
+  let old = old_menu();
+  let new = old_menu().map_ctx(|mi| mi.appctx().to_uppercase());
+
+  assert_eq!(menu::diff_by(&old, &new, |a, b| a == b).changes.len(), 7);
+  assert!(
+    menu::diff_by(&old, &new, |a, b| a.eq_ignore_ascii_case(b)).is_empty()
+  );
+}
+
+#[test]
+fn text() {
+  let d = menu::diff(&old_menu(), &new_menu());
+  let expect = r#"- help (was under <root>)
+~ file: title "File" -> "Files"
+~ file-open: context changed
+~ file-save: reordered under file, position 2 -> 3
+~ file-save: weight 30 -> 50
++ edit-copy (under edit)
++ edit-new (under edit)
++ view (under <root>)"#;
+  assert_eq!(d.to_string(), expect);
+}
+
+#[test]
+fn properties() {
+  let mut mb = menu::Builder::new();
+  mb.add(item("file", "File", None, 10, "/file"));
+  let mut mib = item("file-save", "Save", Some("file"), 10, "/file/save");
+  mib.accel(Accel::parse("Ctrl+S").unwrap()).mnemonic('s');
+  mb.add(mib);
+  let old = mb.build();
+
+  let mut mb = menu::Builder::new();
+  let mut mib = item("file", "File", None, 10, "/file");
+  mib.kind(Kind::Group);
+  mb.add(mib);
+  let mut mib = item("file-save", "Save", Some("file"), 10, "/file/save");
+  mib.badge("2").disabled(true).mnemonic('a');
+  mb.add(mib);
+  let new = mb.build();
+
+  let d = menu::diff(&old, &new);
+  assert_eq!(
+    d.changes,
+    vec![
+      Change::KindChanged {
+        id: "file".to_string(),
+        from: Kind::Link,
+        to: Kind::Group
+      },
+      Change::PresentationChanged {
+        id: "file-save".to_string(),
+        from: Presentation::default(),
+        to: new.get("file-save").unwrap().presentation().clone()
+      },
+      Change::AccelChanged {
+        id: "file-save".to_string(),
+        from: Some(Accel::parse("Ctrl+S").unwrap()),
+        to: None
+      },
+      Change::MnemonicChanged {
+        id: "file-save".to_string(),
+        from: Some('s'),
+        to: Some('a')
+      },
+    ]
+  );
+  let expect = r#"~ file: kind link -> group
+~ file-save: presentation changed (badge, disabled)
+~ file-save: accelerator Ctrl+S -> none
+~ file-save: mnemonic s -> a"#;
+  assert_eq!(d.to_string(), expect);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json() {
+  use serde_json::json;
+
+  let mut new = old_menu();
+  new.move_item("file-open", None).unwrap();
+  new.set_title("edit", "Change").unwrap();
+
+  new.get_mut("edit").unwrap().presentation_mut().icon =
+    Some("pen".to_string());
+
+  let d = menu::diff(&old_menu(), &new);
+  assert_eq!(
+    d.to_json(),
+    json!([
+      { "change": "retitled", "id": "edit", "from": "Edit", "to": "Change" },
+      {
+        "change": "presentation",
+        "id": "edit",
+        "from": {
+          "icon": null,
+          "tooltip": null,
+          "description": null,
+          "badge": null,
+          "disabled": false
+        },
+        "to": {
+          "icon": "pen",
+          "tooltip": null,
+          "description": null,
+          "badge": null,
+          "disabled": false
+        }
+      },
+      { "change": "moved", "id": "file-open", "from": "file", "to": null }
+    ])
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,196 @@
+use menugen::edit::{Error, Removal};
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("file", "File");
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-open", "Open");
+  mib.parent("file").weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-recent", "Recent");
+  mib.parent("file").weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-recent-1", "one.txt");
+  mib.parent("file-recent");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-quit", "Quit");
+  mib.parent("file").weight(30);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("edit", "Edit");
+  mib.weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("help", "Help");
+  mib.weight(30);
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn ids(lst: &[menuitem::MenuItem<()>]) -> Vec<&str> {
+  lst.iter().map(|mi| mi.id()).collect()
+}
+
+
+#[test]
+fn insert() {
+  let mut menu = build_menu();
+
+  let mut mib = menuitem::Builder::new("edit-copy", "Copy");
+  mib.parent("edit");
+  menu.insert(mib).unwrap();
+
+  let mut mib = menuitem::Builder::new("view", "View");
+  mib.weight(25);
+  menu.insert(mib).unwrap();
+
+  assert_eq!(ids(menu.get_rootitems()), ["file", "edit", "view", "help"]);
+  assert_eq!(ids(menu.get("edit").unwrap().children()), ["edit-copy"]);
+}
+
+#[test]
+fn insert_invalid() {
+  let mut menu = build_menu();
+
+  let mut mib = menuitem::Builder::new("file-open", "Open");
+  mib.parent("edit");
+  assert_eq!(
+    menu.insert(mib),
+    Err(Error::DuplicateId("file-open".to_string()))
+  );
+
+  let mut mib = menuitem::Builder::new("tools-x", "X");
+  mib.parent("tools");
+  assert_eq!(
+    menu.insert(mib),
+    Err(Error::UnknownParent("tools".to_string()))
+  );
+  assert!(menu.get("tools-x").is_none());
+}
+
+#[test]
+fn remove() {
+  let mut menu = build_menu();
+
+  assert_eq!(
+    menu.remove("file-recent", Removal::Reject).unwrap_err(),
+    Error::HasChildren("file-recent".to_string())
+  );
+
+  let mi = menu.remove("file-recent", Removal::Recursive).unwrap();
+  assert_eq!(ids(mi.children()), ["file-recent-1"]);
+  assert!(menu.get("file-recent-1").is_none());
+  assert_eq!(
+    ids(menu.get("file").unwrap().children()),
+    ["file-open", "file-quit"]
+  );
+
+  // Put it back
+  menu.insert_item(Some("file"), mi).unwrap();
+  assert_eq!(
+    ids(menu.get("file").unwrap().children()),
+    ["file-open", "file-recent", "file-quit"]
+  );
+  assert!(menu.get("file-recent-1").is_some());
+
+  assert_eq!(
+    menu.remove("nope", Removal::Recursive).unwrap_err(),
+    Error::UnknownItem("nope".to_string())
+  );
+}
+
+#[test]
+fn remove_reparent() {
+  let mut menu = build_menu();
+
+  let mi = menu.remove("file", Removal::Reparent).unwrap();
+  assert!(mi.children().is_empty());
+  assert_eq!(
+    ids(menu.get_rootitems()),
+    ["file-open", "edit", "file-recent", "help", "file-quit"]
+  );
+  assert!(menu.get("file-recent-1").is_some());
+}
+
+#[test]
+fn move_item() {
+  let mut menu = build_menu();
+
+  menu.move_item("file-recent", Some("edit")).unwrap();
+  assert_eq!(ids(menu.get("edit").unwrap().children()), ["file-recent"]);
+  let path: Vec<&str> = menu
+    .path_to("file-recent-1")
+    .unwrap()
+    .iter()
+    .map(|mi| mi.id())
+    .collect();
+  assert_eq!(path, ["edit", "file-recent", "file-recent-1"]);
+
+  menu.move_item("file-quit", None).unwrap();
+  assert_eq!(
+    ids(menu.get_rootitems()),
+    ["file", "edit", "help", "file-quit"]
+  );
+}
+
+#[test]
+fn move_cycle() {
+  let mut menu = build_menu();
+
+  assert_eq!(
+    menu.move_item("file", Some("file-recent-1")),
+    Err(Error::Cycle("file".to_string()))
+  );
+  assert_eq!(
+    menu.move_item("file", Some("file")),
+    Err(Error::Cycle("file".to_string()))
+  );
+  assert_eq!(
+    menu.move_item("file", Some("nope")),
+    Err(Error::UnknownParent("nope".to_string()))
+  );
+  assert_eq!(ids(menu.get_rootitems()), ["file", "edit", "help"]);
+}
+
+#[test]
+fn retitle_reweight() {
+  let mut menu = build_menu();
+
+  menu.set_weight("file", 100).unwrap();
+  assert_eq!(ids(menu.get_rootitems()), ["edit", "help", "file"]);
+
+  menu.set_weight("help", 20).unwrap();
+  menu.set_title("help", "A help").unwrap();
+  assert_eq!(ids(menu.get_rootitems()), ["help", "edit", "file"]);
+  assert_eq!(menu.get("help").unwrap().title(), "A help");
+
+  assert_eq!(
+    menu.set_title("nope", "x"),
+    Err(Error::UnknownItem("nope".to_string()))
+  );
+}
+
+#[test]
+fn insert_adopts_orphan() {
+  let mut mb = menu::Builder::<()>::new();
+  let mut mib = menuitem::Builder::new("lost", "Lost");
+  mib.parent("missing");
+  mb.add(mib);
+  let mut menu = mb.build();
+  assert!(menu.orphans().contains_key("lost"));
+
+  menu
+    .insert(menuitem::Builder::new("lost", "Found"))
+    .unwrap();
+  assert!(menu.orphans().is_empty());
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
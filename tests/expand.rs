@@ -0,0 +1,133 @@
+use menugen::reciter::{Event, Expand};
+use menugen::{menu, menuitem, recfiltiter, reciter};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("a", "A");
+  mib.weight(1);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("a-1", "A1");
+  mib.parent("a");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("a-1-1", "A11");
+  mib.parent("a-1");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("b", "B");
+  mib.weight(2);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("b-1", "B1");
+  mib.parent("b");
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn build_strvec(it: reciter::MenuIter<()>) -> Vec<String> {
+  let mut out = Vec::new();
+  let mut indent = 0;
+  for ev in it {
+    match ev {
+      Event::EnterScope => indent += 1,
+      Event::LeaveScope => indent -= 1,
+      Event::MenuItem(mi) => {
+        out.push(format!("{}{}", "  ".repeat(indent), mi.id()))
+      }
+      Event::Collapsed(mi) => {
+        out.push(format!("{}{}+", "  ".repeat(indent), mi.id()))
+      }
+      _ => {}
+    }
+  }
+  out
+}
+
+
+#[test]
+fn expand_all() {
+  let menu = build_menu();
+  let it = menu.iter_hier();
+
+  assert_eq!(
+    build_strvec(it),
+    vec!["a", "  a-1", "    a-1-1", "b", "  b-1"]
+  );
+}
+
+
+#[test]
+fn expand_ids() {
+  let menu = build_menu();
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::ids(vec!["a"]));
+
+  assert_eq!(build_strvec(it), vec!["a", "  a-1+", "b+"]);
+}
+
+
+#[test]
+fn expand_trail() {
+  let menu = build_menu();
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::trail("a-1-1"));
+
+  assert_eq!(build_strvec(it), vec!["a", "  a-1", "    a-1-1", "b+"]);
+
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::trail("b"));
+
+  assert_eq!(build_strvec(it), vec!["a+", "b", "  b-1"]);
+}
+
+
+#[test]
+fn expand_depth() {
+  let menu = build_menu();
+
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::Depth(0));
+  assert_eq!(build_strvec(it), vec!["a+", "b+"]);
+
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::Depth(1));
+  assert_eq!(build_strvec(it), vec!["a", "  a-1+", "b", "  b-1"]);
+}
+
+
+#[test]
+fn expand_func() {
+  let menu = build_menu();
+
+  let mut it = menu.iter_hier();
+  it.expansion(Expand::func(|mi, _depth| mi.id().starts_with('a')));
+  it.root_scope();
+  assert_eq!(
+    build_strvec(it),
+    vec!["  a", "    a-1", "      a-1-1", "  b+"]
+  );
+}
+
+
+#[test]
+fn expand_filtered() {
+  let menu = build_menu();
+
+  let mut it = menu.filtiter_hier(|mi| mi.id() != "b-1");
+  it.expansion(Expand::Depth(1));
+
+  let mut out = Vec::new();
+  for ev in it {
+    match ev {
+      recfiltiter::Event::MenuItem(mi) => out.push(mi.id().to_string()),
+      recfiltiter::Event::Collapsed(mi) => out.push(format!("{}+", mi.id())),
+      _ => {}
+    }
+  }
+  assert_eq!(out, vec!["a", "a-1+", "b"]);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+
+use menugen::menu::Visibility;
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("adm", "Administration");
+  mib.require_any(vec!["admin"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-users", "Users");
+  mib.parent("adm");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib", "Library");
+  mib.require_any(vec!["reader"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-secret", "Secret");
+  mib.parent("lib").require_any(vec!["admin"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lost", "Lost");
+  mib.parent("nonexistent");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lost-child", "Lost child");
+  mib.parent("lost");
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn explain() {
+  let menu = build_menu();
+  let principal: HashSet<&str> = vec!["reader"].into_iter().collect();
+  let it = menu.for_principal(&principal);
+
+  assert_eq!(it.explain("lib"), Visibility::Visible);
+  assert_eq!(it.explain("lib-secret"), Visibility::Rejected);
+  assert_eq!(it.explain("adm"), Visibility::Rejected);
+  assert_eq!(
+    it.explain("adm-users"),
+    Visibility::PrunedBy("adm".to_string())
+  );
+  assert_eq!(
+    it.explain("lost"),
+    Visibility::Orphan {
+      parent: "nonexistent".to_string()
+    }
+  );
+  assert_eq!(
+    it.explain("lost-child"),
+    Visibility::Orphan {
+      parent: "lost".to_string()
+    }
+  );
+  assert_eq!(it.explain("nope"), Visibility::Unknown);
+
+  // Rejection by an ancestor takes precedence
+  assert_eq!(
+    menu.explain("lib-secret", |_| false),
+    Visibility::PrunedBy("lib".to_string())
+  );
+}
+
+
+#[test]
+fn orphans() {
+  let mut mb = menu::Builder::<()>::new();
+
+  let mut mib = menuitem::Builder::new("a", "A");
+  mib.parent("b");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("b", "B");
+  mib.parent("a");
+  mb.add(mib);
+
+  let mib = menuitem::Builder::new("c", "C");
+  mb.add(mib);
+
+  let menu = mb.build();
+  assert_eq!(menu.orphans().len(), 2);
+  assert_eq!(menu.orphans().get("a").map(|s| s.as_str()), Some("b"));
+  assert_eq!(
+    menu.explain("b", |_| true),
+    Visibility::Orphan {
+      parent: "a".to_string()
+    }
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
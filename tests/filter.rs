@@ -28,6 +28,7 @@ where
       reciter::Event::LeaveScope => {
         indent -= 1;
       }
+      _ => {}
     }
   }
   out
@@ -58,10 +59,10 @@ fn filter_none_of_one() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = vec!["test-1(Test A)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -75,10 +76,10 @@ fn filter_all_of_one() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = Vec::new();
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -96,10 +97,10 @@ fn filter_none_of_two() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = vec!["test-1(Test A)", "test-2(Test B)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -117,10 +118,10 @@ fn filter_all_of_two() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = Vec::new();
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -138,10 +139,10 @@ fn filter_first_of_two() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = vec!["test-2(Test B)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -159,10 +160,10 @@ fn filter_second_of_two() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = vec!["test-1(Test A)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -180,10 +181,10 @@ fn filter_parent_two() {
   let menu = mb.build();
 
   let mut it = reciter::MenuIter::new(&menu);
-  it.itemfilter(|mi| mi.appctx().enabled == true);
+  it.itemfilter(|mi| mi.appctx().enabled);
 
   let expect = vec!["test-1(Test A)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 // vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
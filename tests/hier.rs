@@ -24,6 +24,7 @@ where
       Event::LeaveScope => {
         indent -= 1;
       }
+      _ => {}
     }
   }
   out
@@ -55,7 +56,7 @@ fn simple_no_root_scope() {
   let it = reciter::MenuIter::new(&menu);
 
   let expect = vec!["test-1(Test A)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -71,7 +72,7 @@ fn simple_root_scope() {
   it.root_scope();
 
   let expect = vec!["  test-1(Test A)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -90,7 +91,7 @@ fn sub_root_no_scope() {
   let it = reciter::MenuIter::new(&menu);
 
   let expect = vec!["test-1(Test A)", "  test-1-1(Test A Sub)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
@@ -110,7 +111,7 @@ fn sub_root_scope() {
   it.root_scope();
 
   let expect = vec!["  test-1(Test A)", "    test-1-1(Test A Sub)"];
-  assert_eq!(verify(it, expect), true);
+  assert!(verify(it, expect));
 }
 
 
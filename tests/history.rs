@@ -0,0 +1,280 @@
+use menugen::accel::Accel;
+use menugen::edit::{Error, Removal};
+use menugen::history::{self, Command, Editor};
+use menugen::menuitem::{Kind, Presentation};
+use menugen::rule::Rule;
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<String> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("file", "File");
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-open", "Open");
+  mib.parent("file").weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("file-quit", "Quit");
+  mib.parent("file").weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("edit", "Edit");
+  mib.weight(20);
+  mb.add(mib);
+
+  mb.build()
+}
+
+/// Render the menu as a compact string, for comparisons.  Kinds other than
+/// links, badges and accelerators are only rendered if they are present.
+fn dump<C: ToString>(menu: &menu::Menu<C>) -> String {
+  fn rec<C: ToString>(lst: &[menuitem::MenuItem<C>], out: &mut Vec<String>) {
+    for mi in lst {
+      let mut s = format!(
+        "{}:{}:{}:{}",
+        mi.id(),
+        mi.title(),
+        mi.weight(),
+        mi.appctx().to_string()
+      );
+      if mi.kind() != Kind::Link {
+        s = format!("{}/{}", s, mi.kind());
+      }
+      if let Some(badge) = &mi.presentation().badge {
+        s = format!("{}#{}", s, badge);
+      }
+      if let Some(accel) = mi.accel() {
+        s = format!("{}^{}", s, accel);
+      }
+      if !mi.children().is_empty() {
+        let mut sub = Vec::new();
+        rec(mi.children(), &mut sub);
+        s = format!("{}[{}]", s, sub.join(","));
+      }
+      out.push(s);
+    }
+  }
+  let mut out = Vec::new();
+  rec(menu.get_rootitems(), &mut out);
+  out.join(",")
+}
+
+fn add(id: &str, parent: Option<&str>, weight: isize) -> Command<String> {
+  let mut mib =
+    menuitem::Builder::new_ctx(id, id.to_uppercase(), format!("ctx-{}", id));
+  mib.weight(weight);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  Command::add(mib)
+}
+
+
+#[test]
+fn undo_redo_each_command() {
+  let orig = dump(&build_menu());
+  let cmds = vec![
+    add("view", None, 15),
+    Command::Remove {
+      id: "file".to_string(),
+      policy: Removal::Reparent
+    },
+    Command::Remove {
+      id: "edit".to_string(),
+      policy: Removal::Recursive
+    },
+    Command::Move {
+      id: "file-quit".to_string(),
+      parent: Some("view".to_string())
+    },
+    Command::Retitle {
+      id: "view".to_string(),
+      title: "Look".to_string()
+    },
+    Command::Reweight {
+      id: "view".to_string(),
+      weight: 1
+    },
+    Command::SetContext {
+      id: "file-open".to_string(),
+      ctx: "changed".to_string()
+    },
+    Command::SetKind {
+      id: "view".to_string(),
+      kind: Kind::Group
+    },
+    Command::SetPresentation {
+      id: "file-open".to_string(),
+      presentation: Presentation {
+        badge: Some("3".to_string()),
+        ..Presentation::default()
+      }
+    },
+    Command::SetAccel {
+      id: "file-open".to_string(),
+      accel: Some(Accel::parse("Ctrl+O").unwrap())
+    },
+  ];
+
+  let mut ed = Editor::new(build_menu());
+  let mut states = vec![dump(ed.menu())];
+  for cmd in cmds {
+    ed.apply(cmd).unwrap();
+    states.push(dump(ed.menu()));
+  }
+  assert_eq!(
+    states.last().unwrap(),
+    concat!(
+      "view:Look:1:ctx-view/group[file-quit:Quit:20:],",
+      "file-open:Open:10:changed#3^Ctrl+O"
+    )
+  );
+
+  // Walk all the way back, and then forward again
+  for state in states.iter().rev().skip(1) {
+    assert!(ed.undo().unwrap());
+    assert_eq!(&dump(ed.menu()), state);
+  }
+  assert!(!ed.undo().unwrap());
+  assert_eq!(dump(ed.menu()), orig);
+
+  for state in states.iter().skip(1) {
+    assert!(ed.redo().unwrap());
+    assert_eq!(&dump(ed.menu()), state);
+  }
+  assert!(!ed.redo().unwrap());
+}
+
+#[test]
+fn transactions() {
+  let orig = dump(&build_menu());
+  let mut ed = Editor::new(build_menu());
+
+  ed.begin();
+  ed.apply(add("view", None, 15)).unwrap();
+  ed.apply(add("view-zoom", Some("view"), 10)).unwrap();
+  ed.commit();
+  ed.apply(Command::Retitle {
+    id: "edit".to_string(),
+    title: "Change".to_string()
+  })
+  .unwrap();
+
+  assert!(ed.undo().unwrap());
+  assert!(ed.menu().get("view-zoom").is_some());
+  assert!(ed.undo().unwrap());
+  assert!(ed.menu().get("view").is_none());
+  assert_eq!(dump(ed.menu()), orig);
+  assert!(ed.redo().unwrap());
+  assert!(ed.menu().get("view-zoom").is_some());
+
+  // Rolling back an open transaction
+  ed.begin();
+  ed.apply(add("tools", None, 50)).unwrap();
+  assert!(ed.in_transaction());
+  ed.rollback().unwrap();
+  assert!(!ed.in_transaction());
+  assert!(ed.menu().get("tools").is_none());
+  assert_eq!(ed.log().len(), 2);
+}
+
+#[test]
+fn new_command_discards_redo() {
+  let mut ed = Editor::new(build_menu());
+  ed.apply(add("view", None, 15)).unwrap();
+  ed.undo().unwrap();
+  assert!(ed.can_redo());
+  ed.apply(add("tools", None, 50)).unwrap();
+  assert!(!ed.can_redo());
+}
+
+#[test]
+fn failed_command() {
+  let mut ed = Editor::new(build_menu());
+  assert_eq!(
+    ed.apply(add("file-open", None, 1)),
+    Err(Error::DuplicateId("file-open".to_string()))
+  );
+  assert_eq!(
+    ed.apply(Command::Move {
+      id: "file".to_string(),
+      parent: Some("file-quit".to_string())
+    }),
+    Err(Error::Cycle("file".to_string()))
+  );
+  assert!(!ed.can_undo());
+  assert!(ed.log().is_empty());
+}
+
+#[test]
+fn replay_log() {
+  let mut ed = Editor::new(build_menu());
+  ed.apply(add("view", None, 15)).unwrap();
+  ed.apply(Command::Move {
+    id: "file-open".to_string(),
+    parent: Some("view".to_string())
+  })
+  .unwrap();
+  ed.apply(add("tools", None, 50)).unwrap();
+  ed.undo().unwrap();
+
+  let log: Vec<Command<String>> = ed.log().into_iter().cloned().collect();
+  assert_eq!(log.len(), 2);
+
+  let mut copy = build_menu();
+  history::replay(&mut copy, log).unwrap();
+  assert_eq!(dump(&copy), dump(ed.menu()));
+}
+
+#[test]
+fn restore_full_items() {
+  let mut mib = menuitem::Builder::new_ctx("adm", "Admin", "ctx".to_string());
+  mib
+    .parent("file")
+    .require_all(vec!["admin"])
+    .visible_if(Rule::parse("os:linux").unwrap());
+
+  let mut ed = Editor::new(build_menu());
+  ed.apply(Command::add(mib)).unwrap();
+  let added = ed.menu().get("adm").unwrap().clone();
+  assert_eq!(added.requirements().all(), ["admin".to_string()]);
+  assert!(added.visibility().is_some());
+
+  // Removing the item and undoing the removal puts back the same item
+  ed.apply(Command::Remove {
+    id: "adm".to_string(),
+    policy: Removal::Reject
+  })
+  .unwrap();
+  ed.undo().unwrap();
+  assert_eq!(ed.menu().get("adm"), Some(&added));
+
+  // Replaying the log adds the same item too
+  let log: Vec<Command<String>> = ed.log().into_iter().cloned().collect();
+  let mut copy = build_menu();
+  history::replay(&mut copy, log).unwrap();
+  assert_eq!(copy.get("adm"), Some(&added));
+}
+
+#[cfg(all(feature = "serde", feature = "json"))]
+#[test]
+fn replay_serialized_log() {
+  let mut ed = Editor::new(build_menu());
+  ed.apply(add("view", None, 15)).unwrap();
+  ed.apply(Command::Remove {
+    id: "file".to_string(),
+    policy: Removal::Reparent
+  })
+  .unwrap();
+
+  let s = serde_json::to_string(&ed.log()).unwrap();
+  let log: Vec<Command<String>> = serde_json::from_str(&s).unwrap();
+
+  let mut copy = build_menu();
+  history::replay(&mut copy, log).unwrap();
+  assert_eq!(dump(&copy), dump(ed.menu()));
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,155 @@
+#![cfg(feature = "json")]
+#![recursion_limit = "256"]
+
+use menugen::json::Exporter;
+use menugen::{menu, menuitem};
+use serde_json::json;
+
+#[derive(Clone, Default)]
+struct Context {
+  url: String
+}
+
+fn build_menu() -> menu::Menu<Context> {
+  let mut mb = menu::Builder::<Context>::new();
+  let mib = menuitem::Builder::new_ctx(
+    "lib",
+    "Library",
+    Context {
+      url: "/".to_string()
+    }
+  );
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx(
+    "lib-kb",
+    "Knowledge Base",
+    Context {
+      url: "/kb".to_string()
+    }
+  );
+  mib.parent("lib");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx(
+    "lib-kb-arch",
+    "Archive",
+    Context {
+      url: "/kb/archive".to_string()
+    }
+  );
+  mib.parent("lib-kb");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("home", "Home");
+  mib.weight(0).icon("house").tooltip("Start page");
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn tree() {
+  let menu = build_menu();
+  let mut exp = Exporter::new(&menu, |ctx| json!({ "url": ctx.url }));
+  exp.active("lib-kb");
+
+  let expect = json!([
+    {
+      "id": "home",
+      "title": "Home",
+      "kind": "link",
+      "icon": "house",
+      "tooltip": "Start page",
+      "description": null,
+      "badge": null,
+      "disabled": false,
+      "depth": 0,
+      "path": [],
+      "active": false,
+      "expanded": false,
+      "ctx": { "url": "" },
+      "children": []
+    },
+    {
+      "id": "lib",
+      "title": "Library",
+      "kind": "link",
+      "icon": null,
+      "tooltip": null,
+      "description": null,
+      "badge": null,
+      "disabled": false,
+      "depth": 0,
+      "path": [],
+      "active": false,
+      "expanded": true,
+      "ctx": { "url": "/" },
+      "children": [
+        {
+          "id": "lib-kb",
+          "title": "Knowledge Base",
+          "kind": "link",
+          "icon": null,
+          "tooltip": null,
+          "description": null,
+          "badge": null,
+          "disabled": false,
+          "depth": 1,
+          "path": ["lib"],
+          "active": true,
+          "expanded": true,
+          "ctx": { "url": "/kb" },
+          "children": [
+            {
+              "id": "lib-kb-arch",
+              "title": "Archive",
+              "kind": "link",
+              "icon": null,
+              "tooltip": null,
+              "description": null,
+              "badge": null,
+              "disabled": false,
+              "depth": 2,
+              "path": ["lib", "lib-kb"],
+              "active": false,
+              "expanded": false,
+              "ctx": { "url": "/kb/archive" },
+              "children": []
+            }
+          ]
+        }
+      ]
+    }
+  ]);
+
+  assert_eq!(exp.tree(), expect);
+}
+
+
+#[test]
+fn flat() {
+  let menu = build_menu();
+  let mut exp = Exporter::new(&menu, |_| serde_json::Value::Null);
+  exp.expand_all();
+
+  let v = exp.flat();
+
+  assert_eq!(v["roots"], json!(["home", "lib"]));
+  assert_eq!(v["nodes"].as_object().unwrap().len(), 4);
+
+  let kb = &v["nodes"]["lib-kb"];
+  assert_eq!(kb["parent"], json!("lib"));
+  assert_eq!(kb["children"], json!(["lib-kb-arch"]));
+  assert_eq!(kb["path"], json!(["lib"]));
+  assert_eq!(kb["expanded"], json!(true));
+  assert_eq!(kb["active"], json!(false));
+
+  assert_eq!(v["nodes"]["home"]["parent"], serde_json::Value::Null);
+  assert_eq!(v["nodes"]["home"]["expanded"], json!(false));
+  assert_eq!(v["nodes"]["home"]["icon"], json!("house"));
+  assert_eq!(kb["badge"], serde_json::Value::Null);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
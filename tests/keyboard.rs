@@ -0,0 +1,168 @@
+#![cfg(feature = "json")]
+
+use menugen::keyboard::{Builder, Callback, Error};
+use menugen::menuitem::Kind;
+use menugen::{menu, menuitem};
+use serde_json::json;
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("faq", "FAQ");
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("orders", "Orders");
+  mib.weight(20);
+  mb.add(mib);
+
+  for i in 1..=5 {
+    let mut mib =
+      menuitem::Builder::new(format!("orders-{}", i), format!("Order {}", i));
+    mib.parent("orders").weight(i);
+    mb.add(mib);
+  }
+
+  let mut mib = menuitem::Builder::new("orders-3-a-very-long-identifier", "X");
+  mib.parent("orders-3");
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn root_level() {
+  let menu = build_menu();
+  let mut kb = Builder::new(&menu);
+  kb.columns(2);
+
+  let keyboard = kb.level(None, 0).unwrap();
+  assert_eq!(
+    keyboard.to_json(),
+    json!({
+      "inline_keyboard": [[
+        { "text": "FAQ", "callback_data": "i:faq" },
+        { "text": "Orders", "callback_data": "i:orders" }
+      ]]
+    })
+  );
+}
+
+
+#[test]
+fn paginated_level() {
+  let menu = build_menu();
+  let mut kb = Builder::new(&menu);
+  kb.columns(2).page_size(4).prefix("m/");
+
+  let keyboard = kb.level(Some("orders"), 1).unwrap();
+  let payloads: Vec<Vec<&str>> = keyboard
+    .rows
+    .iter()
+    .map(|row| row.iter().map(|b| b.callback.as_str()).collect())
+    .collect();
+  assert_eq!(
+    payloads,
+    vec![vec!["m/i:orders-5"], vec!["m/l:0:orders"], vec!["m/l:0:"]]
+  );
+
+  match kb.decode("m/l:0:orders") {
+    Some(Callback::Level {
+      parent: Some(mi),
+      page: 0
+    }) => assert_eq!(mi.id(), "orders"),
+    _ => panic!("expected level callback")
+  }
+  match kb.decode("m/i:orders-5") {
+    Some(Callback::Item(mi)) => assert_eq!(mi.title(), "Order 5"),
+    _ => panic!("expected item callback")
+  }
+  assert!(matches!(
+    kb.decode("m/l:0:"),
+    Some(Callback::Level {
+      parent: None,
+      page: 0
+    })
+  ));
+  assert!(kb.decode("i:orders-5").is_none());
+  assert!(kb.decode("m/i:nope").is_none());
+
+  assert_eq!(
+    kb.level(Some("nope"), 0).unwrap_err(),
+    Error::UnknownItem("nope".to_string())
+  );
+}
+
+
+#[test]
+fn positional_payload() {
+  let menu = build_menu();
+  let mut kb = Builder::new(&menu);
+  kb.max_payload(16);
+
+  let keyboard = kb.level(Some("orders-3"), 0).unwrap();
+  let b = &keyboard.rows[0][0];
+  assert_eq!(b.callback, "I:1.2.0");
+  match kb.decode(&b.callback) {
+    Some(Callback::Item(mi)) => {
+      assert_eq!(mi.id(), "orders-3-a-very-long-identifier")
+    }
+    _ => panic!("expected item callback")
+  }
+
+  kb.max_payload(4);
+  assert!(matches!(
+    kb.level(Some("orders-3"), 0),
+    Err(Error::PayloadTooLong(_))
+  ));
+}
+
+
+#[test]
+fn unselectable_payloads() {
+  let mut mb = menu::Builder::<()>::new();
+  for (i, (id, kind)) in [
+    ("hdr", Kind::Header),
+    ("tools", Kind::Group),
+    ("sep", Kind::Separator),
+    ("quit", Kind::Link)
+  ]
+  .iter()
+  .enumerate()
+  {
+    let mut mib = menuitem::Builder::new(*id, *id);
+    mib.kind(*kind).weight(i as isize);
+    mb.add(mib);
+  }
+  let mut mib = menuitem::Builder::new("tools-saw", "Saw");
+  mib.parent("tools");
+  mb.add(mib);
+  let menu = mb.build();
+  let kb = Builder::new(&menu);
+
+  // Groups open their children instead of being selected
+  let keyboard = kb.level(None, 0).unwrap();
+  let payloads: Vec<&str> = keyboard
+    .rows
+    .iter()
+    .flatten()
+    .map(|b| &*b.callback)
+    .collect();
+  assert_eq!(payloads, ["l:0:tools", "i:quit"]);
+  assert!(matches!(
+    kb.decode("l:0:tools"),
+    Some(Callback::Level {
+      parent: Some(_),
+      page: 0
+    })
+  ));
+
+  // Forged or stale payloads for items that can not be selected
+  for payload in ["i:hdr", "i:tools", "i:sep", "I:1"] {
+    assert!(kb.decode(payload).is_none(), "{}", payload);
+  }
+  assert!(kb.decode("i:quit").is_some());
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
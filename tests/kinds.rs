@@ -0,0 +1,184 @@
+use menugen::menu::Visibility;
+use menugen::menuitem::{self, Kind};
+use menugen::session::Session;
+use menugen::{menu, recfiltiter, reciter};
+
+#[derive(Clone, Default)]
+struct Context {
+  hidden: bool
+}
+
+fn item(
+  id: &str,
+  parent: Option<&str>,
+  weight: isize,
+  kind: Kind,
+  hidden: bool
+) -> menuitem::Builder<Context> {
+  let mut mib = menuitem::Builder::new_ctx(id, id, Context { hidden });
+  mib.weight(weight).kind(kind);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  mib
+}
+
+/// File menu:
+///   new, open, ---, save, save-as (hidden), ---, ---, [recent],
+///   recent-1 (hidden), ---, [print] > print-now (hidden), ---, quit
+fn build_menu() -> menu::Menu<Context> {
+  let f = Some("file");
+  let mut mb = menu::Builder::new();
+  mb.add(item("file", None, 0, Kind::Link, false));
+  mb.add(item("new", f, 1, Kind::Link, false));
+  mb.add(item("open", f, 2, Kind::Link, false));
+  mb.add(item("sep-1", f, 3, Kind::Separator, false));
+  mb.add(item("save", f, 4, Kind::Link, false));
+  mb.add(item("save-as", f, 5, Kind::Link, true));
+  mb.add(item("sep-2", f, 6, Kind::Separator, false));
+  mb.add(item("sep-3", f, 7, Kind::Separator, false));
+  mb.add(item("recent", f, 8, Kind::Header, false));
+  mb.add(item("recent-1", f, 9, Kind::Link, true));
+  mb.add(item("sep-4", f, 10, Kind::Separator, false));
+  mb.add(item("print", f, 11, Kind::Group, false));
+  mb.add(item("print-now", Some("print"), 1, Kind::Link, true));
+  mb.add(item("sep-5", f, 12, Kind::Separator, false));
+  mb.add(item("quit", f, 13, Kind::Link, false));
+  mb.build()
+}
+
+fn visible(mi: &menuitem::MenuItem<Context>) -> bool {
+  !mi.appctx().hidden
+}
+
+fn ids<F>(it: recfiltiter::MenuIter<Context, F>) -> Vec<String>
+where
+  F: Fn(&menuitem::MenuItem<Context>) -> bool
+{
+  it.filter_map(|ev| match ev {
+    recfiltiter::Event::MenuItem(mi) => Some(mi.id().to_string()),
+    _ => None
+  })
+  .collect()
+}
+
+
+#[test]
+fn defaults() {
+  let mib = menuitem::Builder::<()>::new("a", "A");
+  let mut mb = menu::Builder::new();
+  mb.add(mib);
+  let menu = mb.build();
+  let mi = menu.get("a").unwrap();
+  assert_eq!(mi.kind(), Kind::Link);
+  assert!(mi.is_selectable());
+  assert_eq!(Kind::Separator.to_string(), "separator");
+}
+
+#[test]
+fn unfiltered() {
+  let menu = build_menu();
+  let ids: Vec<&str> = menu
+    .iter_hier()
+    .filter_map(|ev| match ev {
+      reciter::Event::MenuItem(mi) => Some(mi.item.id()),
+      _ => None
+    })
+    .collect();
+
+  // Only the double separator is dropped
+  assert_eq!(
+    ids,
+    [
+      "file",
+      "new",
+      "open",
+      "sep-1",
+      "save",
+      "save-as",
+      "sep-2",
+      "recent",
+      "recent-1",
+      "sep-4",
+      "print",
+      "print-now",
+      "sep-5",
+      "quit"
+    ]
+  );
+}
+
+#[test]
+fn filtered_iteration() {
+  let menu = build_menu();
+  assert_eq!(
+    ids(menu.filtiter_hier(visible)),
+    ["file", "new", "open", "sep-1", "save", "sep-2", "quit"]
+  );
+
+  let mut rit = menu.iter_hier();
+  rit.itemfilter(visible);
+  let n = rit
+    .filter(|ev| matches!(ev, reciter::Event::MenuItem(_)))
+    .count();
+  assert_eq!(n, 7);
+}
+
+#[test]
+fn leading_and_trailing() {
+  let mut mb = menu::Builder::new();
+  mb.add(item("sep-1", None, 1, Kind::Separator, false));
+  mb.add(item("a", None, 2, Kind::Link, true));
+  mb.add(item("sep-2", None, 3, Kind::Separator, false));
+  mb.add(item("b", None, 4, Kind::Link, false));
+  mb.add(item("sep-3", None, 5, Kind::Separator, false));
+  mb.add(item("hdr", None, 6, Kind::Header, false));
+  mb.add(item("c", None, 7, Kind::Link, true));
+  let menu = mb.build();
+
+  assert_eq!(ids(menu.filtiter_hier(visible)), ["b"]);
+  assert_eq!(
+    ids(menu.filtiter_hier(|_| true)),
+    ["a", "sep-2", "b", "sep-3", "hdr", "c"]
+  );
+}
+
+#[test]
+fn materialized_and_explained() {
+  let menu = build_menu();
+  let filtered = menu.filtered(visible);
+  let children: Vec<&str> = filtered
+    .get("file")
+    .unwrap()
+    .children()
+    .iter()
+    .map(|mi| mi.id())
+    .collect();
+  assert_eq!(children, ["new", "open", "sep-1", "save", "sep-2", "quit"]);
+
+  assert_eq!(menu.explain("sep-2", visible), Visibility::Visible);
+  assert_eq!(menu.explain("sep-3", visible), Visibility::Redundant);
+  assert_eq!(menu.explain("recent", visible), Visibility::Redundant);
+  assert_eq!(menu.explain("print", visible), Visibility::Redundant);
+  assert_eq!(menu.explain("save-as", visible), Visibility::Rejected);
+}
+
+#[test]
+fn session_skips_non_selectable() {
+  let menu = build_menu();
+  let mut sess = Session::new(&menu);
+  sess.input("1");
+  let titles: Vec<String> = sess
+    .render()
+    .choices
+    .into_iter()
+    .filter(|ch| ch.miid.is_some())
+    .map(|ch| ch.title)
+    .collect();
+  assert_eq!(
+    titles,
+    ["new", "open", "save", "save-as", "recent-1", "print", "quit"]
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,116 @@
+use std::rc::Rc;
+
+use menugen::reciter::Event;
+use menugen::{menu, menuitem};
+
+#[derive(Clone, Default)]
+struct Config {
+  url: String,
+  handle: Option<Rc<String>>
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+struct View {
+  url: String,
+  badge: usize
+}
+
+fn build_menu() -> menu::Menu<Config> {
+  let mut mb = menu::Builder::<Config>::new();
+
+  let mib = menuitem::Builder::new_ctx(
+    "inbox",
+    "Inbox",
+    Config {
+      url: "/inbox".to_string(),
+      handle: Some(Rc::new("db".to_string()))
+    }
+  );
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx(
+    "inbox-spam",
+    "Spam",
+    Config {
+      url: "/inbox/spam".to_string(),
+      handle: None
+    }
+  );
+  mib.parent("inbox").weight(5);
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn collect(menu: &menu::Menu<View>) -> Vec<(String, View)> {
+  let mut out = Vec::new();
+  for ev in menu.iter_hier() {
+    if let Event::MenuItem(mi) = ev {
+      out.push((mi.id().to_string(), mi.appctx().clone()));
+    }
+  }
+  out
+}
+
+
+#[test]
+fn map_ctx() {
+  let menu = build_menu();
+  let mut seen = Vec::new();
+  let mut view = menu.map_ctx(|mi| {
+    seen.push(mi.id().to_string());
+    View {
+      url: mi.appctx().url.clone(),
+      badge: 0
+    }
+  });
+  assert_eq!(seen, vec!["inbox", "inbox-spam"]);
+
+  view.for_each_mut(|mi| {
+    if mi.id() == "inbox-spam" {
+      mi.appctx_mut().badge = 3;
+    }
+  });
+
+  assert_eq!(
+    collect(&view),
+    vec![
+      (
+        "inbox".to_string(),
+        View {
+          url: "/inbox".to_string(),
+          badge: 0
+        }
+      ),
+      (
+        "inbox-spam".to_string(),
+        View {
+          url: "/inbox/spam".to_string(),
+          badge: 3
+        }
+      )
+    ]
+  );
+  assert_eq!(view.get("inbox-spam").unwrap().title(), "Spam");
+}
+
+
+#[test]
+fn try_map_ctx() {
+  let menu = build_menu();
+  let res = menu.try_map_ctx(|mi| match mi.appctx().handle {
+    Some(ref h) => Ok(View {
+      url: h.to_string(),
+      badge: 0
+    }),
+    None => Err(format!("{} has no handle", mi.id()))
+  });
+  assert_eq!(res.err(), Some("inbox-spam has no handle".to_string()));
+
+  let menu = build_menu();
+  let res: Result<menu::Menu<View>, ()> =
+    menu.try_map_ctx(|_| Ok(View::default()));
+  assert_eq!(res.unwrap().get_rootitems().len(), 1);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
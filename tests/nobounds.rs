@@ -0,0 +1,91 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use menugen::reciter::Event;
+use menugen::{menu, menuitem};
+
+/// A context which can neither be cloned nor defaulted.
+struct Action {
+  run: Box<dyn Fn() -> usize>
+}
+
+fn action(n: usize) -> Action {
+  Action {
+    run: Box::new(move || n)
+  }
+}
+
+fn build_menu() -> menu::Menu<Action> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new_ctx("file", "File", action(1));
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx("file-open", "Open", action(2));
+  mib.parent("file");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new_ctx("file-close", "Close", action(3));
+  mib.parent("file").weight(200);
+  mb.add(mib);
+
+  mb.add(menuitem::Builder::new_ctx("help", "Help", action(4)));
+
+  mb.build()
+}
+
+
+#[test]
+fn build_and_iterate() {
+  let menu = build_menu();
+
+  let mut ids = Vec::new();
+  let mut sum = 0;
+  for ev in menu.iter_hier() {
+    if let Event::MenuItem(mi) = ev {
+      ids.push(mi.id().to_string());
+      sum += (mi.appctx().run)();
+    }
+  }
+  assert_eq!(ids, ["file", "file-open", "file-close", "help"]);
+  assert_eq!(sum, 10);
+}
+
+
+#[test]
+fn filter_and_lookup() {
+  let menu = build_menu();
+
+  let ids: Vec<&str> = menu
+    .iter_root()
+    .filter(|mi| (mi.appctx().run)() > 1)
+    .map(|mi| mi.id())
+    .collect();
+  assert_eq!(ids, ["help"]);
+
+  let path: Vec<&str> = menu
+    .path_to("file-close")
+    .unwrap()
+    .iter()
+    .map(|mi| mi.id())
+    .collect();
+  assert_eq!(path, ["file", "file-close"]);
+}
+
+
+#[test]
+fn map_shared_handle() {
+  let calls = Rc::new(Cell::new(0));
+  let menu = build_menu();
+
+  let c = Rc::clone(&calls);
+  let menu = menu.map_ctx(move |mi| {
+    c.set(c.get() + 1);
+    (mi.appctx().run)() * 10
+  });
+  assert_eq!(calls.get(), 4);
+  assert_eq!(*menu.get("file-close").unwrap().appctx(), 30);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
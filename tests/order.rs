@@ -0,0 +1,228 @@
+use std::cmp::Ordering;
+
+use menugen::edit::Removal;
+use menugen::history::{self, Command, Editor};
+use menugen::order::{natural_cmp, Collation, Order};
+use menugen::patch::Patch;
+use menugen::{menu, menuitem};
+
+fn build(order: Order<u32>, titles: &[&str]) -> menu::Menu<u32> {
+  let mut mb = menu::Builder::new();
+  mb.order(order);
+  for (i, title) in titles.iter().enumerate() {
+    mb.add(menuitem::Builder::new_ctx(
+      format!("item-{}", i),
+      title,
+      i as u32
+    ));
+  }
+  mb.build()
+}
+
+fn titles<C>(menu: &menu::Menu<C>) -> Vec<&str> {
+  menu.get_rootitems().iter().map(|mi| mi.title()).collect()
+}
+
+
+#[test]
+fn default_is_bytewise() {
+  let menu = build(Order::Weight, &["apple", "Zebra", "Item 10", "Item 2"]);
+  assert_eq!(titles(&menu), ["Item 10", "Item 2", "Zebra", "apple"]);
+}
+
+#[test]
+fn natural() {
+  let menu = build(
+    Order::Natural,
+    &["apple", "Zebra", "Item 10", "Item 2", "item 1", "Item 02b"]
+  );
+  assert_eq!(
+    titles(&menu),
+    ["apple", "item 1", "Item 2", "Item 02b", "Item 10", "Zebra"]
+  );
+
+  assert_eq!(natural_cmp("a2", "a02"), Ordering::Greater);
+  assert_eq!(natural_cmp("A", "a"), Ordering::Less);
+  assert_eq!(natural_cmp("x9y", "x10"), Ordering::Less);
+}
+
+#[test]
+fn declaration() {
+  let mut mb = menu::Builder::<()>::new();
+  mb.order(Order::Declaration);
+  for id in ["c", "a", "d", "b"] {
+    mb.add(menuitem::Builder::new(id, id.to_uppercase()));
+  }
+  let mut mib = menuitem::Builder::new("e", "E");
+  mib.weight(0);
+  mb.add(mib);
+  let mut menu = mb.build();
+  assert_eq!(titles(&menu), ["E", "C", "A", "D", "B"]);
+
+  // Items inserted later go last
+  menu.insert(menuitem::Builder::new("0", "0")).unwrap();
+  assert_eq!(titles(&menu), ["E", "C", "A", "D", "B", "0"]);
+}
+
+fn declared(ids: &[&str]) -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+  mb.order(Order::Declaration);
+  for id in ids {
+    mb.add(menuitem::Builder::new(*id, *id));
+  }
+  mb.build()
+}
+
+fn add(id: &str) -> Command<()> {
+  let mut mib = menuitem::Builder::new(id, id);
+  mib.weight(100);
+  Command::add(mib)
+}
+
+#[test]
+fn declaration_edit_paths() {
+  // Items put back using insert_item() keep their position
+  let mut menu = declared(&["b", "c"]);
+  menu.insert(menuitem::Builder::new("a", "a")).unwrap();
+  let mi = menu.remove("b", Removal::Reject).unwrap();
+  menu.insert_item(None, mi).unwrap();
+  assert_eq!(titles(&menu), ["b", "c", "a"]);
+
+  // Editor commands, and undo and redo
+  let mut ed = Editor::new(declared(&["b", "c"]));
+  ed.apply(add("a")).unwrap();
+  assert_eq!(titles(ed.menu()), ["b", "c", "a"]);
+  ed.undo().unwrap();
+  ed.redo().unwrap();
+  assert_eq!(titles(ed.menu()), ["b", "c", "a"]);
+
+  // Removed items keep their position when put back
+  ed.apply(Command::Remove {
+    id: "b".to_string(),
+    policy: Removal::Reject
+  })
+  .unwrap();
+  assert_eq!(titles(ed.menu()), ["c", "a"]);
+  ed.undo().unwrap();
+  assert_eq!(titles(ed.menu()), ["b", "c", "a"]);
+
+  // Replayed commands
+  let mut menu = declared(&["b", "c"]);
+  history::replay(&mut menu, vec![add("a"), add("0")]).unwrap();
+  assert_eq!(titles(&menu), ["b", "c", "a", "0"]);
+
+  // Patches
+  let mut menu = declared(&["b", "c"]);
+  let mut patch = Patch::new();
+  patch.ops.push(add("a"));
+  patch.apply(&mut menu).unwrap();
+  assert_eq!(titles(&menu), ["b", "c", "a"]);
+
+  let mut mb = menu::Builder::new();
+  mb.order(Order::Declaration);
+  mb.add(menuitem::Builder::new("b", "b"));
+  let mut patch = Patch::new();
+  patch.ops.push(add("a"));
+  patch.apply_to_builder(&mut mb).unwrap();
+  assert_eq!(titles(&mb.build()), ["b", "a"]);
+}
+
+#[test]
+fn set_order_after_map_ctx() {
+  let mut mb = menu::Builder::<u32>::new();
+  mb.order(Order::custom(|a: &menuitem::MenuItem<u32>, b| {
+    b.appctx().cmp(a.appctx())
+  }));
+  for (id, n) in [("a", 1), ("b", 3), ("c", 2)] {
+    mb.add(menuitem::Builder::new_ctx(id, id, n));
+  }
+  let menu = mb.build();
+  assert_eq!(titles(&menu), ["b", "c", "a"]);
+
+  // The custom comparison can not be carried over to the new context type
+  let mut menu = menu.map_ctx(|mi| mi.appctx().to_string());
+  menu.insert(menuitem::Builder::new("0", "0")).unwrap();
+  assert_eq!(titles(&menu), ["0", "a", "b", "c"]);
+
+  menu.set_order(Order::custom(|a: &menuitem::MenuItem<String>, b| {
+    b.appctx().cmp(a.appctx())
+  }));
+  assert_eq!(titles(&menu), ["b", "c", "a", "0"]);
+}
+
+#[test]
+fn swedish_collation() {
+  let words = ["Öl", "ägg", "Zon", "åsna", "apa", "Ära", "ost", "éclair"];
+
+  let menu = build(Order::collate("sv-SE"), &words);
+  assert_eq!(
+    titles(&menu),
+    ["apa", "éclair", "ost", "Zon", "åsna", "ägg", "Ära", "Öl"]
+  );
+
+  // Without tailoring, accented letters sort as their base letters
+  let menu = build(Order::collate("de"), &words);
+  assert_eq!(
+    titles(&menu),
+    ["ägg", "apa", "Ära", "åsna", "éclair", "Öl", "ost", "Zon"]
+  );
+}
+
+#[test]
+fn collation_levels() {
+  let coll = Collation::new("en");
+  assert_eq!(coll.compare("resume", "résumé"), Ordering::Less);
+  assert_eq!(coll.compare("résumé", "resumes"), Ordering::Less);
+  assert_eq!(coll.compare("abc", "ABC"), Ordering::Less);
+  assert_eq!(coll.compare("Straße", "strasse"), Ordering::Greater);
+
+  let coll = Collation::new("da");
+  assert_eq!(coll.compare("ø", "å"), Ordering::Less);
+  assert_eq!(coll.compare("æ", "z"), Ordering::Greater);
+
+  // Tailored letters sort between "z" and the characters that follow it
+  let coll = Collation::new("sv");
+  for c in ["{", "|", "}", "~"].iter() {
+    assert_eq!(coll.compare("ö", c), Ordering::Less, "{}", c);
+    assert_eq!(coll.compare("å", c), Ordering::Less, "{}", c);
+  }
+  assert_eq!(coll.compare("|a", "åa"), Ordering::Greater);
+}
+
+#[test]
+fn custom_and_collate_with() {
+  // Order by context, descending
+  let order =
+    Order::custom(|a: &menuitem::MenuItem<u32>, b| b.appctx().cmp(a.appctx()));
+  let menu = build(order, &["a", "b", "c"]);
+  assert_eq!(titles(&menu), ["c", "b", "a"]);
+
+  let order = Order::collate_with(|a: &str, b: &str| a.len().cmp(&b.len()));
+  let menu = build(order, &["ccc", "a", "bb"]);
+  assert_eq!(titles(&menu), ["a", "bb", "ccc"]);
+}
+
+#[test]
+fn id_tiebreak() {
+  let order = Order::custom(|_: &menuitem::MenuItem<u32>, _| Ordering::Equal);
+  let menu = build(order, &["x", "y", "z", "w"]);
+  let ids: Vec<&str> = menu.get_rootitems().iter().map(|mi| mi.id()).collect();
+  assert_eq!(ids, ["item-0", "item-1", "item-2", "item-3"]);
+}
+
+#[test]
+fn edits_use_order() {
+  let mut menu = build(Order::Natural, &["Item 1", "Item 3"]);
+  menu
+    .insert(menuitem::Builder::new_ctx("x", "Item 20", 0))
+    .unwrap();
+  menu
+    .insert(menuitem::Builder::new_ctx("y", "item 2", 0))
+    .unwrap();
+  assert_eq!(titles(&menu), ["Item 1", "item 2", "Item 3", "Item 20"]);
+
+  menu.set_title("item-0", "Item 100").unwrap();
+  assert_eq!(titles(&menu), ["item 2", "Item 3", "Item 20", "Item 100"]);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
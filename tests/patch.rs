@@ -0,0 +1,385 @@
+use menugen::accel::Accel;
+use menugen::edit::{Error, Removal};
+use menugen::history::{Command, Editor};
+use menugen::menuitem::Kind;
+use menugen::patch::{self, Conflict, Patch, Side};
+use menugen::{menu, menuitem};
+
+fn item(
+  id: &str,
+  title: &str,
+  parent: Option<&str>,
+  weight: isize
+) -> menuitem::Builder<String> {
+  let mut mib = menuitem::Builder::new_ctx(id, title, format!("/{}", id));
+  mib.weight(weight);
+  if let Some(parent) = parent {
+    mib.parent(parent);
+  }
+  mib
+}
+
+fn base_builder() -> menu::Builder<String> {
+  let mut mb = menu::Builder::new();
+  mb.add(item("file", "File", None, 10));
+  mb.add(item("file-new", "New", Some("file"), 10));
+  mb.add(item("file-open", "Open", Some("file"), 20));
+  mb.add(item("file-quit", "Quit", Some("file"), 30));
+  mb.add(item("edit", "Edit", None, 20));
+  mb.add(item("edit-copy", "Copy", Some("edit"), 10));
+  mb.add(item("help", "Help", None, 30));
+  mb
+}
+
+fn base() -> menu::Menu<String> {
+  base_builder().build()
+}
+
+/// Render the menu as a compact string, for comparisons.
+fn dump(menu: &menu::Menu<String>) -> String {
+  fn rec(lst: &[menuitem::MenuItem<String>], out: &mut Vec<String>) {
+    for mi in lst {
+      let mut s = format!("{}:{}:{}", mi.id(), mi.title(), mi.weight());
+      if !mi.children().is_empty() {
+        let mut sub = Vec::new();
+        rec(mi.children(), &mut sub);
+        s = format!("{}[{}]", s, sub.join(","));
+      }
+      out.push(s);
+    }
+  }
+  let mut out = Vec::new();
+  rec(menu.get_rootitems(), &mut out);
+  out.join(",")
+}
+
+fn modified() -> menu::Menu<String> {
+  let mut m = base();
+  let mut view = item("view", "View", None, 15);
+  view.require_any(vec!["viewer"]);
+  m.insert(view).unwrap();
+  m.insert(item("view-zoom", "Zoom", Some("view"), 10))
+    .unwrap();
+  m.move_item("edit-copy", Some("view")).unwrap();
+  m.remove("help", Removal::Reject).unwrap();
+  m.remove("file-quit", Removal::Reject).unwrap();
+  m.set_title("file", "Files").unwrap();
+  m.set_weight("file-new", 50).unwrap();
+  m
+}
+
+
+#[test]
+fn between_and_apply() {
+  let new = modified();
+  let patch = Patch::between(&base(), &new);
+
+  let mut m = base();
+  patch.clone().apply(&mut m).unwrap();
+  assert_eq!(dump(&m), dump(&new));
+  assert_eq!(m.get("view-zoom").unwrap().appctx(), "/view-zoom");
+  // Added items are copied in full
+  assert_eq!(m.get("view"), new.get("view"));
+
+  let mut mb = base_builder();
+  patch.apply_to_builder(&mut mb).unwrap();
+  let m = mb.build();
+  assert_eq!(dump(&m), dump(&new));
+  assert_eq!(m.get("view"), new.get("view"));
+
+  assert!(Patch::between(&new, &new).is_empty());
+}
+
+#[test]
+fn builder_ops() {
+  let mut mb = base_builder();
+  let patch = Patch {
+    ops: vec![
+      Command::Remove {
+        id: "file".to_string(),
+        policy: Removal::Reparent
+      },
+      Command::Remove {
+        id: "edit".to_string(),
+        policy: Removal::Recursive
+      },
+      Command::SetContext {
+        id: "help".to_string(),
+        ctx: "/support".to_string()
+      },
+    ]
+  };
+  patch.apply_to_builder(&mut mb).unwrap();
+  let m = mb.build();
+  assert_eq!(
+    dump(&m),
+    "file-new:New:10,file-open:Open:20,help:Help:30,file-quit:Quit:30"
+  );
+  assert_eq!(m.get("help").unwrap().appctx(), "/support");
+
+  // Items are added along with their children
+  let mut mb = base_builder();
+  let edit = base().remove("edit", Removal::Recursive).unwrap();
+  Patch {
+    ops: vec![
+      Command::Remove {
+        id: "edit".to_string(),
+        policy: Removal::Recursive
+      },
+      Command::Add {
+        parent: Some("help".to_string()),
+        item: Box::new(edit)
+      },
+    ]
+  }
+  .apply_to_builder(&mut mb)
+  .unwrap();
+  assert_eq!(
+    dump(&mb.build()),
+    "file:File:10[file-new:New:10,file-open:Open:20,file-quit:Quit:30],help:\
+     Help:30[edit:Edit:20[edit-copy:Copy:10]]"
+  );
+}
+
+#[test]
+fn builder_errors() {
+  let apply = |op: Command<String>| {
+    Patch { ops: vec![op] }.apply_to_builder(&mut base_builder())
+  };
+
+  assert_eq!(
+    apply(Command::Move {
+      id: "file".to_string(),
+      parent: Some("file-open".to_string())
+    }),
+    Err(Error::Cycle("file".to_string()))
+  );
+  assert_eq!(
+    apply(Command::Remove {
+      id: "file".to_string(),
+      policy: Removal::Reject
+    }),
+    Err(Error::HasChildren("file".to_string()))
+  );
+  assert_eq!(
+    apply(Command::add(item("x", "X", Some("nope"), 0))),
+    Err(Error::UnknownParent("nope".to_string()))
+  );
+  assert_eq!(
+    apply(Command::Retitle {
+      id: "nope".to_string(),
+      title: "X".to_string()
+    }),
+    Err(Error::UnknownItem("nope".to_string()))
+  );
+}
+
+#[test]
+fn merge_clean() {
+  // Customer customizations
+  let mut ours = base();
+  ours.set_title("help", "Support").unwrap();
+  ours
+    .insert(item("file-export", "Export", Some("file"), 25))
+    .unwrap();
+  ours.move_item("edit-copy", None).unwrap();
+
+  // New default menu
+  let mut theirs = base();
+  theirs.insert(item("view", "View", None, 15)).unwrap();
+  theirs.set_weight("help", 99).unwrap();
+  theirs.set_title("edit-copy", "Copy text").unwrap();
+  theirs.remove("file-new", Removal::Reject).unwrap();
+
+  let m = patch::merge(&base(), &ours, &theirs);
+  assert!(m.is_clean());
+
+  let mut merged = ours;
+  m.patch.apply(&mut merged).unwrap();
+  assert_eq!(
+    dump(&merged),
+    concat!(
+      "edit-copy:Copy text:10,",
+      "file:File:10[",
+      "file-open:Open:20,file-export:Export:25,file-quit:Quit:30",
+      "],",
+      "view:View:15,edit:Edit:20,help:Support:99"
+    )
+  );
+}
+
+#[test]
+fn merge_conflicts() {
+  let mut ours = base();
+  ours.move_item("file-open", Some("edit")).unwrap();
+  ours.set_title("file", "Files").unwrap();
+  ours.set_weight("help", 5).unwrap();
+  ours
+    .insert(item("edit-paste", "Paste", Some("edit"), 20))
+    .unwrap();
+  ours.remove("file-quit", Removal::Reject).unwrap();
+
+  let mut theirs = base();
+  theirs.move_item("file-open", None).unwrap();
+  theirs.set_title("file", "Document").unwrap();
+  theirs.remove("help", Removal::Reject).unwrap();
+  theirs.remove("edit", Removal::Recursive).unwrap();
+  theirs.set_weight("file-quit", 90).unwrap();
+
+  let m = patch::merge(&base(), &ours, &theirs);
+  let mut conflicts = m.conflicts.clone();
+  conflicts.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+  assert_eq!(
+    conflicts,
+    vec![
+      Conflict::Parent {
+        id: "file-open".to_string(),
+        ours: Some("edit".to_string()),
+        theirs: None
+      },
+      Conflict::Removed {
+        id: "edit".to_string(),
+        by: Side::Theirs
+      },
+      Conflict::Removed {
+        id: "file-quit".to_string(),
+        by: Side::Ours
+      },
+      Conflict::Removed {
+        id: "help".to_string(),
+        by: Side::Theirs
+      },
+      Conflict::Title {
+        id: "file".to_string(),
+        ours: "Files".to_string(),
+        theirs: "Document".to_string()
+      },
+    ]
+  );
+
+  // All conflicts are resolved in our favour, but the removal of the
+  // unmodified "edit-copy" is carried over.
+  let mut merged = ours;
+  m.patch.apply(&mut merged).unwrap();
+  assert_eq!(
+    dump(&merged),
+    concat!(
+      "help:Help:5,",
+      "file:Files:10[file-new:New:10],",
+      "edit:Edit:20[file-open:Open:20,edit-paste:Paste:20]"
+    )
+  );
+}
+
+#[test]
+fn merge_properties() {
+  let mut ours = base();
+  ours.get_mut("help").unwrap().presentation_mut().icon =
+    Some("question".to_string());
+  let mut ed = Editor::new(ours);
+  ed.apply(Command::SetKind {
+    id: "edit".to_string(),
+    kind: Kind::Group
+  })
+  .unwrap();
+  ed.apply(Command::SetAccel {
+    id: "file-open".to_string(),
+    accel: Some(Accel::parse("Ctrl+O").unwrap())
+  })
+  .unwrap();
+  let ours = ed.into_menu();
+
+  let mut theirs = Editor::new(base());
+  theirs
+    .apply(Command::SetAccel {
+      id: "file-open".to_string(),
+      accel: Some(Accel::parse("Ctrl+Shift+O").unwrap())
+    })
+    .unwrap();
+  theirs
+    .apply(Command::SetAccel {
+      id: "file-quit".to_string(),
+      accel: Some(Accel::parse("Ctrl+Q").unwrap())
+    })
+    .unwrap();
+  theirs
+    .apply(Command::SetKind {
+      id: "help".to_string(),
+      kind: Kind::Header
+    })
+    .unwrap();
+  let theirs = theirs.into_menu();
+
+  let m = patch::merge(&base(), &ours, &theirs);
+  assert_eq!(
+    m.conflicts,
+    vec![Conflict::Accel {
+      id: "file-open".to_string(),
+      ours: Some(Accel::parse("Ctrl+O").unwrap()),
+      theirs: Some(Accel::parse("Ctrl+Shift+O").unwrap())
+    }]
+  );
+
+  let mut merged = ours;
+  m.patch.apply(&mut merged).unwrap();
+  let accel = |id: &str| merged.get(id).unwrap().accel().map(Accel::to_string);
+  assert_eq!(accel("file-open").as_deref(), Some("Ctrl+O"));
+  assert_eq!(accel("file-quit").as_deref(), Some("Ctrl+Q"));
+  let help = merged.get("help").unwrap();
+  assert_eq!(help.kind(), Kind::Header);
+  assert_eq!(help.presentation().icon.as_deref(), Some("question"));
+  assert_eq!(merged.get("edit").unwrap().kind(), Kind::Group);
+
+  // The same changes can be made to a builder
+  let patch = Patch::between(&base(), &merged);
+  let mut mb = base_builder();
+  patch.apply_to_builder(&mut mb).unwrap();
+  assert_eq!(mb.build().get("help"), merged.get("help"));
+}
+
+#[test]
+fn merge_missing_parent_and_cycle() {
+  let mut ours = base();
+  ours.remove("help", Removal::Reject).unwrap();
+  ours.move_item("edit", Some("file-new")).unwrap();
+
+  let mut theirs = base();
+  theirs
+    .insert(item("help-about", "About", Some("help"), 10))
+    .unwrap();
+  theirs.move_item("file", Some("edit-copy")).unwrap();
+
+  let m = patch::merge(&base(), &ours, &theirs);
+  assert_eq!(
+    m.conflicts,
+    vec![
+      Conflict::MissingParent {
+        id: "help-about".to_string(),
+        parent: "help".to_string()
+      },
+      Conflict::Cycle {
+        id: "edit".to_string()
+      },
+    ]
+  );
+
+  let mut merged = ours;
+  let before = dump(&merged);
+  m.patch.apply(&mut merged).unwrap();
+  assert_eq!(dump(&merged), before);
+}
+
+#[cfg(all(feature = "serde", feature = "json"))]
+#[test]
+fn patch_serde() {
+  let patch = Patch::between(&base(), &modified());
+  let s = serde_json::to_string(&patch).unwrap();
+  let patch2: Patch<String> = serde_json::from_str(&s).unwrap();
+  assert_eq!(patch, patch2);
+
+  let mut m = base();
+  patch2.apply(&mut m).unwrap();
+  assert_eq!(dump(&m), dump(&modified()));
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
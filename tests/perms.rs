@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use menugen::recfiltiter::Event;
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("adm", "Administration");
+  mib.require_any(vec!["admin", "operator"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-users", "Users");
+  mib.parent("adm").require_all(vec!["admin", "users"]);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-status", "Status");
+  mib.parent("adm");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-help", "Help");
+  mib.parent("adm").inherit_requirements(false);
+  mb.add(mib);
+
+  let mib = menuitem::Builder::new("home", "Home");
+  mb.add(mib);
+
+  mb.build()
+}
+
+fn visible(menu: &menu::Menu<()>, perms: &[&str]) -> Vec<String> {
+  let principal: HashSet<String> =
+    perms.iter().map(|s| s.to_string()).collect();
+  let mut out = Vec::new();
+  for ev in menu.for_principal(&principal) {
+    match ev {
+      Event::MenuItem(mi) | Event::Container(mi) => {
+        out.push(mi.id().to_string())
+      }
+      _ => {}
+    }
+  }
+  out
+}
+
+fn containers(menu: &menu::Menu<()>, perms: &[&str]) -> Vec<String> {
+  let principal: HashSet<String> =
+    perms.iter().map(|s| s.to_string()).collect();
+  let mut out = Vec::new();
+  for ev in menu.for_principal(&principal) {
+    if let Event::Container(mi) = ev {
+      out.push(mi.id().to_string());
+    }
+  }
+  out
+}
+
+
+#[test]
+fn for_principal() {
+  let menu = build_menu();
+
+  // "adm-help" does not inherit the requirements of "adm", so "adm" is
+  // yielded as a container for it, but not for its other children
+  let principal: &[&str] = &[];
+  assert_eq!(visible(&menu, principal), vec!["adm", "adm-help", "home"]);
+  assert!(!menu.permits("adm", principal));
+  assert!(menu.permits("adm-help", principal));
+  assert_eq!(
+    visible(&menu, &["operator"]),
+    vec!["adm", "adm-help", "adm-status", "home"]
+  );
+  assert_eq!(
+    visible(&menu, &["admin", "users"]),
+    vec!["adm", "adm-help", "adm-status", "adm-users", "home"]
+  );
+}
+
+
+#[test]
+fn forbidden_container() {
+  let menu = build_menu();
+
+  // "adm" is only shown to lead to "adm-help", so it's not reported as an
+  // ordinary menu item
+  assert_eq!(containers(&menu, &[]), vec!["adm"]);
+  let principal: HashSet<String> = HashSet::new();
+  let mut items = Vec::new();
+  for ev in menu.for_principal(&principal) {
+    if let Event::MenuItem(mi) = ev {
+      items.push(mi.item.id());
+    }
+  }
+  assert_eq!(items, vec!["adm-help", "home"]);
+
+  assert!(containers(&menu, &["operator"]).is_empty());
+}
+
+
+#[test]
+fn permits() {
+  let menu = build_menu();
+
+  let principal: &[&str] = &["users"];
+  assert!(menu.permits("home", principal));
+  assert!(!menu.permits("adm", principal));
+  assert!(!menu.permits("adm-status", principal));
+  assert!(menu.permits("adm-help", principal));
+  assert!(!menu.permits("nonexistent", principal));
+
+  let principal = |perm: &str| perm == "admin" || perm == "users";
+  assert!(menu.permits("adm-users", &principal));
+
+  // Every item yielded for a principal is either accessible, or leads to
+  // an accessible item
+  for perms in [&[][..], &["users"], &["operator"], &["admin", "users"]] {
+    let ids = visible(&menu, perms);
+    for id in &ids {
+      let leads = menu.get(id).unwrap().children().iter().any(|mi| {
+        ids.contains(&mi.id().to_string()) && menu.permits(mi.id(), perms)
+      });
+      assert!(menu.permits(id, perms) || leads, "{}", id);
+    }
+  }
+
+  let reqs = menu.get("adm-users").unwrap().requirements();
+  assert!(reqs.any().is_empty());
+  assert_eq!(reqs.all(), ["admin".to_string(), "users".to_string()]);
+  assert!(reqs.inherits());
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
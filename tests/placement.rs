@@ -0,0 +1,203 @@
+use menugen::menu::{self, Error};
+use menugen::menuitem;
+
+fn ids(lst: &[menuitem::MenuItem<()>]) -> Vec<&str> {
+  lst.iter().map(|mi| mi.id()).collect()
+}
+
+fn lib_builder() -> menu::Builder<()> {
+  let mut mb = menu::Builder::new();
+  mb.add(menuitem::Builder::new("lib", "Library"));
+
+  let mut mib = menuitem::Builder::new("lib-docs", "Documents");
+  mib.parent("lib").weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb", "Knowledge Base");
+  mib.parent("lib").weight(20);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-faq", "FAQ");
+  mib.parent("lib").weight(30);
+  mb.add(mib);
+
+  mb
+}
+
+
+#[test]
+fn before_after() {
+  let mut mb = lib_builder();
+
+  // Plugin items which do not know the weights of the items they refer to
+  let mut mib = menuitem::Builder::new("plugin-wiki", "Wiki");
+  mib.parent("lib").weight(1000).after("lib-kb");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("plugin-news", "News");
+  mib.parent("lib").before("lib-docs");
+  mb.add(mib);
+
+  let menu = mb.try_build().unwrap();
+  assert_eq!(
+    ids(menu.get("lib").unwrap().children()),
+    [
+      "plugin-news",
+      "lib-docs",
+      "lib-kb",
+      "plugin-wiki",
+      "lib-faq"
+    ]
+  );
+}
+
+#[test]
+fn first_last() {
+  let mut mb = lib_builder();
+
+  let mut mib = menuitem::Builder::new("lib-help", "Help");
+  mib.parent("lib").weight(-100).last();
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-about", "About");
+  mib.parent("lib").weight(-50).last();
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-home", "Home");
+  mib.parent("lib").weight(500).first();
+  mb.add(mib);
+
+  // Constraints among items that are pinned to the same end still apply
+  let mut mib = menuitem::Builder::new("lib-start", "Start");
+  mib.parent("lib").first().after("lib-home");
+  mb.add(mib);
+
+  let menu = mb.try_build().unwrap();
+  assert_eq!(
+    ids(menu.get("lib").unwrap().children()),
+    [
+      "lib-home",
+      "lib-start",
+      "lib-docs",
+      "lib-kb",
+      "lib-faq",
+      "lib-help",
+      "lib-about"
+    ]
+  );
+}
+
+#[test]
+fn unknown_and_non_sibling_targets_ignored() {
+  let mut mb = lib_builder();
+
+  let mut mib = menuitem::Builder::new("news", "News");
+  mib.before("lib-kb").after("nope").weight(-1);
+  mb.add(mib);
+
+  let menu = mb.try_build().unwrap();
+  assert_eq!(ids(menu.get_rootitems()), ["news", "lib"]);
+}
+
+#[test]
+fn contradiction() {
+  let mut mb = lib_builder();
+
+  let mut mib = menuitem::Builder::new("lib-a", "A");
+  mib.parent("lib").before("lib-b");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-b", "B");
+  mib.parent("lib").before("lib-a");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-c", "C");
+  mib.parent("lib").first().after("lib-faq");
+  mb.add(mib);
+
+  // Every list of siblings with contradictions is reported
+  let mut mib = menuitem::Builder::new("x", "X");
+  mib.after("y");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("y", "Y");
+  mib.after("x");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb-a", "A");
+  mib.parent("lib-kb").first();
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb-b", "B");
+  mib.parent("lib-kb").last().before("lib-kb-a");
+  mb.add(mib);
+
+  let errs = mb.try_build().unwrap_err();
+  assert_eq!(
+    errs,
+    [
+      Error::Contradiction {
+        parent: None,
+        ids: vec!["x".to_string(), "y".to_string()]
+      },
+      Error::Contradiction {
+        parent: Some("lib".to_string()),
+        ids: vec![
+          "lib-faq".to_string(),
+          "lib-a".to_string(),
+          "lib-b".to_string(),
+          "lib-c".to_string()
+        ]
+      },
+      Error::Contradiction {
+        parent: Some("lib-kb".to_string()),
+        ids: vec!["lib-kb-a".to_string(), "lib-kb-b".to_string()]
+      }
+    ]
+  );
+}
+
+#[test]
+fn contradiction_falls_back_to_weight() {
+  let mut mb = menu::Builder::<()>::new();
+
+  let mut mib = menuitem::Builder::new("b", "B");
+  mib.weight(2).before("a");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("a", "A");
+  mib.weight(1).before("b");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("c", "C");
+  mib.weight(3);
+  mb.add(mib);
+
+  let menu = mb.build();
+  assert_eq!(ids(menu.get_rootitems()), ["a", "b", "c"]);
+}
+
+#[test]
+fn edits_respect_constraints() {
+  let mut mb = lib_builder();
+  let mut mib = menuitem::Builder::new("lib-new", "New");
+  mib.parent("lib").first();
+  mb.add(mib);
+  let mut menu = mb.try_build().unwrap();
+
+  menu.set_weight("lib-faq", -10).unwrap();
+  assert_eq!(
+    ids(menu.get("lib").unwrap().children()),
+    ["lib-new", "lib-faq", "lib-docs", "lib-kb"]
+  );
+
+  let mut mib = menuitem::Builder::new("lib-x", "X");
+  mib.parent("lib").after("lib-kb").weight(-100);
+  menu.insert(mib).unwrap();
+  assert_eq!(
+    ids(menu.get("lib").unwrap().children()),
+    ["lib-new", "lib-faq", "lib-docs", "lib-kb", "lib-x"]
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
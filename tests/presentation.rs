@@ -0,0 +1,99 @@
+use menugen::menuitem::Presentation;
+use menugen::session::{Outcome, Session};
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("inbox", "Inbox");
+  mib
+    .weight(10)
+    .icon("mail")
+    .tooltip("Received messages")
+    .description("Messages sent to you")
+    .badge(3);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("archive", "Archive");
+  mib.weight(20).disabled(true);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("sent", "Sent");
+  mib.weight(30);
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn builder() {
+  let menu = build_menu();
+
+  let inbox = menu.get("inbox").unwrap();
+  assert_eq!(
+    inbox.presentation(),
+    &Presentation {
+      icon: Some("mail".to_string()),
+      tooltip: Some("Received messages".to_string()),
+      description: Some("Messages sent to you".to_string()),
+      badge: Some("3".to_string()),
+      disabled: false
+    }
+  );
+  assert!(inbox.is_selectable());
+
+  let archive = menu.get("archive").unwrap();
+  assert!(archive.is_disabled());
+  assert!(!archive.is_selectable());
+  assert_eq!(
+    menu.get("sent").unwrap().presentation(),
+    &Presentation::default()
+  );
+}
+
+#[test]
+fn update_badge() {
+  let mut menu = build_menu();
+  menu.get_mut("inbox").unwrap().presentation_mut().badge = None;
+  menu.get_mut("sent").unwrap().presentation_mut().badge =
+    Some("new".to_string());
+
+  let sess = Session::new(&menu);
+  assert_eq!(
+    sess.render().to_string(),
+    "1. Inbox\nArchive\n2. Sent (new)"
+  );
+}
+
+#[test]
+fn session() {
+  let menu = build_menu();
+  let mut sess = Session::new(&menu);
+
+  // Disabled items are shown, but are not offered as choices
+  assert_eq!(sess.render().to_string(), "1. Inbox (3)\nArchive\n2. Sent");
+  match sess.input("2") {
+    Outcome::Selected(mi) => assert_eq!(mi.id(), "sent"),
+    _ => panic!("expected sent to be selected")
+  }
+  assert!(matches!(sess.input("3"), Outcome::Invalid));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn keyboard() {
+  use menugen::keyboard::Builder;
+
+  let menu = build_menu();
+  let builder = Builder::new(&menu);
+  let kb = builder.level(None, 0).unwrap();
+  let texts: Vec<&str> = kb.rows.iter().flatten().map(|b| &*b.text).collect();
+  assert_eq!(texts, ["Inbox (3)", "Archive", "Sent"]);
+
+  // The disabled item's button does nothing
+  assert!(builder.decode(&kb.rows[1][0].callback).is_none());
+  assert!(builder.decode(&kb.rows[2][0].callback).is_some());
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,103 @@
+use menugen::recfiltiter::Event;
+use menugen::rule::{Parser, Rule};
+use menugen::{menu, menuitem};
+
+fn env(kind: &str, value: &str) -> bool {
+  matches!((kind, value), ("role", "admin") | ("flag", "beta"))
+}
+
+
+#[test]
+fn precedence() {
+  let rule = Rule::parse("role:admin && !flag:beta || env:staging").unwrap();
+  assert_eq!(
+    rule.to_string(),
+    "((role:admin && !flag:beta) || env:staging)"
+  );
+  assert!(!rule.eval(&env));
+
+  let rule = Rule::parse("role:admin && !(flag:beta || env:staging)").unwrap();
+  assert_eq!(
+    rule.to_string(),
+    "(role:admin && !(flag:beta || env:staging))"
+  );
+
+  let rule = Rule::parse(" role:admin&&flag:beta ").unwrap();
+  assert!(rule.eval(&env));
+
+  assert!(Rule::parse("true").unwrap().eval(&env));
+  assert!(!Rule::parse("!true || false").unwrap().eval(&env));
+}
+
+
+#[test]
+fn parse_errors() {
+  let err = Rule::parse("role:admin &&").unwrap_err();
+  assert_eq!(err.pos, 13);
+  assert_eq!(err.to_string(), "Unexpected end of rule at position 13");
+
+  let err = Rule::parse("(role:admin").unwrap_err();
+  assert_eq!(err.msg, "Expected ')'");
+
+  let err = Rule::parse("role:admin flag:beta").unwrap_err();
+  assert_eq!(err.pos, 11);
+
+  let err = Rule::parse("role:").unwrap_err();
+  assert_eq!(err.msg, "Expected value after 'role:'");
+
+  let err = Rule::parse("admin").unwrap_err();
+  assert_eq!(err.msg, "Expected ':' after 'admin'");
+
+  let mut parser = Parser::new();
+  parser.kind("role").kind("flag");
+  assert!(parser.parse("role:admin || flag:x").is_ok());
+  let src = "role:admin || rol:x";
+  let err = parser.parse(src).unwrap_err();
+  assert_eq!(err.msg, "Unknown kind 'rol' (expected one of: role, flag)");
+  assert_eq!(
+    err.explain(src),
+    "Unknown kind 'rol' (expected one of: role, flag) at position \
+     14\nrole:admin || rol:x\n              ^"
+  );
+}
+
+
+#[test]
+fn for_env() {
+  let mut mb = menu::Builder::<()>::new();
+
+  let mut mib = menuitem::Builder::new("a", "A");
+  mib.visible_if(Rule::parse("role:admin").unwrap());
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("a-1", "A1");
+  mib
+    .parent("a")
+    .visible_if(Rule::parse("!flag:beta").unwrap());
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("a-2", "A2");
+  mib.parent("a");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("b", "B");
+  mib.visible_if(Rule::parse("env:staging").unwrap());
+  mb.add(mib);
+
+  let menu = mb.build();
+
+  let mut out = Vec::new();
+  for ev in menu.for_env(&env) {
+    if let Event::MenuItem(mi) = ev {
+      out.push(mi.item.id());
+    }
+  }
+  assert_eq!(out, vec!["a", "a-2"]);
+
+  assert_eq!(
+    menu.get("b").unwrap().visibility().map(|r| r.to_string()),
+    Some("env:staging".to_string())
+  );
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
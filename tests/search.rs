@@ -0,0 +1,142 @@
+use menugen::menuitem::Kind;
+use menugen::search::Index;
+use menugen::{menu, menuitem};
+
+#[derive(Clone, Default)]
+struct Context {
+  aliases: Vec<String>,
+  admin: bool
+}
+
+fn build_menu() -> menu::Menu<Context> {
+  let mut mb = menu::Builder::<Context>::new();
+
+  let mib = menuitem::Builder::new_ctx(
+    "lib",
+    "Library",
+    Context {
+      aliases: vec!["books".to_string()],
+      admin: false
+    }
+  );
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb", "Knowledge Base");
+  mib.parent("lib");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("lib-kb-arch", "Archive");
+  mib.parent("lib-kb");
+  mb.add(mib);
+
+  let mib = menuitem::Builder::new_ctx(
+    "adm",
+    "Administration",
+    Context {
+      aliases: Vec::new(),
+      admin: true
+    }
+  );
+  mb.add(mib);
+
+  let mib = menuitem::Builder::new("arch", "Archive");
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("adm-kb", "Keyboard Bindings");
+  mib.parent("adm");
+  mb.add(mib);
+
+  mb.build()
+}
+
+
+#[test]
+fn title_match() {
+  let menu = build_menu();
+  let idx = Index::new(&menu);
+
+  // Equal matches are ranked by depth
+  let hits = idx.search("arch");
+  assert_eq!(hits.len(), 2);
+  assert_eq!(hits[0].item.id(), "arch");
+  assert_eq!(hits[0].path, "Archive");
+  assert_eq!(hits[1].item.id(), "lib-kb-arch");
+  assert_eq!(hits[1].path, "Library › Knowledge Base › Archive");
+  assert_eq!(hits[1].ranges, vec![0..4]);
+  assert!(hits[1].keyword.is_none());
+
+  assert!(idx.search("xyz").is_empty());
+  assert!(idx.search("  ").is_empty());
+}
+
+
+#[test]
+fn word_starts() {
+  let menu = build_menu();
+  let idx = Index::new(&menu);
+
+  // "kb" matches both "Knowledge Base" and "Keyboard Bindings" on word
+  // starts; the one with the shorter gap wins.
+  let hits = idx.search("kb");
+  let ids: Vec<&str> = hits.iter().map(|h| h.item.id()).collect();
+  assert_eq!(ids, vec!["adm-kb", "lib-kb"]);
+  assert_eq!(hits[0].ranges, vec![0..1, 9..10]);
+  assert_eq!(hits[1].ranges, vec![0..1, 10..11]);
+
+  // Word start matches beat mid-word matches
+  let hits = idx.search("ad");
+  assert_eq!(hits[0].item.id(), "adm");
+}
+
+
+#[test]
+fn keywords_and_filter() {
+  let menu = build_menu();
+  let mut idx = Index::with_keywords(&menu, |ctx| ctx.aliases.clone());
+  idx.separator(" / ");
+
+  let hits = idx.search("books");
+  assert_eq!(hits.len(), 1);
+  assert_eq!(hits[0].item.id(), "lib");
+  assert_eq!(hits[0].keyword.as_deref(), Some("books"));
+  assert!(hits[0].ranges.is_empty());
+
+  let hits = idx.search_filtered("kb", |mi| !mi.appctx().admin);
+  let ids: Vec<&str> = hits.iter().map(|h| h.item.id()).collect();
+  assert_eq!(ids, vec!["lib-kb"]);
+  assert_eq!(hits[0].path, "Library / Knowledge Base");
+}
+
+
+#[test]
+fn only_selectable() {
+  let mut mb = menu::Builder::<()>::new();
+  for (id, title, kind) in [
+    ("tools", "Tools", Kind::Group),
+    ("tools-hdr", "Tools and toys", Kind::Header),
+    ("tools-hammer", "Hammer", Kind::Link),
+    ("tools-sep", "Toolbox", Kind::Separator),
+    ("tools-saw", "Saw", Kind::Link),
+    ("tools-drill", "Toolbox drill", Kind::Link)
+  ] {
+    let mut mib = menuitem::Builder::new(id, title);
+    mib.kind(kind);
+    if id != "tools" {
+      mib.parent("tools");
+    }
+    if id == "tools-drill" {
+      mib.disabled(true);
+    }
+    mb.add(mib);
+  }
+  let menu = mb.build();
+  let idx = Index::new(&menu);
+
+  // Only the links match, but they keep the group in their paths
+  assert!(idx.search("tool").is_empty());
+  let hits = idx.search("ham");
+  assert_eq!(hits.len(), 1);
+  assert_eq!(hits[0].path, "Tools › Hammer");
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :
@@ -0,0 +1,159 @@
+use menugen::session::{Outcome, Session, State};
+use menugen::{menu, menuitem};
+
+fn build_menu() -> menu::Menu<()> {
+  let mut mb = menu::Builder::new();
+
+  let mut mib = menuitem::Builder::new("bal", "Balance");
+  mib.weight(10);
+  mb.add(mib);
+
+  let mut mib = menuitem::Builder::new("xfer", "Transfers");
+  mib.weight(20);
+  mb.add(mib);
+
+  for i in 1..=5 {
+    let mut mib =
+      menuitem::Builder::new(format!("xfer-{}", i), format!("Account {}", i));
+    mib.parent("xfer").weight(i);
+    mb.add(mib);
+  }
+
+  mb.build()
+}
+
+fn keys(sess: &Session<()>) -> Vec<String> {
+  sess.render().choices.into_iter().map(|ch| ch.key).collect()
+}
+
+
+#[test]
+fn root_level() {
+  let menu = build_menu();
+  let sess = Session::new(&menu);
+
+  let screen = sess.render();
+  assert_eq!(screen.title, None);
+  assert_eq!(screen.to_string(), "1. Balance\n2. Transfers");
+}
+
+
+#[test]
+fn select_leaf() {
+  let menu = build_menu();
+  let mut sess = Session::new(&menu);
+
+  match sess.input("1") {
+    Outcome::Selected(mi) => assert_eq!(mi.id(), "bal"),
+    _ => panic!("expected leaf selection")
+  }
+  assert_eq!(sess.state(), &State::default());
+
+  assert!(matches!(sess.input("7"), Outcome::Invalid));
+  assert!(matches!(sess.input("x"), Outcome::Invalid));
+  assert!(matches!(sess.input("0"), Outcome::Invalid));
+}
+
+
+#[test]
+fn descend_and_paginate() {
+  let menu = build_menu();
+  let mut sess = Session::new(&menu);
+  sess.page_size(2);
+
+  match sess.input("2") {
+    Outcome::Entered(mi) => assert_eq!(mi.id(), "xfer"),
+    _ => panic!("expected to enter submenu")
+  }
+
+  let screen = sess.render();
+  assert_eq!(screen.title.as_deref(), Some("Transfers"));
+  assert_eq!(
+    screen.to_string(),
+    "Transfers\n1. Account 1\n2. Account 2\n9. More\n0. Back\n00. Home"
+  );
+
+  assert!(matches!(sess.input("9"), Outcome::More));
+  assert!(matches!(sess.input("9"), Outcome::More));
+  assert_eq!(keys(&sess), vec!["1", "0", "00"]);
+  match sess.input("1") {
+    Outcome::Selected(mi) => assert_eq!(mi.id(), "xfer-5"),
+    _ => panic!("expected leaf selection")
+  }
+  assert!(matches!(sess.input("9"), Outcome::Invalid));
+
+  assert!(matches!(sess.input("*"), Outcome::Repeat));
+  assert!(matches!(sess.input("0"), Outcome::Back));
+  assert_eq!(sess.state().page, 1);
+
+  assert!(matches!(sess.input("00"), Outcome::Home));
+  assert_eq!(sess.state(), &State::default());
+}
+
+
+#[test]
+fn resume() {
+  let menu = build_menu();
+  let mut sess = Session::new(&menu);
+  sess.input("2");
+  let state = sess.into_state();
+  assert_eq!(state.path, vec!["xfer".to_string()]);
+
+  let sess = Session::resume(&menu, state);
+  assert_eq!(sess.current().map(|mi| mi.id()), Some("xfer"));
+
+  // Stale paths are truncated
+  let state = State {
+    path: vec!["xfer".to_string(), "gone".to_string()],
+    page: 3
+  };
+  let sess = Session::resume(&menu, state);
+  assert_eq!(sess.state().path, vec!["xfer".to_string()]);
+  assert_eq!(sess.state().page, 0);
+}
+
+
+#[test]
+fn resume_page_size() {
+  let menu = build_menu();
+
+  // The saved page is kept until the page size is known
+  let state = State {
+    path: vec!["xfer".to_string()],
+    page: 2
+  };
+  let mut sess = Session::resume(&menu, state.clone());
+  sess.page_size(2);
+  assert_eq!(
+    sess.render().to_string(),
+    "Transfers\n1. Account 5\n0. Back\n00. Home"
+  );
+  assert!(matches!(sess.input("0"), Outcome::Back));
+  assert_eq!(sess.state().page, 1);
+
+  // Out of range pages are clamped to the last page
+  let mut sess = Session::resume(&menu, state);
+  sess.page_size(4);
+  assert_eq!(
+    sess.render().to_string(),
+    "Transfers\n1. Account 5\n0. Back\n00. Home"
+  );
+  assert!(matches!(sess.input("*"), Outcome::Repeat));
+  assert_eq!(sess.state().page, 1);
+}
+
+
+#[cfg(all(feature = "serde", feature = "json"))]
+#[test]
+fn state_serde() {
+  let state = State {
+    path: vec!["xfer".to_string()],
+    page: 1
+  };
+  let s = serde_json::to_string(&state).unwrap();
+  assert_eq!(s, r#"{"path":["xfer"],"page":1}"#);
+  let state2: State = serde_json::from_str(&s).unwrap();
+  assert_eq!(state, state2);
+}
+
+// vim: set ft=rust et sw=2 ts=2 sts=2 cinoptions=2 tw=79 :